@@ -0,0 +1,113 @@
+//! # Integration with actix
+//!
+//! This module provides the [`WeruEngine`](WeruEngine) extractor, which lets
+//! handlers take the cache engine registered in the application data as an
+//! argument:
+//!
+//! ```no_run
+//! use actix_web::{web, App, HttpResponse};
+//! use weru_cache::actix::WeruEngine;
+//! use weru_cache::{CacheProducer, Engine};
+//!
+//! async fn handler(engine: WeruEngine) -> HttpResponse {
+//!     match engine.cache::<String, String>("greetings").await {
+//!         Ok(_) => HttpResponse::Ok().finish(),
+//!         Err(_) => HttpResponse::InternalServerError().finish(),
+//!     }
+//! }
+//!
+//! fn app(engine: Engine) {
+//!     App::new()
+//!         .app_data(web::Data::new(engine))
+//!         .route("/", web::get().to(handler));
+//! }
+//! ```
+
+use std::future::{ready, Ready};
+use std::ops::Deref;
+
+use actix_web::dev::Payload;
+use actix_web::{web, FromRequest, HttpRequest, ResponseError};
+
+use crate::Engine;
+
+/// An extractor for the cache engine.
+///
+/// The engine must be registered in the application data as a
+/// `web::Data<Engine>`; otherwise extraction fails with
+/// [`NotRegistered`](NotRegistered).
+#[derive(Clone)]
+pub struct WeruEngine(web::Data<Engine>);
+
+impl Deref for WeruEngine {
+    type Target = Engine;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.as_ref()
+    }
+}
+
+impl FromRequest for WeruEngine {
+    type Error = NotRegistered;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(
+            req.app_data::<web::Data<Engine>>()
+                .cloned()
+                .map(WeruEngine)
+                .ok_or(NotRegistered),
+        )
+    }
+}
+
+/// The error returned when no cache engine is registered.
+///
+/// This results in an _Internal Server Error_ response.
+#[derive(Debug, thiserror::Error)]
+#[error("no cache engine is registered in the application data")]
+pub struct NotRegistered;
+
+impl ResponseError for NotRegistered {}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::StatusCode;
+    use actix_web::test::TestRequest;
+
+    use super::*;
+
+    #[cfg(feature = "local")]
+    #[actix_rt::test]
+    async fn extract_registered() {
+        // Arrange
+        let engine = crate::Configuration::Local(Default::default())
+            .engine()
+            .await
+            .unwrap();
+        let (req, mut payload) = TestRequest::default()
+            .app_data(web::Data::new(engine))
+            .to_http_parts();
+
+        // Act
+        let actual = WeruEngine::from_request(&req, &mut payload).await;
+
+        // Assert
+        assert!(actual.is_ok());
+    }
+
+    #[actix_rt::test]
+    async fn extract_not_registered() {
+        // Arrange
+        let (req, mut payload) = TestRequest::default().to_http_parts();
+
+        // Act
+        let actual = WeruEngine::from_request(&req, &mut payload).await;
+
+        // Assert
+        assert_eq!(
+            Some(StatusCode::INTERNAL_SERVER_ERROR),
+            actual.err().map(|e| e.status_code()),
+        );
+    }
+}
@@ -0,0 +1,298 @@
+//! # Value compression
+//!
+//! Cache values may optionally be compressed before being stored. Only values
+//! at least as large as a configurable threshold are compressed, so small
+//! values are not penalised.
+//!
+//! Every algorithm is enabled by a feature of the same name. None of them is
+//! enabled by default; `zstd` in particular builds a native library.
+//!
+//! Compressed values are prefixed by a short header identifying the
+//! algorithm. The header starts with a byte that is never the first byte of
+//! an encoded value, so values stored without compression remain readable
+//! when compression is enabled.
+#![cfg_attr(
+    not(any(feature = "gzip", feature = "zstd")),
+    allow(unreachable_code, unused)
+)]
+
+use std::borrow::Cow;
+use std::io::{Read, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::Error;
+
+/// The magic bytes starting the header of a compressed value.
+///
+/// `0xFF` is the _CBOR_ break marker, which never starts an encoded value.
+const MAGIC: [u8; 2] = [0xFF, b'w'];
+
+/// The length of the header of a compressed value.
+const HEADER_SIZE: usize = MAGIC.len() + 1;
+
+/// A compression algorithm.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Compression {
+    /// _gzip_ compression.
+    #[cfg(feature = "gzip")]
+    Gzip,
+
+    /// _Zstandard_ compression.
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+impl Compression {
+    /// The default minimum size of values to compress.
+    pub const DEFAULT_THRESHOLD: usize = 1024;
+
+    /// The default minimum size of values to compress.
+    ///
+    /// This is used as default value when deserialising configurations.
+    pub fn default_threshold() -> usize {
+        Self::DEFAULT_THRESHOLD
+    }
+
+    /// The identifier of this algorithm in value headers.
+    fn id(self) -> u8 {
+        match self {
+            #[cfg(feature = "gzip")]
+            Self::Gzip => 1,
+            #[cfg(feature = "zstd")]
+            Self::Zstd => 2,
+        }
+    }
+
+    /// Looks up an algorithm from its identifier in value headers.
+    ///
+    /// # Arguments
+    /// *  `id` - The identifier.
+    fn from_id(id: u8) -> Option<Self> {
+        match id {
+            #[cfg(feature = "gzip")]
+            1 => Some(Self::Gzip),
+            #[cfg(feature = "zstd")]
+            2 => Some(Self::Zstd),
+            _ => None,
+        }
+    }
+
+    /// Compresses data using this algorithm.
+    ///
+    /// # Arguments
+    /// *  `data` - The data to compress.
+    fn compress(self, data: &[u8]) -> Result<Vec<u8>, Error> {
+        let mut result = Vec::with_capacity(HEADER_SIZE + data.len() / 2);
+        result.extend(MAGIC);
+        result.push(self.id());
+        match self {
+            #[cfg(feature = "gzip")]
+            Self::Gzip => {
+                let mut encoder = flate2::write::GzEncoder::new(
+                    result,
+                    flate2::Compression::default(),
+                );
+                encoder.write_all(data).map_err(Self::error)?;
+                encoder.finish().map_err(Self::error)
+            }
+            #[cfg(feature = "zstd")]
+            Self::Zstd => {
+                let mut encoder =
+                    zstd::Encoder::new(result, 0).map_err(Self::error)?;
+                encoder.write_all(data).map_err(Self::error)?;
+                encoder.finish().map_err(Self::error)
+            }
+        }
+    }
+
+    /// Decompresses data using this algorithm.
+    ///
+    /// # Arguments
+    /// *  `data` - The compressed data, without header.
+    fn decompress(self, data: &[u8]) -> Result<Vec<u8>, Error> {
+        let mut result = Vec::new();
+        match self {
+            #[cfg(feature = "gzip")]
+            Self::Gzip => flate2::read::GzDecoder::new(data)
+                .read_to_end(&mut result)
+                .map_err(Self::error)?,
+            #[cfg(feature = "zstd")]
+            Self::Zstd => zstd::Decoder::new(data)
+                .and_then(|mut decoder| decoder.read_to_end(&mut result))
+                .map_err(Self::error)?,
+        };
+        Ok(result)
+    }
+
+    /// Converts an IO error to a crate error.
+    ///
+    /// # Arguments
+    /// *  `source` - The source error.
+    fn error(source: std::io::Error) -> Error {
+        Error::Encoding(source.to_string())
+    }
+}
+
+/// Compresses and decompresses values stored in a cache.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Compressor {
+    /// The compression algorithm, if any.
+    compression: Option<Compression>,
+
+    /// The minimum size of values to compress.
+    threshold: usize,
+}
+
+impl Compressor {
+    /// Creates a new compressor.
+    ///
+    /// # Arguments
+    /// *  `compression` - The compression algorithm, if any.
+    /// *  `threshold` - The minimum size of values to compress.
+    pub fn new(compression: Option<Compression>, threshold: usize) -> Self {
+        Self {
+            compression,
+            threshold,
+        }
+    }
+
+    /// Compresses an encoded value, if it is large enough and compression is
+    /// enabled.
+    ///
+    /// # Arguments
+    /// *  `data` - The encoded value.
+    pub fn compress(&self, data: Vec<u8>) -> Result<Vec<u8>, Error> {
+        match self.compression {
+            Some(compression) if data.len() >= self.threshold => {
+                compression.compress(&data)
+            }
+            _ => Ok(data),
+        }
+    }
+
+    /// Decompresses an encoded value, if it has a compression header.
+    ///
+    /// Values without a header are returned as is, regardless of whether
+    /// compression is enabled.
+    ///
+    /// # Arguments
+    /// *  `data` - The possibly compressed value.
+    pub fn decompress<'a>(
+        &self,
+        data: &'a [u8],
+    ) -> Result<Cow<'a, [u8]>, Error> {
+        if data.len() >= HEADER_SIZE && data.starts_with(&MAGIC) {
+            let id = data[MAGIC.len()];
+            Compression::from_id(id)
+                .ok_or_else(|| {
+                    Error::Encoding(format!("unknown compression: {}", id))
+                })?
+                .decompress(&data[HEADER_SIZE..])
+                .map(Cow::Owned)
+        } else {
+            Ok(Cow::Borrowed(data))
+        }
+    }
+}
+
+#[cfg(all(test, any(feature = "gzip", feature = "zstd")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn below_threshold() {
+        // Arrange
+        let compressor = Compressor::new(Some(algorithm()), 16);
+        let value = b"small".to_vec();
+
+        // Act
+        let actual = compressor.compress(value.clone()).unwrap();
+
+        // Assert
+        assert_eq!(value, actual);
+    }
+
+    #[test]
+    fn disabled() {
+        // Arrange
+        let compressor = Compressor::new(None, 0);
+        let value = large();
+
+        // Act
+        let actual = compressor.compress(value.clone()).unwrap();
+
+        // Assert
+        assert_eq!(value, actual);
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn roundtrip_gzip() {
+        roundtrip(Compression::Gzip);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn roundtrip_zstd() {
+        roundtrip(Compression::Zstd);
+    }
+
+    #[test]
+    fn decompress_uncompressed() {
+        // Arrange
+        let compressor = Compressor::new(Some(algorithm()), 0);
+        let value = large();
+
+        // Act
+        let actual = compressor.decompress(&value).unwrap();
+
+        // Assert
+        assert_eq!(value, actual.as_ref());
+    }
+
+    #[test]
+    fn decompress_unknown() {
+        // Arrange
+        let compressor = Compressor::new(Some(algorithm()), 0);
+        let value = [MAGIC[0], MAGIC[1], 0xFF, 0, 1, 2];
+
+        // Act
+        let actual = compressor.decompress(&value);
+
+        // Assert
+        assert!(actual.is_err());
+    }
+
+    /// Compresses and decompresses a large value.
+    ///
+    /// # Arguments
+    /// *  `compression` - The compression algorithm to test.
+    fn roundtrip(compression: Compression) {
+        // Arrange
+        let compressor = Compressor::new(Some(compression), 16);
+        let value = large();
+
+        // Act
+        let compressed = compressor.compress(value.clone()).unwrap();
+        let actual = compressor.decompress(&compressed).unwrap();
+
+        // Assert
+        assert!(compressed.len() < value.len());
+        assert_eq!(value, actual.as_ref());
+    }
+
+    /// Any available compression algorithm.
+    fn algorithm() -> Compression {
+        #[cfg(feature = "gzip")]
+        return Compression::Gzip;
+        #[cfg(all(not(feature = "gzip"), feature = "zstd"))]
+        return Compression::Zstd;
+    }
+
+    /// A large, compressible value.
+    fn large() -> Vec<u8> {
+        cbor4ii::serde::to_vec(Vec::new(), &"value ".repeat(256)).unwrap()
+    }
+}
@@ -5,39 +5,234 @@
 //! process.
 
 use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
 use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 
-use crate::{configuration, CacheProducer, Error, Key, Name, Value};
+use crate::compression::Compressor;
+use crate::{
+    configuration, CacheConfig, CacheProducer, Compression, Error, Key, Name,
+    Updater, Value,
+};
 
 /// The configuration for a local cache.
+///
+/// All fields are optional, so configurations written before there were any
+/// fields are still valid.
 #[derive(Clone, Debug, Deserialize, Serialize)]
-pub struct Configuration;
+#[serde(default)]
+pub struct Configuration {
+    /// The compression to apply to large values.
+    pub compression: Option<Compression>,
+
+    /// The minimum size in bytes of values to compress.
+    pub compression_threshold: usize,
+
+    /// The namespace of the storage.
+    ///
+    /// All engines with the same namespace in a process share storage. If
+    /// this is not set, every engine has its own storage.
+    pub namespace: Option<String>,
+}
 
 impl Configuration {
     /// Constructs a cache engine from this configuration.
     pub async fn engine(&self) -> Result<crate::Engine, configuration::Error> {
+        let data = if let Some(namespace) = &self.namespace {
+            Arc::clone(
+                NAMESPACES
+                    .get_or_init(Default::default)
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .entry(namespace.clone())
+                    .or_default(),
+            )
+        } else {
+            Buffers::default()
+        };
         Ok(crate::Engine::Local(Engine {
-            data: Arc::new(Mutex::new(HashMap::new())),
+            data,
+            on_evict: None,
+            compressor: Compressor::new(
+                self.compression,
+                self.compression_threshold,
+            ),
         }))
     }
 }
 
+impl Configuration {
+    /// Verifies this configuration, and returns all problems found.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        match &self.namespace {
+            Some(namespace) if namespace.is_empty() => {
+                Err(vec!["the namespace must not be empty".into()])
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Default for Configuration {
+    fn default() -> Self {
+        Self {
+            compression: None,
+            compression_threshold: Compression::DEFAULT_THRESHOLD,
+            namespace: None,
+        }
+    }
+}
+
+/// The buffers of serialised data, by cache name.
+type Buffers = Arc<Mutex<HashMap<String, Arc<Mutex<Buffer>>>>>;
+
+/// The buffers shared by all engines in a namespace.
+static NAMESPACES: OnceLock<Mutex<HashMap<String, Buffers>>> = OnceLock::new();
+
+/// A callback invoked with the serialised key of every expired value removed
+/// from a cache.
+pub type EvictionCallback = Arc<dyn Fn(&[u8]) + Send + Sync>;
+
 /// An engine creating local cache instances.
-#[derive(Debug)]
 pub struct Engine {
     /// The buffers of serialised data.
-    data: Arc<Mutex<HashMap<String, Arc<Mutex<Buffer>>>>>,
+    data: Buffers,
+
+    /// The callback invoked when expired values are removed.
+    on_evict: Option<EvictionCallback>,
+
+    /// The compressor applied to values.
+    compressor: Compressor,
+}
+
+impl ::std::fmt::Debug for Engine {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        f.debug_struct("Engine")
+            .field("data", &self.data)
+            .field("on_evict", &self.on_evict.is_some())
+            .field("compressor", &self.compressor)
+            .finish()
+    }
+}
+
+impl Engine {
+    /// Registers a callback invoked when expired values are removed from
+    /// caches created by this engine.
+    ///
+    /// Expired values are removed lazily when accessed. The callback is
+    /// invoked while the cache is locked, so it must not access the cache.
+    ///
+    /// # Arguments
+    /// *  `callback` - The callback, receiving the serialised key.
+    pub fn with_eviction_callback(self, callback: EvictionCallback) -> Self {
+        Self {
+            on_evict: Some(callback),
+            ..self
+        }
+    }
+
+    /// Verifies that this engine is usable.
+    ///
+    /// A local engine is always available.
+    pub async fn health_check(&self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Atomically moves a value from one cache to another.
+    ///
+    /// Both caches are locked for the duration of the move.
+    ///
+    /// # Arguments
+    /// *  `from` - The name of the source cache.
+    /// *  `to` - The name of the destination cache.
+    /// *  `key` - The key of the value to move.
+    /// *  `ttl` - The time-to-live for the value in the destination cache.
+    pub async fn move_entry<K, V>(
+        &self,
+        from: impl Name,
+        to: impl Name,
+        key: &K,
+        ttl: Duration,
+    ) -> Result<Option<V>, Error>
+    where
+        K: Key,
+        V: Value,
+    {
+        let key = cbor4ii::serde::to_vec(Vec::new(), key)?;
+        let (source, destination) = {
+            let mut data = self.data.lock()?;
+            let mut buffer = |name: &str| {
+                Arc::clone(
+                    data.entry(name.to_string())
+                        .or_insert_with(|| Arc::new(Mutex::new(Buffer::new()))),
+                )
+            };
+            (buffer(from.as_ref()), buffer(to.as_ref()))
+        };
+
+        if Arc::ptr_eq(&source, &destination) {
+            let mut source = source.lock()?;
+            let result = self.transfer(&mut source, None, &key, ttl);
+            source.notify(self.on_evict.as_ref());
+            result
+        } else {
+            // Lock in a consistent order to not deadlock with a concurrent
+            // move in the opposite direction
+            let (mut source, mut destination) = if from.as_ref() < to.as_ref() {
+                let source = source.lock()?;
+                (source, destination.lock()?)
+            } else {
+                let destination = destination.lock()?;
+                (source.lock()?, destination)
+            };
+            let result =
+                self.transfer(&mut source, Some(&mut destination), &key, ttl);
+            source.notify(self.on_evict.as_ref());
+            destination.notify(self.on_evict.as_ref());
+            result
+        }
+    }
+
+    /// Moves a serialised value between locked buffers.
+    ///
+    /// # Arguments
+    /// *  `source` - The source buffer.
+    /// *  `destination` - The destination buffer, or `None` if it is the
+    ///    source buffer.
+    /// *  `key` - The serialised key.
+    /// *  `ttl` - The time-to-live for the value in the destination buffer.
+    fn transfer<V>(
+        &self,
+        source: &mut Buffer,
+        destination: Option<&mut Buffer>,
+        key: &[u8],
+        ttl: Duration,
+    ) -> Result<Option<V>, Error>
+    where
+        V: Value,
+    {
+        let Some(bytes) = source.get(key) else {
+            return Ok(None);
+        };
+        let value =
+            cbor4ii::serde::from_slice(&self.compressor.decompress(&bytes)?)?;
+
+        source.remove(key);
+        destination
+            .unwrap_or(source)
+            .put(key.to_vec(), bytes, expiry(ttl));
+        Ok(Some(value))
+    }
 }
 
 #[async_trait]
 impl CacheProducer for Engine {
-    async fn cache<K, V>(
+    async fn cache_with_config<K, V>(
         &self,
         name: impl Name,
+        config: CacheConfig,
     ) -> Result<Box<dyn crate::Cache<K, V>>, Error>
     where
         K: Key,
@@ -45,10 +240,16 @@ impl CacheProducer for Engine {
     {
         let mut data = self.data.lock()?;
 
-        Ok(Box::new(Cache::new(Arc::clone(
-            data.entry(name.as_ref().to_string())
-                .or_insert_with(|| Arc::new(Mutex::new(Buffer::new()))),
-        ))))
+        Ok(Box::new(Cache::new(
+            name.as_ref().to_string(),
+            self.compressor,
+            config,
+            self.on_evict.clone(),
+            Arc::clone(
+                data.entry(name.as_ref().to_string())
+                    .or_insert_with(|| Arc::new(Mutex::new(Buffer::new()))),
+            ),
+        )))
     }
 }
 
@@ -56,6 +257,19 @@ impl CacheProducer for Engine {
 ///
 /// Values are not automatically expired, only removed upon access.
 pub struct Cache<K, V> {
+    /// The name of the cache.
+    #[cfg_attr(not(feature = "trace"), allow(unused))]
+    name: String,
+
+    /// The compressor applied to values.
+    compressor: Compressor,
+
+    /// The options for this cache.
+    config: CacheConfig,
+
+    /// The callback invoked when expired values are removed.
+    on_evict: Option<EvictionCallback>,
+
     /// The data.
     data: Arc<Mutex<Buffer>>,
 
@@ -66,13 +280,35 @@ impl<K, V> Cache<K, V> {
     /// Creates a new cache with a backing buffer.
     ///
     /// # Arguments
+    /// *  `name` - The name of the cache.
+    /// *  `compressor` - The compressor applied to values.
+    /// *  `config` - The options for this cache.
+    /// *  `on_evict` - The callback invoked when expired values are removed.
     /// *  `data` The backing buffer.
-    fn new(data: Arc<Mutex<Buffer>>) -> Self {
+    fn new(
+        name: String,
+        compressor: Compressor,
+        config: CacheConfig,
+        on_evict: Option<EvictionCallback>,
+        data: Arc<Mutex<Buffer>>,
+    ) -> Self {
         Self {
+            name,
+            compressor,
+            config,
+            on_evict,
             data,
             _m: ::std::marker::PhantomData,
         }
     }
+
+    /// Invokes the eviction callback for all values evicted from a buffer.
+    ///
+    /// # Arguments
+    /// *  `data` - The buffer from which values were evicted.
+    fn notify(&self, data: &mut Buffer) {
+        data.notify(self.on_evict.as_ref());
+    }
 }
 
 impl<K, V> Cache<K, V>
@@ -88,12 +324,39 @@ where
         Ok(cbor4ii::serde::to_vec(Vec::new(), key)?)
     }
 
+    /// Converts a byte vector to a key.
+    ///
+    /// # Arguments
+    /// *  `key` - The key to convert.
+    fn key_deserialize(&self, key: &[u8]) -> Result<K, Error> {
+        Ok(cbor4ii::serde::from_slice(key)?)
+    }
+
+    /// Describes a key for logging.
+    ///
+    /// # Arguments
+    /// *  `key` - The key to describe.
+    #[cfg(feature = "trace")]
+    fn describe(&self, key: &K) -> String {
+        self.key_serialize(key)
+            .map(|key| {
+                format!(
+                    "{}{}{}",
+                    self.name,
+                    super::DEFAULT_SEPARATOR,
+                    key.escape_ascii(),
+                )
+            })
+            .unwrap_or_else(|_| self.name.clone())
+    }
+
     /// Converts a value to a byte vector.
     ///
     /// # Arguments
     /// *  `value` - The value to convert.
     fn value_serialize(&self, value: &V) -> Result<Vec<u8>, Error> {
-        Ok(cbor4ii::serde::to_vec(Vec::new(), value)?)
+        self.compressor
+            .compress(cbor4ii::serde::to_vec(Vec::new(), value)?)
     }
 
     /// Converts a byte vector to a value.
@@ -104,7 +367,9 @@ where
     where
         T: for<'a> Deserialize<'a>,
     {
-        Ok(cbor4ii::serde::from_slice(value)?)
+        Ok(cbor4ii::serde::from_slice(
+            &self.compressor.decompress(value)?,
+        )?)
     }
 }
 
@@ -115,52 +380,191 @@ where
     V: Value,
 {
     async fn get(&self, key: &K) -> Result<Option<V>, Error> {
-        let mut data = self.data.lock()?;
-        let key = self.key_serialize(key)?;
+        traced!("local", "get", self.describe(key), {
+            let mut data = self.data.lock()?;
+            let key = self.key_serialize(key)?;
+
+            let value = data.get(&key);
+            self.notify(&mut data);
+            value
+                .map(|bytes| self.value_deserialize(&bytes))
+                .transpose()
+        })
+    }
+
+    async fn touch(&self, key: &K, ttl: Duration) -> Result<Option<V>, Error> {
+        traced!("local", "touch", self.describe(key), {
+            let mut data = self.data.lock()?;
+            let key = self.key_serialize(key)?;
+
+            let value = data.touch(&key, expiry(ttl));
+            self.notify(&mut data);
+            value
+                .map(|bytes| self.value_deserialize(&bytes))
+                .transpose()
+        })
+    }
+
+    async fn persist(&self, key: &K) -> Result<bool, Error> {
+        traced!("local", "persist", self.describe(key), {
+            let mut data = self.data.lock()?;
+            let key = self.key_serialize(key)?;
 
-        data.get(&key)
-            .map(|bytes| Ok(self.value_deserialize(&bytes)?))
-            .transpose()
+            let exists = data.set_expiry(&key, None);
+            self.notify(&mut data);
+            Ok(exists)
+        })
+    }
+
+    async fn expire(&self, key: &K, ttl: Duration) -> Result<bool, Error> {
+        traced!("local", "expire", self.describe(key), {
+            let mut data = self.data.lock()?;
+            let key = self.key_serialize(key)?;
+
+            let exists = data.set_expiry(&key, expiry(ttl));
+            self.notify(&mut data);
+            Ok(exists)
+        })
     }
 
     async fn pop(&self, key: &K) -> Result<Option<V>, Error> {
-        let mut data = self.data.lock()?;
-        let key = self.key_serialize(key)?;
+        traced!("local", "pop", self.describe(key), {
+            let mut data = self.data.lock()?;
+            let key = self.key_serialize(key)?;
 
-        data.remove(&key)
-            .map(|d| Ok(self.value_deserialize(&d.value)?))
-            .transpose()
+            let value = data.remove(&key);
+            self.notify(&mut data);
+            value.map(|d| self.value_deserialize(&d.value)).transpose()
+        })
     }
 
     async fn put(&self, key: K, value: V, ttl: Duration) -> Result<(), Error> {
-        let mut data = self.data.lock()?;
-        let key = self.key_serialize(&key)?;
-        let value = self.value_serialize(&value)?;
+        traced!("local", "put", self.describe(&key), {
+            let mut data = self.data.lock()?;
+            let key = self.key_serialize(&key)?;
+            let value = self.value_serialize(&value)?;
 
-        data.put(key, value, Instant::now() + ttl);
-        Ok(())
+            data.put(key, value, expiry(ttl));
+            self.notify(&mut data);
+            Ok(())
+        })
     }
 
-    async fn replace(
+    async fn get_swr(&self, key: &K) -> Result<Option<(V, bool)>, Error> {
+        traced!("local", "get_swr", self.describe(key), {
+            let mut data = self.data.lock()?;
+            let key = self.key_serialize(key)?;
+
+            let value = data.get_swr(&key);
+            self.notify(&mut data);
+            value
+                .map(|(bytes, stale)| {
+                    self.value_deserialize(&bytes).map(|value| (value, stale))
+                })
+                .transpose()
+        })
+    }
+
+    async fn put_swr(
         &self,
         key: K,
         value: V,
-        ttl: Option<Duration>,
-    ) -> Result<Option<V>, Error> {
-        let mut data = self.data.lock()?;
-        let key = self.key_serialize(&key)?;
-        let value = self.value_serialize(&value)?;
+        fresh: Duration,
+        stale: Duration,
+    ) -> Result<(), Error> {
+        traced!("local", "put_swr", self.describe(&key), {
+            let mut data = self.data.lock()?;
+            let key = self.key_serialize(&key)?;
+            let value = self.value_serialize(&value)?;
 
-        if let Some(d) = data.remove(&key) {
-            data.put(
+            data.put_swr(
                 key,
                 value,
-                ttl.map(|ttl| Instant::now() + ttl).unwrap_or(d.expiry),
+                expiry(fresh.saturating_add(stale)),
+                expiry(fresh),
             );
-            Some(self.value_deserialize(&d.value)).transpose()
-        } else {
-            Ok(None)
-        }
+            self.notify(&mut data);
+            Ok(())
+        })
+    }
+
+    async fn replace(
+        &self,
+        key: K,
+        value: V,
+        ttl: Option<Duration>,
+    ) -> Result<Option<V>, Error> {
+        traced!("local", "replace", self.describe(&key), {
+            let mut data = self.data.lock()?;
+            let key = self.key_serialize(&key)?;
+            let value = self.value_serialize(&value)?;
+
+            let previous = data.remove(&key);
+            if let Some(d) = &previous {
+                data.put(key, value, ttl.map(expiry).unwrap_or(d.expiry));
+            }
+            self.notify(&mut data);
+            previous
+                .map(|d| self.value_deserialize(&d.value))
+                .transpose()
+        })
+    }
+
+    async fn update(
+        &self,
+        key: &K,
+        f: &mut Updater<'_, V>,
+    ) -> Result<Option<V>, Error> {
+        traced!("local", "update", self.describe(key), {
+            let mut data = self.data.lock()?;
+            let key = self.key_serialize(key)?;
+
+            let current = data.get(&key);
+            self.notify(&mut data);
+            let current = current
+                .map(|bytes| self.value_deserialize(&bytes))
+                .transpose()?;
+            let Some((value, ttl)) = f(current.as_ref()) else {
+                return Ok(current);
+            };
+            let expires = match ttl {
+                Some(ttl) => expiry(ttl),
+                None => data.data.get(&key).and_then(|d| d.expiry),
+            };
+            data.put(key, self.value_serialize(&value)?, expires);
+            Ok(Some(value))
+        })
+    }
+
+    async fn keys(&self) -> Result<Vec<K>, Error> {
+        traced!("local", "keys", self.name, {
+            let mut data = self.data.lock()?;
+
+            let keys = data.keys();
+            self.notify(&mut data);
+            keys.iter().map(|key| self.key_deserialize(key)).collect()
+        })
+    }
+
+    async fn scan(&self, pattern: &str) -> Result<Vec<K>, Error> {
+        traced!("local", "scan", self.name, {
+            let mut data = self.data.lock()?;
+
+            let keys = data.keys();
+            self.notify(&mut data);
+            keys.iter()
+                .filter(|key| glob(pattern.as_bytes(), key))
+                .map(|key| self.key_deserialize(key))
+                .collect()
+        })
+    }
+
+    fn key_bytes(&self, key: &K) -> Result<Vec<u8>, Error> {
+        self.key_serialize(key)
+    }
+
+    fn config(&self) -> &CacheConfig {
+        &self.config
     }
 }
 
@@ -169,6 +573,9 @@ where
 struct Buffer {
     /// The data.
     data: HashMap<Vec<u8>, Data>,
+
+    /// The keys of expired values removed since last drained.
+    evicted: Vec<Vec<u8>>,
 }
 
 impl Buffer {
@@ -176,6 +583,29 @@ impl Buffer {
     pub fn new() -> Self {
         Buffer {
             data: HashMap::new(),
+            evicted: Vec::new(),
+        }
+    }
+
+    /// Invokes an eviction callback for all values evicted since last
+    /// called.
+    ///
+    /// # Arguments
+    /// *  `on_evict` - The callback, if any.
+    fn notify(&mut self, on_evict: Option<&EvictionCallback>) {
+        let evicted = self.evicted.drain(..);
+        if let Some(on_evict) = on_evict {
+            evicted.for_each(|key| on_evict(&key));
+        }
+    }
+
+    /// Removes an expired value from the buffer, and records its key.
+    ///
+    /// # Arguments
+    /// *  `key` - The key to evict.
+    fn evict(&mut self, key: &[u8]) {
+        if let Some((key, _)) = self.data.remove_entry(key) {
+            self.evicted.push(key);
         }
     }
 
@@ -186,14 +616,24 @@ impl Buffer {
     /// # Arguments
     /// *  `key` - The key to read.
     pub fn get(&mut self, key: &[u8]) -> Option<Vec<u8>> {
+        self.get_swr(key).map(|(value, _)| value)
+    }
+
+    /// Reads a value from the buffer, and whether it is stale.
+    ///
+    /// If the value has expired, it is removed and nothing is returned.
+    ///
+    /// # Arguments
+    /// *  `key` - The key to read.
+    pub fn get_swr(&mut self, key: &[u8]) -> Option<(Vec<u8>, bool)> {
         let now = Instant::now();
 
         match self.data.get(key) {
             Some(w) => {
                 if w.live(now) {
-                    Some(w.clone_inner())
+                    Some((w.clone_inner(), w.stale(now)))
                 } else {
-                    self.data.remove(key);
+                    self.evict(key);
                     None
                 }
             }
@@ -201,20 +641,52 @@ impl Buffer {
         }
     }
 
-    /// Removes a value from the buffer and returnes it.
+    /// Updates the expiry of a value without reading it.
+    ///
+    /// If the value has expired, it is removed. This returns whether a live
+    /// value exists.
+    ///
+    /// # Arguments
+    /// *  `key` - The key of the value.
+    /// *  `expiry` - The new instant of expiry, or `None` to never expire.
+    pub fn set_expiry(&mut self, key: &[u8], expiry: Option<Instant>) -> bool {
+        let now = Instant::now();
+
+        match self.data.get_mut(key) {
+            Some(w) => {
+                if w.live(now) {
+                    w.expiry = expiry;
+                    true
+                } else {
+                    self.evict(key);
+                    false
+                }
+            }
+            None => false,
+        }
+    }
+
+    /// Reads a value from the buffer and updates its expiry.
     ///
     /// If the value has expired, it is removed and nothing is returned.
     ///
     /// # Arguments
-    /// *  `key` - The key to remove.
-    pub fn remove(&mut self, key: &[u8]) -> Option<Data> {
+    /// *  `key` - The key to read.
+    /// *  `expiry` - The new instant of expiry, or `None` to never expire.
+    pub fn touch(
+        &mut self,
+        key: &[u8],
+        expiry: Option<Instant>,
+    ) -> Option<Vec<u8>> {
         let now = Instant::now();
 
-        match self.data.remove(key) {
+        match self.data.get_mut(key) {
             Some(w) => {
                 if w.live(now) {
-                    Some(w)
+                    w.expiry = expiry;
+                    Some(w.clone_inner())
                 } else {
+                    self.evict(key);
                     None
                 }
             }
@@ -222,24 +694,162 @@ impl Buffer {
         }
     }
 
+    /// Removes a value from the buffer and returnes it.
+    ///
+    /// If the value has expired, it is removed and nothing is returned.
+    ///
+    /// # Arguments
+    /// *  `key` - The key to remove.
+    pub fn remove(&mut self, key: &[u8]) -> Option<Data> {
+        let now = Instant::now();
+
+        match self.data.remove_entry(key) {
+            Some((_, w)) if w.live(now) => Some(w),
+            Some((key, _)) => {
+                self.evicted.push(key);
+                None
+            }
+            None => None,
+        }
+    }
+
     /// Writes a value to the buffer.
     ///
     /// # Arguments
     /// *  `key` - The key to write.
     /// *  `value` - The value to write.
-    /// *  `ttl` - The time-to-live.
-    pub fn put(&mut self, key: Vec<u8>, value: Vec<u8>, expiry: Instant) {
-        self.data.insert(key, Data::new(value, expiry));
+    /// *  `expiry` - The instant of expiry, or `None` to never expire.
+    pub fn put(
+        &mut self,
+        key: Vec<u8>,
+        value: Vec<u8>,
+        expiry: Option<Instant>,
+    ) {
+        self.put_swr(key, value, expiry, None)
+    }
+
+    /// Writes a value that becomes stale before it expires to the buffer.
+    ///
+    /// # Arguments
+    /// *  `key` - The key to write.
+    /// *  `value` - The value to write.
+    /// *  `expiry` - The instant of expiry, or `None` to never expire.
+    /// *  `stale` - The instant after which the value is stale, or `None` to
+    ///    never become stale.
+    pub fn put_swr(
+        &mut self,
+        key: Vec<u8>,
+        value: Vec<u8>,
+        expiry: Option<Instant>,
+        stale: Option<Instant>,
+    ) {
+        let now = Instant::now();
+
+        if let Some(w) = self
+            .data
+            .insert(key.clone(), Data::new(value, expiry).with_stale(stale))
+        {
+            if !w.live(now) {
+                self.evicted.push(key);
+            }
+        }
+    }
+
+    /// Lists the keys of all values in the buffer.
+    ///
+    /// Expired values are removed.
+    pub fn keys(&mut self) -> Vec<Vec<u8>> {
+        let now = Instant::now();
+
+        let evicted = &mut self.evicted;
+        self.data.retain(|key, w| {
+            let live = w.live(now);
+            if !live {
+                evicted.push(key.clone());
+            }
+            live
+        });
+        self.data.keys().cloned().collect()
+    }
+}
+
+/// Calculates the instant of expiry for a time-to-live.
+///
+/// A time-to-live too large to represent, such as
+/// [`NEVER_EXPIRES`](crate::NEVER_EXPIRES), yields `None`, meaning that the
+/// value never expires.
+///
+/// # Arguments
+/// *  `ttl` - The time-to-live.
+fn expiry(ttl: Duration) -> Option<Instant> {
+    Instant::now().checked_add(ttl)
+}
+
+/// Matches a byte string against a glob pattern.
+///
+/// The syntax is that of Redis: `*` matches any sequence, `?` matches a single
+/// byte, `[...]` matches a set of bytes, optionally negated by a leading `^`
+/// and with ranges like `a-z`, and `\` escapes the following byte.
+///
+/// # Arguments
+/// *  `pattern` - The pattern.
+/// *  `string` - The string to match.
+fn glob(pattern: &[u8], string: &[u8]) -> bool {
+    match pattern {
+        [] => string.is_empty(),
+        [b'*', rest @ ..] => {
+            (0..=string.len()).any(|i| glob(rest, &string[i..]))
+        }
+        [b'?', rest @ ..] => !string.is_empty() && glob(rest, &string[1..]),
+        [b'[', rest @ ..] => {
+            let (negated, rest) = match rest {
+                [b'^', rest @ ..] => (true, rest),
+                rest => (false, rest),
+            };
+            let Some(end) = rest.iter().position(|&c| c == b']') else {
+                return string.first() == Some(&b'[')
+                    && glob(&pattern[1..], &string[1..]);
+            };
+            let Some((&c, string)) = string.split_first() else {
+                return false;
+            };
+            let (mut set, mut found) = (&rest[..end], false);
+            while let Some((&first, tail)) = set.split_first() {
+                set = match (first, tail) {
+                    (b'\\', [escaped, tail @ ..]) => {
+                        found |= c == *escaped;
+                        tail
+                    }
+                    (low, [b'-', high, tail @ ..]) => {
+                        found |= (low.min(*high)..=low.max(*high)).contains(&c);
+                        tail
+                    }
+                    (first, tail) => {
+                        found |= c == first;
+                        tail
+                    }
+                };
+            }
+            found != negated && glob(&rest[end + 1..], string)
+        }
+        [b'\\', escaped, rest @ ..] | [escaped, rest @ ..] => {
+            string.first() == Some(escaped) && glob(rest, &string[1..])
+        }
     }
 }
+
 /// A wrapper for a value with a time-to-live.
 #[derive(Clone, Debug)]
 struct Data {
     /// The value.
     value: Vec<u8>,
 
-    /// The instant of expiry.
-    expiry: Instant,
+    /// The instant of expiry, or `None` if the value never expires.
+    expiry: Option<Instant>,
+
+    /// The instant after which the value is stale, or `None` if it never
+    /// becomes stale.
+    stale: Option<Instant>,
 }
 
 impl Data {
@@ -247,9 +857,22 @@ impl Data {
     ///
     /// # Arguments
     /// *  `value` - The value to wrap.
-    /// *  `expiry` - The instant of expiry for the value.
-    pub fn new(value: Vec<u8>, expiry: Instant) -> Self {
-        Self { value, expiry }
+    /// *  `expiry` - The instant of expiry for the value, or `None` if it
+    ///    never expires.
+    pub fn new(value: Vec<u8>, expiry: Option<Instant>) -> Self {
+        Self {
+            value,
+            expiry,
+            stale: None,
+        }
+    }
+
+    /// Sets the instant after which this wrapper is stale.
+    ///
+    /// # Arguments
+    /// *  `stale` - The instant, or `None` to never become stale.
+    pub fn with_stale(self, stale: Option<Instant>) -> Self {
+        Self { stale, ..self }
     }
 
     /// Checks the expiry of this wrapper, and returns whether it is still
@@ -258,7 +881,15 @@ impl Data {
     /// # Arguments
     /// *  `expiry` - The expiry time to check.
     pub fn live(&self, expiry: Instant) -> bool {
-        expiry < self.expiry
+        self.expiry.is_none_or(|e| expiry < e)
+    }
+
+    /// Checks whether this wrapper is stale.
+    ///
+    /// # Arguments
+    /// *  `now` - The time to check.
+    pub fn stale(&self, now: Instant) -> bool {
+        self.stale.is_some_and(|s| now >= s)
     }
 
     /// Clones the inner value.
@@ -269,9 +900,177 @@ impl Data {
 
 #[cfg(test)]
 fn configuration() -> Option<crate::Configuration> {
-    Some(crate::Configuration::Local(Configuration))
+    Some(crate::Configuration::Local(Configuration::default()))
 }
 
 #[cfg(test)]
 #[path = "tests.rs"]
 mod tests;
+
+#[cfg(test)]
+mod local_tests {
+    use super::*;
+
+    #[test]
+    fn configuration_deserialize_empty() {
+        // Arrange
+        let source = r#"{"type": "local"}"#;
+
+        // Act
+        let actual = serde_json::from_str::<crate::Configuration>(source);
+
+        // Assert
+        let actual = match actual.unwrap() {
+            crate::Configuration::Local(actual) => actual,
+            #[cfg(feature = "redis")]
+            _ => unreachable!(),
+        };
+        assert_eq!(None, actual.compression);
+        assert_eq!(
+            Compression::DEFAULT_THRESHOLD,
+            actual.compression_threshold,
+        );
+        assert_eq!(None, actual.namespace);
+    }
+
+    #[actix_rt::test]
+    async fn namespace_shared() {
+        // Arrange
+        let configuration = Configuration {
+            namespace: Some("namespace_shared".into()),
+            ..Default::default()
+        };
+        let engine1 = configuration.engine().await.unwrap();
+        let engine2 = configuration.engine().await.unwrap();
+        let cache1 = engine1.cache::<String, String>("cache").await.unwrap();
+        let cache2 = engine2.cache::<String, String>("cache").await.unwrap();
+        let key = "key".to_string();
+        let expected = Some("expected".to_string());
+
+        // Act
+        cache1
+            .put(
+                key.clone(),
+                expected.clone().unwrap(),
+                Duration::from_secs(32),
+            )
+            .await
+            .unwrap();
+        let actual = cache2.get(&key).await;
+
+        // Assert
+        assert_eq!(Ok(expected), actual);
+    }
+
+    #[actix_rt::test]
+    async fn namespace_isolated() {
+        // Arrange
+        let configuration = Configuration::default();
+        let engine1 = configuration.engine().await.unwrap();
+        let engine2 = configuration.engine().await.unwrap();
+        let cache1 = engine1.cache::<String, String>("cache").await.unwrap();
+        let cache2 = engine2.cache::<String, String>("cache").await.unwrap();
+        let key = "key".to_string();
+
+        // Act
+        cache1
+            .put(key.clone(), "value".to_string(), Duration::from_secs(32))
+            .await
+            .unwrap();
+        let actual = cache2.get(&key).await;
+
+        // Assert
+        assert_eq!(Ok(None), actual);
+    }
+
+    #[actix_rt::test]
+    async fn stale_while_revalidate() {
+        // Arrange
+        let engine = Configuration::default().engine().await.unwrap();
+        let cache = engine.cache::<String, String>("cache").await.unwrap();
+        let (swr, plain) = ("swr".to_string(), "plain".to_string());
+
+        // Act
+        cache
+            .put_swr(
+                swr.clone(),
+                swr.clone(),
+                Duration::from_millis(20),
+                Duration::from_millis(60),
+            )
+            .await
+            .unwrap();
+        cache
+            .put(plain.clone(), plain.clone(), Duration::from_secs(32))
+            .await
+            .unwrap();
+        let fresh = cache.get_swr(&swr).await;
+        actix_rt::time::sleep(Duration::from_millis(40)).await;
+        let stale = cache.get_swr(&swr).await;
+        let stale_plain = cache.get(&swr).await;
+        let never_stale = cache.get_swr(&plain).await;
+        actix_rt::time::sleep(Duration::from_millis(60)).await;
+        let expired = cache.get_swr(&swr).await;
+
+        // Assert
+        assert_eq!(Ok(Some((swr.clone(), false))), fresh);
+        assert_eq!(Ok(Some((swr.clone(), true))), stale);
+        assert_eq!(Ok(Some(swr)), stale_plain);
+        assert_eq!(Ok(Some((plain, false))), never_stale);
+        assert_eq!(Ok(None), expired);
+    }
+
+    #[test]
+    fn glob_patterns() {
+        assert!(glob(b"", b""));
+        assert!(glob(b"*", b"anything"));
+        assert!(glob(b"a*c", b"abbc"));
+        assert!(!glob(b"a*c", b"abbd"));
+        assert!(glob(b"a?c", b"abc"));
+        assert!(!glob(b"a?c", b"ac"));
+        assert!(glob(b"[ab]c", b"bc"));
+        assert!(!glob(b"[^ab]c", b"bc"));
+        assert!(glob(b"[a-c]", b"b"));
+        assert!(!glob(b"[a-c]", b"d"));
+        assert!(glob(b"a\\*", b"a*"));
+        assert!(!glob(b"a\\*", b"ab"));
+        assert!(glob(b"[a", b"[a"));
+    }
+
+    #[actix_rt::test]
+    async fn eviction_callback() {
+        // Arrange
+        let evicted = Arc::new(Mutex::new(Vec::new()));
+        let engine = match Configuration::default().engine().await.unwrap() {
+            crate::Engine::Local(engine) => {
+                let evicted = evicted.clone();
+                engine.with_eviction_callback(Arc::new(move |key| {
+                    evicted.lock().unwrap().push(key.to_vec())
+                }))
+            }
+            #[cfg(feature = "redis")]
+            _ => unreachable!(),
+        };
+        let cache = engine.cache::<String, String>("cache").await.unwrap();
+        let (expired, live) = ("expired".to_string(), "live".to_string());
+        let expected =
+            vec![cbor4ii::serde::to_vec(Vec::new(), &expired).unwrap()];
+
+        // Act
+        cache
+            .put(expired.clone(), expired.clone(), Duration::from_millis(10))
+            .await
+            .unwrap();
+        cache
+            .put(live.clone(), live.clone(), Duration::from_secs(32))
+            .await
+            .unwrap();
+        actix_rt::time::sleep(Duration::from_millis(50)).await;
+        cache.get(&expired).await.unwrap();
+        cache.get(&live).await.unwrap();
+        cache.pop(&live).await.unwrap();
+
+        // Assert
+        assert_eq!(expected, *evicted.lock().unwrap());
+    }
+}
@@ -1,3 +1,39 @@
+/// Runs a cache operation, and logs it at the debug level if the `trace`
+/// feature is enabled.
+///
+/// The record reads `<backend> <operation> <key>: <duration>`, followed by
+/// `(failed)` for errors. Values are never logged, and the key is described
+/// by its serialised form, so a hashed key is logged as its hash. The key is
+/// only serialised for logging when tracing is enabled.
+///
+/// # Arguments
+/// *  `backend` - The name of the backend.
+/// *  `operation` - The name of the cache method.
+/// *  `target` - The key, or the cache name or key prefix for operations on
+///    all keys.
+/// *  `body` - The operation.
+macro_rules! traced {
+    ($backend:literal, $operation:literal, $target:expr, $body:expr) => {{
+        #[cfg(feature = "trace")]
+        let start = ::std::time::Instant::now();
+        let result: Result<_, crate::Error> = async { $body }.await;
+        #[cfg(feature = "trace")]
+        ::log::debug!(
+            "{} {} {}: {:?}{}",
+            $backend,
+            $operation,
+            $target,
+            start.elapsed(),
+            if result.is_ok() { "" } else { " (failed)" },
+        );
+        result
+    }};
+}
+
+/// The default separator between the segments of a key.
+#[cfg(any(feature = "redis", feature = "trace"))]
+const DEFAULT_SEPARATOR: &str = ":";
+
 #[cfg(feature = "local")]
 pub mod local;
 
@@ -5,6 +5,9 @@
 //!
 //! The minimum version of Redis reqired is 6.2.
 
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::PathBuf;
 use std::time::Duration;
 
 use async_trait::async_trait;
@@ -13,27 +16,215 @@ use redis::aio::{ConnectionLike, MultiplexedConnection};
 use redis::{AsyncCommands, Client};
 use serde::{Deserialize, Serialize};
 
-use crate::{configuration, CacheProducer, Error, Key, Name, Value};
+use crate::compression::Compressor;
+use crate::{
+    configuration, CacheConfig, CacheProducer, Compression, Error, Key, Name,
+    Updater, Value,
+};
 
-/// The configuration for a local cache.
-#[derive(Clone, Debug, Deserialize, Serialize)]
+/// The configuration for a Redis cache.
+#[derive(Clone, Deserialize, Serialize)]
 pub struct Configuration {
     /// The prefix used for keys.
     pub prefix: String,
 
+    /// The separator inserted between the prefix, the cache name and the
+    /// key.
+    ///
+    /// This defaults to `":"`.
+    #[serde(default = "Configuration::default_separator")]
+    pub separator: String,
+
     /// The Redis connection string.
     ///
     /// This is a string on the format `"redis://host:port"`.
     pub connection_string: String,
+
+    /// The compression to apply to large values.
+    #[serde(default)]
+    pub compression: Option<Compression>,
+
+    /// The minimum size in bytes of values to compress.
+    #[serde(default = "Compression::default_threshold")]
+    pub compression_threshold: usize,
+
+    /// The maximum time in milliseconds an operation may take, including
+    /// acquiring a connection.
+    ///
+    /// Operations not completing in time fail with
+    /// [`Error::Timeout`](Error::Timeout). If this is not set, operations
+    /// never time out.
+    #[serde(default)]
+    pub operation_timeout: Option<u64>,
+
+    /// The maximum time in milliseconds to wait for a connection when all
+    /// connections of the pool are in use.
+    ///
+    /// Operations waiting longer fail with
+    /// [`Error::ConnectionPoolExhausted`](Error::ConnectionPoolExhausted).
+    /// This should be shorter than the operation timeout, since operations
+    /// timing out first fail with [`Error::Timeout`](Error::Timeout). If this
+    /// is not set, the operation timeout is used.
+    #[serde(default)]
+    pub pool_timeout: Option<u64>,
+
+    /// The TLS settings, if any.
+    #[serde(default)]
+    pub tls: Option<Tls>,
+
+    /// Whether to store values under a hash of the serialised key.
+    ///
+    /// When enabled, every Redis key is the prefix followed by the
+    /// hexadecimal BLAKE3 hash of the serialised key, which keeps long keys
+    /// from bloating memory. Since the original key cannot be recovered from
+    /// its hash, [`keys`](crate::Cache::keys) and
+    /// [`scan`](crate::Cache::scan) fail with
+    /// [`Error::Unsupported`](Error::Unsupported).
+    #[serde(default)]
+    pub hash_keys: bool,
+}
+
+/// The TLS settings for connections to Redis.
+///
+/// These apply only to connection strings with the `rediss://` scheme, and
+/// require the `tls` feature.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Tls {
+    /// The path to a PEM file containing the root certificate used to verify
+    /// the server.
+    ///
+    /// If this is not set, the root certificates of the system are used.
+    #[serde(default)]
+    pub ca_cert: Option<PathBuf>,
+
+    /// Whether to skip verifying the certificate of the server.
+    ///
+    /// This is intended for self-signed certificates in test environments.
+    #[serde(default)]
+    pub insecure: bool,
+}
+
+impl ::std::fmt::Debug for Configuration {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        f.debug_struct("Configuration")
+            .field("prefix", &self.prefix)
+            .field("separator", &self.separator)
+            .field(
+                "connection_string",
+                &configuration::redis::redact(&self.connection_string),
+            )
+            .field("compression", &self.compression)
+            .field("compression_threshold", &self.compression_threshold)
+            .field("operation_timeout", &self.operation_timeout)
+            .field("pool_timeout", &self.pool_timeout)
+            .field("tls", &self.tls)
+            .field("hash_keys", &self.hash_keys)
+            .finish()
+    }
 }
 
 impl Configuration {
     /// Constructs a cache engine from this configuration.
     pub async fn engine(&self) -> Result<crate::Engine, configuration::Error> {
         let prefix = self.prefix.clone();
-        let client = Client::open(self.connection_string.clone())?;
-        let pool = Pool::builder().build(ConnectionManager { client });
-        Ok(crate::Engine::Redis(Engine { prefix, pool }))
+        let separator = self.separator.clone();
+        let compressor =
+            Compressor::new(self.compression, self.compression_threshold);
+        let timeout = self.operation_timeout.map(Duration::from_millis);
+        let hash_keys = self.hash_keys;
+        let client = self.client()?;
+        let pool = Pool::builder()
+            .get_timeout(
+                self.pool_timeout.map(Duration::from_millis).or(timeout),
+            )
+            .build(ConnectionManager { client });
+        Ok(crate::Engine::Redis(Engine {
+            prefix,
+            separator,
+            compressor,
+            timeout,
+            hash_keys,
+            pool,
+        }))
+    }
+
+    /// Verifies this configuration, and returns all problems found.
+    ///
+    /// No connection to the server is attempted.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut problems = Vec::new();
+        if self.connection_string.is_empty() {
+            problems.push("the connection string must not be empty".into());
+        }
+        if self.separator.is_empty() {
+            problems.push("the separator must not be empty".into());
+        }
+        if self.operation_timeout == Some(0) {
+            problems.push("the operation timeout must be positive".into());
+        }
+        if self.pool_timeout == Some(0) {
+            problems.push("the pool timeout must be positive".into());
+        }
+        if let Some(ca_cert) =
+            self.tls.as_ref().and_then(|tls| tls.ca_cert.as_ref())
+        {
+            if !ca_cert.is_file() {
+                problems.push(format!(
+                    "the CA certificate {} does not exist",
+                    ca_cert.display(),
+                ));
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems)
+        }
+    }
+
+    /// The default separator between key segments.
+    fn default_separator() -> String {
+        super::DEFAULT_SEPARATOR.into()
+    }
+
+    /// Creates a client for the Redis server, applying any TLS settings.
+    fn client(&self) -> Result<Client, configuration::Error> {
+        match &self.tls {
+            None => Ok(Client::open(self.connection_string.clone())?),
+
+            #[cfg(feature = "tls")]
+            Some(tls) => {
+                use redis::{ConnectionAddr, IntoConnectionInfo};
+
+                let mut info =
+                    self.connection_string.as_str().into_connection_info()?;
+                if let ConnectionAddr::TcpTls { insecure, .. } = &mut info.addr
+                {
+                    *insecure |= tls.insecure;
+                }
+                let root_cert = tls
+                    .ca_cert
+                    .as_ref()
+                    .map(std::fs::read)
+                    .transpose()
+                    .map_err(redis::RedisError::from)?;
+                Ok(Client::build_with_tls(
+                    info,
+                    redis::TlsCertificates {
+                        client_tls: None,
+                        root_cert,
+                    },
+                )?)
+            }
+
+            #[cfg(not(feature = "tls"))]
+            Some(_) => Err(redis::RedisError::from((
+                redis::ErrorKind::InvalidClientConfig,
+                "TLS support is not enabled",
+            ))
+            .into()),
+        }
     }
 }
 
@@ -42,33 +233,163 @@ pub struct Engine {
     /// The prefix used for keys.
     prefix: String,
 
+    /// The separator between key segments.
+    separator: String,
+
+    /// The compressor applied to values.
+    compressor: Compressor,
+
+    /// The maximum duration of operations.
+    timeout: Option<Duration>,
+
+    /// Whether keys are hashed.
+    hash_keys: bool,
+
     /// The connection pool.
     pool: Pool<ConnectionManager>,
 }
 
 impl ::std::fmt::Debug for Engine {
     fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
-        write!(f, "Engine {{ prefix: {} }}", self.prefix)
+        write!(
+            f,
+            "Engine {{ prefix: {}, separator: {} }}",
+            self.prefix, self.separator,
+        )
     }
 }
 
-#[async_trait]
-impl CacheProducer for Engine {
-    async fn cache<K, V>(
+impl Engine {
+    /// Verifies that the Redis server is reachable by sending `PING`.
+    pub async fn health_check(&self) -> Result<(), Error> {
+        let mut conn = self.pool.get().await?;
+        redis::cmd("PING").query_async::<()>(&mut *conn).await?;
+        Ok(())
+    }
+
+    /// Atomically moves a value from one cache to another.
+    ///
+    /// The move is performed by a script on the Redis server, so no other
+    /// client observes the value in both caches, or in neither.
+    ///
+    /// # Arguments
+    /// *  `from` - The name of the source cache.
+    /// *  `to` - The name of the destination cache.
+    /// *  `key` - The key of the value to move.
+    /// *  `ttl` - The time-to-live for the value in the destination cache.
+    pub async fn move_entry<K, V>(
+        &self,
+        from: impl Name,
+        to: impl Name,
+        key: &K,
+        ttl: Duration,
+    ) -> Result<Option<V>, Error>
+    where
+        K: Key,
+        V: Value,
+    {
+        let source = self.redis_cache::<K, V>(from, CacheConfig::default());
+        let destination = self.redis_cache::<K, V>(to, CacheConfig::default());
+        source
+            .timed(async {
+                let mut conn = source.connection().await?;
+                let script = redis::Script::new(MOVE_SCRIPT);
+                let mut invocation = script.prepare_invoke();
+                invocation
+                    .key(source.key_serialize(key)?)
+                    .key(destination.key_serialize(key)?);
+                if ttl != crate::NEVER_EXPIRES {
+                    invocation.arg(ttl.as_millis() as usize);
+                }
+
+                match invocation
+                    .invoke_async::<redis::Value>(&mut *conn)
+                    .await?
+                {
+                    redis::Value::BulkString(value) => {
+                        Ok(Some(source.value_deserialize(&value)?))
+                    }
+                    _ => Ok(None),
+                }
+            })
+            .await
+    }
+
+    /// Creates a cache instance.
+    ///
+    /// # Arguments
+    /// *  `name` - The cache name.
+    /// *  `config` - The options for the cache.
+    fn redis_cache<K, V>(
         &self,
         name: impl Name,
-    ) -> Result<Box<dyn crate::Cache<K, V>>, Error>
+        config: CacheConfig,
+    ) -> Cache<K, V>
     where
         K: Key,
         V: Value,
     {
-        let prefix = format!("{}{}", self.prefix, name.as_ref()).into_bytes();
+        let prefix = format!(
+            "{prefix}{separator}{name}{separator}",
+            prefix = self.prefix,
+            separator = self.separator,
+            name = name.as_ref(),
+        )
+        .into_bytes();
         let pool = self.pool.clone();
-        Ok(Box::new(Cache {
+        Cache {
             prefix,
+            compressor: self.compressor,
+            config,
+            timeout: self.timeout,
+            hash_keys: self.hash_keys,
             pool,
             _m: ::std::marker::PhantomData,
-        }))
+        }
+    }
+}
+
+/// The script moving a value between keys.
+///
+/// The value under the first key is moved to the second key, and returned.
+/// The optional argument is the time-to-live in milliseconds; if it is not
+/// passed, the moved value never expires.
+const MOVE_SCRIPT: &str = r"
+local value = redis.call('GETDEL', KEYS[1])
+if value then
+    if ARGV[1] then
+        redis.call('SET', KEYS[2], value, 'PX', ARGV[1])
+    else
+        redis.call('SET', KEYS[2], value)
+    end
+end
+return value
+";
+
+/// The script removing the expiry of a key.
+///
+/// This returns whether the key exists, since `PERSIST` alone does not
+/// distinguish a missing key from one without expiry.
+const PERSIST_SCRIPT: &str = r"
+if redis.call('EXISTS', KEYS[1]) == 1 then
+    redis.call('PERSIST', KEYS[1])
+    return 1
+end
+return 0
+";
+
+#[async_trait]
+impl CacheProducer for Engine {
+    async fn cache_with_config<K, V>(
+        &self,
+        name: impl Name,
+        config: CacheConfig,
+    ) -> Result<Box<dyn crate::Cache<K, V>>, Error>
+    where
+        K: Key,
+        V: Value,
+    {
+        Ok(Box::new(self.redis_cache(name, config)))
     }
 }
 
@@ -83,6 +404,18 @@ where
     /// A prefix prepended to key names.
     prefix: Vec<u8>,
 
+    /// The compressor applied to values.
+    compressor: Compressor,
+
+    /// The options for this cache.
+    config: CacheConfig,
+
+    /// The maximum duration of operations.
+    timeout: Option<Duration>,
+
+    /// Whether keys are hashed.
+    hash_keys: bool,
+
     /// The connection pool.
     pool: Pool<ConnectionManager>,
 
@@ -99,25 +432,112 @@ where
         Ok(self.pool.get().await?)
     }
 
+    /// Runs an operation, failing if it does not complete in time.
+    ///
+    /// # Arguments
+    /// *  `operation` - The operation to run.
+    async fn timed<T>(
+        &self,
+        operation: impl Future<Output = Result<T, Error>>,
+    ) -> Result<T, Error> {
+        match self.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, operation)
+                .await
+                .map_err(|_| Error::Timeout)?,
+            None => operation.await,
+        }
+    }
+
     /// Generates the Redis key to use for a key name.
     ///
+    /// If keys are hashed, the serialised key is replaced by its hash.
+    ///
     /// # Arguments
     /// *  `key` - The name of the key.
     fn key_serialize(&self, key: &K) -> Result<Vec<u8>, Error> {
-        let key = cbor4ii::serde::to_vec(Vec::new(), key)?;
+        let key = crate::Cache::key_bytes(self, key)?;
         Ok({
             let mut result = self.prefix.clone();
-            result.extend(&key);
+            if self.hash_keys {
+                result.extend(blake3::hash(&key).to_hex().as_bytes());
+            } else {
+                result.extend(&key);
+            }
             result
         })
     }
 
+    /// Converts a Redis key to a key name.
+    ///
+    /// # Arguments
+    /// *  `key` - The Redis key, including the prefix.
+    fn key_deserialize(&self, key: &[u8]) -> Result<K, Error> {
+        Ok(cbor4ii::serde::from_slice(
+            key.strip_prefix(self.prefix.as_slice()).unwrap_or(key),
+        )?)
+    }
+
+    /// Generates the pattern matching Redis keys of this cache.
+    ///
+    /// Glob characters in the prefix are escaped.
+    ///
+    /// # Arguments
+    /// *  `glob` - The pattern matching the serialised keys.
+    fn pattern(&self, glob: &[u8]) -> Vec<u8> {
+        let mut result = self.prefix.iter().fold(
+            Vec::with_capacity(self.prefix.len() + glob.len()),
+            |mut result, &c| {
+                if matches!(c, b'*' | b'?' | b'[' | b']' | b'\\') {
+                    result.push(b'\\');
+                }
+                result.push(c);
+                result
+            },
+        );
+        result.extend(glob);
+        result
+    }
+
+    /// Lists the keys of all values in this cache matching a pattern.
+    ///
+    /// # Arguments
+    /// *  `glob` - The pattern matching the serialised keys.
+    async fn scan_keys(&self, glob: &[u8]) -> Result<Vec<K>, Error> {
+        if self.hash_keys {
+            return Err(Error::Unsupported(
+                "keys cannot be listed when they are hashed".into(),
+            ));
+        }
+
+        let mut conn = self.connection().await?;
+        let mut keys =
+            conn.scan_match::<_, Vec<u8>>(self.pattern(glob)).await?;
+
+        let mut result = Vec::new();
+        while let Some(key) = keys.next_item().await {
+            result.push(self.key_deserialize(&key)?);
+        }
+        Ok(result)
+    }
+
+    /// Describes a key for logging.
+    ///
+    /// # Arguments
+    /// *  `key` - The key to describe.
+    #[cfg(feature = "trace")]
+    fn describe(&self, key: &K) -> String {
+        self.key_serialize(key)
+            .map(|key| key.escape_ascii().to_string())
+            .unwrap_or_default()
+    }
+
     /// Converts a value to a byte vector.
     ///
     /// # Arguments
     /// *  `value` - The value to convert.
     fn value_serialize(&self, value: &V) -> Result<Vec<u8>, Error> {
-        Ok(cbor4ii::serde::to_vec(Vec::new(), value)?)
+        self.compressor
+            .compress(cbor4ii::serde::to_vec(Vec::new(), value)?)
     }
 
     /// Converts a byte vector to a value.
@@ -128,7 +548,9 @@ where
     where
         T: for<'a> Deserialize<'a>,
     {
-        Ok(cbor4ii::serde::from_slice(value)?)
+        Ok(cbor4ii::serde::from_slice(
+            &self.compressor.decompress(value)?,
+        )?)
     }
 }
 
@@ -139,45 +561,164 @@ where
     V: Value,
 {
     async fn get(&self, key: &K) -> Result<Option<V>, Error> {
-        let mut conn = self.connection().await?;
-        let key = self.key_serialize(key)?;
+        traced!(
+            "redis",
+            "get",
+            self.describe(key),
+            self.timed(async {
+                let mut conn = self.connection().await?;
+                let key = self.key_serialize(key)?;
 
-        match conn.get(&key).await? {
-            redis::Value::BulkString(value) => {
-                Ok(Some(self.value_deserialize(&value)?))
-            }
-            _ => Ok(None),
+                match conn.get(&key).await? {
+                    redis::Value::BulkString(value) => {
+                        Ok(Some(self.value_deserialize(&value)?))
+                    }
+                    _ => Ok(None),
+                }
+            })
+            .await
+        )
+    }
+
+    async fn get_many(&self, keys: Vec<K>) -> Result<HashMap<K, V>, Error> {
+        traced!(
+            "redis",
+            "get_many",
+            self.prefix.escape_ascii(),
+            self.timed(async {
+                if keys.is_empty() {
+                    return Ok(HashMap::new());
+                }
+
+                let mut conn = self.connection().await?;
+                let redis_keys = keys
+                    .iter()
+                    .map(|key| self.key_serialize(key))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let values = redis::cmd("MGET")
+                    .arg(&redis_keys)
+                    .query_async::<Vec<redis::Value>>(&mut *conn)
+                    .await?;
+
+                keys.into_iter()
+                    .zip(values)
+                    .filter_map(|(key, value)| match value {
+                        redis::Value::BulkString(value) => Some(
+                            self.value_deserialize(&value)
+                                .map(|value| (key, value)),
+                        ),
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .await
+        )
+    }
+
+    async fn touch(&self, key: &K, ttl: Duration) -> Result<Option<V>, Error> {
+        traced!(
+            "redis",
+            "touch",
+            self.describe(key),
+            self.timed(async {
+                let mut conn = self.connection().await?;
+                let key = self.key_serialize(key)?;
+
+                let mut cmd = redis::Cmd::new();
+                cmd.arg("GETEX").arg(key);
+                if ttl == crate::NEVER_EXPIRES {
+                    cmd.arg("PERSIST");
+                } else {
+                    cmd.arg("PX").arg(ttl.as_millis() as usize);
+                }
+
+                match conn.req_packed_command(&cmd).await? {
+                    redis::Value::BulkString(value) => {
+                        Ok(Some(self.value_deserialize(&value)?))
+                    }
+                    _ => Ok(None),
+                }
+            })
+            .await
+        )
+    }
+
+    async fn persist(&self, key: &K) -> Result<bool, Error> {
+        traced!(
+            "redis",
+            "persist",
+            self.describe(key),
+            self.timed(async {
+                let mut conn = self.connection().await?;
+                let script = redis::Script::new(PERSIST_SCRIPT);
+                let mut invocation = script.prepare_invoke();
+                invocation.key(self.key_serialize(key)?);
+
+                Ok(invocation.invoke_async::<bool>(&mut *conn).await?)
+            })
+            .await
+        )
+    }
+
+    async fn expire(&self, key: &K, ttl: Duration) -> Result<bool, Error> {
+        if ttl == crate::NEVER_EXPIRES {
+            return self.persist(key).await;
         }
+        traced!(
+            "redis",
+            "expire",
+            self.describe(key),
+            self.timed(async {
+                let mut conn = self.connection().await?;
+                let key = self.key_serialize(key)?;
+
+                Ok(conn.pexpire(&key, ttl.as_millis() as i64).await?)
+            })
+            .await
+        )
     }
 
     async fn pop(&self, key: &K) -> Result<Option<V>, Error> {
-        let mut conn = self.connection().await?;
-        let key = self.key_serialize(key)?;
+        traced!(
+            "redis",
+            "pop",
+            self.describe(key),
+            self.timed(async {
+                let mut conn = self.connection().await?;
+                let key = self.key_serialize(key)?;
 
-        match conn.get_del(&key).await? {
-            redis::Value::BulkString(value) => {
-                Ok(Some(self.value_deserialize(&value)?))
-            }
-            _ => Ok(None),
-        }
+                match conn.get_del(&key).await? {
+                    redis::Value::BulkString(value) => {
+                        Ok(Some(self.value_deserialize(&value)?))
+                    }
+                    _ => Ok(None),
+                }
+            })
+            .await
+        )
     }
 
     async fn put(&self, key: K, value: V, ttl: Duration) -> Result<(), Error> {
-        let mut conn = self.connection().await?;
-        let key = self.key_serialize(&key)?;
-        let value = self.value_serialize(&value)?;
-
-        conn.req_packed_command(
-            redis::Cmd::new()
-                .arg("SET")
-                .arg(key)
-                .arg(value)
-                .arg("PX")
-                .arg(ttl.as_millis() as usize),
-        )
-        .await?;
+        traced!(
+            "redis",
+            "put",
+            self.describe(&key),
+            self.timed(async {
+                let mut conn = self.connection().await?;
+                let key = self.key_serialize(&key)?;
+                let value = self.value_serialize(&value)?;
 
-        Ok(())
+                let mut cmd = redis::Cmd::new();
+                cmd.arg("SET").arg(key).arg(value);
+                if ttl != crate::NEVER_EXPIRES {
+                    cmd.arg("PX").arg(ttl.as_millis() as usize);
+                }
+                conn.req_packed_command(&cmd).await?;
+
+                Ok(())
+            })
+            .await
+        )
     }
 
     async fn replace(
@@ -186,41 +727,213 @@ where
         value: V,
         ttl: Option<Duration>,
     ) -> Result<Option<V>, Error> {
-        let mut conn = self.connection().await?;
-        let key = self.key_serialize(&key)?;
-        let value = self.value_serialize(&value)?;
-
-        let previous = if let Some(ttl) = ttl {
-            conn.req_packed_command(
-                redis::Cmd::new()
-                    .arg("SET")
-                    .arg(key)
-                    .arg(value)
-                    .arg("XX")
-                    .arg("GET")
-                    .arg("PX")
-                    .arg(ttl.as_millis() as usize),
-            )
-            .await?
-        } else {
-            conn.req_packed_command(
-                redis::Cmd::new()
-                    .arg("SET")
-                    .arg(key)
-                    .arg(value)
-                    .arg("XX")
-                    .arg("GET")
-                    .arg("KEEPTTL"),
-            )
-            .await?
-        };
+        traced!(
+            "redis",
+            "replace",
+            self.describe(&key),
+            self.timed(async {
+                let mut conn = self.connection().await?;
+                let key = self.key_serialize(&key)?;
+                let value = self.value_serialize(&value)?;
+
+                let mut cmd = redis::Cmd::new();
+                cmd.arg("SET").arg(key).arg(value).arg("XX").arg("GET");
+                match ttl {
+                    Some(ttl) if ttl == crate::NEVER_EXPIRES => {}
+                    Some(ttl) => {
+                        cmd.arg("PX").arg(ttl.as_millis() as usize);
+                    }
+                    None => {
+                        cmd.arg("KEEPTTL");
+                    }
+                }
+                let previous = conn.req_packed_command(&cmd).await?;
+
+                Ok(match previous {
+                    redis::Value::BulkString(value) => {
+                        Some(self.value_deserialize(&value)?)
+                    }
+                    _ => None,
+                })
+            })
+            .await
+        )
+    }
+
+    async fn update(
+        &self,
+        key: &K,
+        f: &mut Updater<'_, V>,
+    ) -> Result<Option<V>, Error> {
+        traced!(
+            "redis",
+            "update",
+            self.describe(key),
+            self.timed(async {
+                let mut conn = Watched::new(self.connection().await?);
+                let key = self.key_serialize(key)?;
+
+                loop {
+                    conn.watch(&key).await?;
+                    let current = match conn.connection().get(&key).await? {
+                        redis::Value::BulkString(value) => Some(
+                            conn.unwatch_on_err(self.value_deserialize(&value))
+                                .await?,
+                        ),
+                        _ => None,
+                    };
+                    let Some((value, ttl)) = f(current.as_ref()) else {
+                        conn.unwatch().await?;
+                        return Ok(current);
+                    };
+
+                    let mut cmd = redis::Cmd::new();
+                    cmd.arg("SET").arg(&key).arg(
+                        conn.unwatch_on_err(self.value_serialize(&value))
+                            .await?,
+                    );
+                    match ttl {
+                        Some(ttl) if ttl == crate::NEVER_EXPIRES => {}
+                        Some(ttl) => {
+                            cmd.arg("PX").arg(ttl.as_millis() as usize);
+                        }
+                        None => {
+                            cmd.arg("KEEPTTL");
+                        }
+                    }
+
+                    // The transaction is aborted if the value was modified
+                    // after it was watched
+                    let committed = redis::pipe()
+                        .atomic()
+                        .add_command(cmd)
+                        .ignore()
+                        .query_async::<Option<()>>(conn.connection())
+                        .await?;
+                    conn.executed();
+                    if committed.is_some() {
+                        return Ok(Some(value));
+                    }
+                }
+            })
+            .await
+        )
+    }
+
+    async fn keys(&self) -> Result<Vec<K>, Error> {
+        traced!(
+            "redis",
+            "keys",
+            self.prefix.escape_ascii(),
+            self.timed(self.scan_keys(b"*")).await
+        )
+    }
+
+    async fn scan(&self, pattern: &str) -> Result<Vec<K>, Error> {
+        traced!(
+            "redis",
+            "scan",
+            self.prefix.escape_ascii(),
+            self.timed(self.scan_keys(pattern.as_bytes())).await
+        )
+    }
+
+    fn key_bytes(&self, key: &K) -> Result<Vec<u8>, Error> {
+        Ok(cbor4ii::serde::to_vec(Vec::new(), key)?)
+    }
+
+    fn config(&self) -> &CacheConfig {
+        &self.config
+    }
+}
+
+/// A pooled connection on which keys may be watched.
+///
+/// Redis aborts the next transaction on a connection on which keys are
+/// watched if any of them changes, so a connection must not be returned to
+/// the pool while keys are watched. If this is dropped while keys may still
+/// be watched, for example because the operation timed out or a command
+/// failed, the connection is discarded instead.
+struct Watched {
+    /// The connection, which is only taken when this is dropped.
+    connection: Option<Connection<ConnectionManager>>,
+
+    /// Whether keys may be watched on the connection.
+    watching: bool,
+}
+
+impl Watched {
+    /// Wraps a pooled connection on which no keys are watched.
+    ///
+    /// # Arguments
+    /// *  `connection` - The pooled connection.
+    fn new(connection: Connection<ConnectionManager>) -> Self {
+        Self {
+            connection: Some(connection),
+            watching: false,
+        }
+    }
+
+    /// The wrapped connection.
+    fn connection(&mut self) -> &mut MultiplexedConnection {
+        self.connection
+            .as_deref_mut()
+            .expect("the connection is only taken when dropped")
+    }
+
+    /// Watches a key.
+    ///
+    /// # Arguments
+    /// *  `key` - The key to watch.
+    async fn watch(&mut self, key: &[u8]) -> Result<(), Error> {
+        self.watching = true;
+        redis::cmd("WATCH")
+            .arg(key)
+            .query_async::<()>(self.connection())
+            .await?;
+        Ok(())
+    }
 
-        Ok(match previous {
-            redis::Value::BulkString(value) => {
-                Some(self.value_deserialize(&value)?)
+    /// Stops watching all keys.
+    async fn unwatch(&mut self) -> Result<(), Error> {
+        redis::cmd("UNWATCH")
+            .query_async::<()>(self.connection())
+            .await?;
+        self.watching = false;
+        Ok(())
+    }
+
+    /// Stops watching all keys if a result is an error.
+    ///
+    /// The original error is returned even if unwatching fails; the
+    /// connection is then discarded when this is dropped.
+    ///
+    /// # Arguments
+    /// *  `result` - The result to inspect.
+    async fn unwatch_on_err<T>(
+        &mut self,
+        result: Result<T, Error>,
+    ) -> Result<T, Error> {
+        if result.is_err() {
+            self.unwatch().await.ok();
+        }
+        result
+    }
+
+    /// Records that a transaction was executed, which unwatches all keys.
+    fn executed(&mut self) {
+        self.watching = false;
+    }
+}
+
+impl Drop for Watched {
+    fn drop(&mut self) {
+        if self.watching {
+            if let Some(connection) = self.connection.take() {
+                // Taking the inner connection keeps it out of the pool
+                drop(connection.into_inner());
             }
-            _ => None,
-        })
+        }
     }
 }
 
@@ -245,7 +958,7 @@ impl Manager for ConnectionManager {
         &self,
         mut conn: Self::Connection,
     ) -> Result<Self::Connection, Self::Error> {
-        redis::cmd("PING").query_async(&mut conn).await?;
+        redis::cmd("PING").query_async::<()>(&mut conn).await?;
         Ok(conn)
     }
 }
@@ -256,10 +969,215 @@ fn configuration() -> Option<crate::Configuration> {
         crate::Configuration::Redis(Configuration {
             connection_string: connection_string.into(),
             prefix: "test".to_string(),
+            separator: Configuration::default_separator(),
+            compression: None,
+            compression_threshold: Compression::DEFAULT_THRESHOLD,
+            operation_timeout: None,
+            pool_timeout: None,
+            tls: None,
+            hash_keys: false,
         })
     })
 }
 
 #[cfg(test)]
+#[allow(clippy::duplicate_mod)]
 #[path = "tests.rs"]
 mod tests;
+
+#[cfg(test)]
+mod redis_tests {
+    use super::*;
+
+    use crate::FailOpen;
+
+    /// A configuration for a server that is never contacted.
+    ///
+    /// # Arguments
+    /// *  `connection_string` - The connection string.
+    /// *  `tls` - The TLS settings.
+    fn configuration(
+        connection_string: &str,
+        tls: Option<Tls>,
+    ) -> Configuration {
+        Configuration {
+            prefix: "test".into(),
+            separator: Configuration::default_separator(),
+            connection_string: connection_string.into(),
+            compression: None,
+            compression_threshold: Compression::DEFAULT_THRESHOLD,
+            operation_timeout: None,
+            pool_timeout: None,
+            tls,
+            hash_keys: false,
+        }
+    }
+
+    /// A cache for a server that is never contacted.
+    ///
+    /// # Arguments
+    /// *  `hash_keys` - Whether keys are hashed.
+    fn cache(hash_keys: bool) -> Cache<String, String> {
+        Cache {
+            prefix: b"test:cache:".to_vec(),
+            compressor: Compressor::new(None, Compression::DEFAULT_THRESHOLD),
+            config: CacheConfig::default(),
+            timeout: None,
+            hash_keys,
+            pool: Pool::builder().build(ConnectionManager {
+                client: configuration("redis://localhost", None)
+                    .client()
+                    .unwrap(),
+            }),
+            _m: ::std::marker::PhantomData,
+        }
+    }
+
+    #[test]
+    fn key_serialize_plain() {
+        let cache = cache(false);
+        let key = "a".repeat(1000);
+
+        assert_eq!(
+            b"test:cache:".len() + 1000 + 3,
+            cache.key_serialize(&key).unwrap().len(),
+        );
+    }
+
+    #[test]
+    fn key_serialize_hashed() {
+        let cache = cache(true);
+        let short = cache.key_serialize(&"a".into()).unwrap();
+        let long = cache.key_serialize(&"a".repeat(1000)).unwrap();
+
+        assert!(short.starts_with(b"test:cache:"));
+        assert_eq!(b"test:cache:".len() + 64, short.len());
+        assert_eq!(short.len(), long.len());
+        assert_ne!(short, long);
+    }
+
+    #[actix_rt::test]
+    async fn keys_hashed_unsupported() {
+        let cache = cache(true);
+
+        assert!(matches!(
+            crate::Cache::keys(&cache).await,
+            Err(Error::Unsupported(_)),
+        ));
+        assert!(matches!(
+            crate::Cache::scan(&cache, "*").await,
+            Err(Error::Unsupported(_)),
+        ));
+    }
+
+    #[actix_rt::test]
+    async fn swr_unsupported() {
+        let cache = cache(false);
+
+        assert!(matches!(
+            crate::Cache::get_swr(&cache, &"key".into()).await,
+            Err(Error::Unsupported(_)),
+        ));
+        assert!(matches!(
+            crate::Cache::put_swr(
+                &cache,
+                "key".into(),
+                "value".into(),
+                Duration::from_secs(1),
+                Duration::from_secs(1),
+            )
+            .await,
+            Err(Error::Unsupported(_)),
+        ));
+    }
+
+    #[test]
+    fn client_plain() {
+        assert!(configuration("redis://localhost", None).client().is_ok());
+    }
+
+    #[cfg(not(feature = "tls"))]
+    #[test]
+    fn client_tls_disabled() {
+        assert!(configuration("rediss://localhost", Some(Tls::default()))
+            .client()
+            .is_err());
+    }
+
+    #[cfg(feature = "tls")]
+    #[test]
+    fn client_tls() {
+        assert!(configuration(
+            "rediss://localhost",
+            Some(Tls {
+                ca_cert: None,
+                insecure: true,
+            }),
+        )
+        .client()
+        .is_ok());
+    }
+
+    #[cfg(feature = "tls")]
+    #[test]
+    fn client_tls_plain_url() {
+        assert!(configuration("redis://localhost", Some(Tls::default()))
+            .client()
+            .is_err());
+    }
+
+    #[cfg(feature = "tls")]
+    #[test]
+    fn client_tls_missing_ca_cert() {
+        assert!(configuration(
+            "rediss://localhost",
+            Some(Tls {
+                ca_cert: Some("/nonexistent/ca.pem".into()),
+                insecure: false,
+            }),
+        )
+        .client()
+        .is_err());
+    }
+
+    #[actix_rt::test]
+    async fn get_or_unavailable() {
+        // Arrange
+        let engine = configuration("redis://127.0.0.1:1", None)
+            .engine()
+            .await
+            .unwrap();
+        let cache = engine.cache::<String, String>("cache").await.unwrap();
+
+        // Act
+        let actual = cache
+            .get_or(&"key".into(), || async { Some("fallback".into()) })
+            .await;
+
+        // Assert
+        assert_eq!(Ok(Some("fallback".to_string())), actual);
+    }
+
+    #[test]
+    fn validate_valid() {
+        assert_eq!(Ok(()), configuration("redis://localhost", None).validate(),);
+    }
+
+    #[test]
+    fn validate_invalid() {
+        let configuration = Configuration {
+            separator: String::new(),
+            operation_timeout: Some(0),
+            ..configuration("", None)
+        };
+
+        assert_eq!(
+            Err(vec![
+                "the connection string must not be empty".to_string(),
+                "the separator must not be empty".to_string(),
+                "the operation timeout must be positive".to_string(),
+            ]),
+            configuration.validate(),
+        );
+    }
+}
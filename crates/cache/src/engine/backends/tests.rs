@@ -1,6 +1,12 @@
+use std::collections::HashMap;
 use std::thread::sleep;
 
 use super::*;
+use crate::rate::Window;
+use crate::{
+    CacheEntry, CacheMap, Cached, Idempotency, Idempotent, NegativeCache,
+    RateLimiter,
+};
 
 macro_rules! engine {
     () => {
@@ -52,6 +58,184 @@ async fn put_get() {
     assert_eq!(Ok(expected), actual);
 }
 
+#[actix_rt::test]
+async fn get_map() {
+    // Arrange
+    let name = "get_map".to_string();
+    let engine = engine!();
+    let cache = engine.cache::<String, String>(&name).await.unwrap();
+    let keys = ["a".to_string(), "b".to_string(), "c".to_string()];
+    let ttl = Duration::from_secs(32);
+    cache.put(keys[0].clone(), "1".into(), ttl).await.unwrap();
+    cache.pop(&keys[1]).await.unwrap();
+    cache.put(keys[2].clone(), "3".into(), ttl).await.unwrap();
+    let expected = [
+        (keys[0].clone(), "1".to_string()),
+        (keys[2].clone(), "3".to_string()),
+    ]
+    .into_iter()
+    .collect();
+
+    // Act
+    let actual = cache.get_map(keys).await;
+
+    // Assert
+    assert_eq!(Ok(expected), actual);
+}
+
+#[actix_rt::test]
+async fn get_map_empty() {
+    // Arrange
+    let name = "get_map_empty".to_string();
+    let engine = engine!();
+    let cache = engine.cache::<String, String>(&name).await.unwrap();
+
+    // Act
+    let actual = cache.get_map(Vec::new()).await;
+
+    // Assert
+    assert_eq!(Ok(HashMap::new()), actual);
+}
+
+#[actix_rt::test]
+async fn put_never_expires() {
+    // Arrange
+    let name = "put_never_expires".to_string();
+    let engine = engine!();
+    let cache = engine.cache::<String, String>(&name).await.unwrap();
+    let key = "key".to_string();
+    let expected = Some("expected".to_string());
+
+    // Act
+    cache
+        .put(key.clone(), expected.clone().unwrap(), crate::NEVER_EXPIRES)
+        .await
+        .unwrap();
+    let touched = cache.touch(&key, crate::NEVER_EXPIRES).await;
+    let replaced = cache
+        .replace(
+            key.clone(),
+            expected.clone().unwrap(),
+            Some(crate::NEVER_EXPIRES),
+        )
+        .await;
+    let actual = cache.get(&key).await;
+
+    // Assert
+    assert_eq!(Ok(expected.clone()), touched);
+    assert_eq!(Ok(expected.clone()), replaced);
+    assert_eq!(Ok(expected), actual);
+}
+
+#[actix_rt::test]
+async fn persist_and_expire() {
+    // Arrange
+    let name = "persist_and_expire".to_string();
+    let engine = engine!();
+    let cache = engine.cache::<String, String>(&name).await.unwrap();
+    let key = "key".to_string();
+    let missing = "missing".to_string();
+    let expected = Some("expected".to_string());
+    cache
+        .put(
+            key.clone(),
+            expected.clone().unwrap(),
+            Duration::from_millis(100),
+        )
+        .await
+        .unwrap();
+
+    // Act
+    let persisted = cache.persist(&key).await;
+    let persisted_missing = cache.persist(&missing).await;
+    let expired_missing = cache.expire(&missing, Duration::from_secs(1)).await;
+    sleep(Duration::from_millis(200));
+    let after_persist = cache.get(&key).await;
+    let expired = cache.expire(&key, Duration::from_millis(100)).await;
+    sleep(Duration::from_millis(200));
+    let after_expire = cache.get(&key).await;
+
+    // Assert
+    assert_eq!(Ok(true), persisted);
+    assert_eq!(Ok(false), persisted_missing);
+    assert_eq!(Ok(false), expired_missing);
+    assert_eq!(Ok(expected), after_persist);
+    assert_eq!(Ok(true), expired);
+    assert_eq!(Ok(None), after_expire);
+}
+
+#[actix_rt::test]
+async fn negative_cache() {
+    // Arrange
+    let name = "negative_cache".to_string();
+    let engine = engine!();
+    let cache = engine
+        .cache::<String, Cached<Option<String>>>(&name)
+        .await
+        .unwrap();
+    let ttl = Duration::from_secs(10);
+
+    // Act
+    cache.put_present("none".into(), None, ttl).await.unwrap();
+    cache.put_negative("negative".into(), ttl).await.unwrap();
+    let missing = cache.get_cached(&"missing".into()).await;
+    let none = cache.get_cached(&"none".into()).await;
+    let negative = cache.get_cached(&"negative".into()).await;
+
+    // Assert
+    assert_eq!(Ok(Cached::Miss), missing);
+    assert_eq!(Ok(Cached::Present(None)), none);
+    assert_eq!(Ok(Cached::NegativeHit), negative);
+}
+
+#[actix_rt::test]
+async fn move_entry() {
+    // Arrange
+    let engine = engine!();
+    let queue = engine
+        .cache::<String, String>("move_entry_queue")
+        .await
+        .unwrap();
+    let active = engine
+        .cache::<String, String>("move_entry_active")
+        .await
+        .unwrap();
+    let key = "key".to_string();
+    let expected = Some("expected".to_string());
+    queue
+        .put(
+            key.clone(),
+            expected.clone().unwrap(),
+            Duration::from_secs(32),
+        )
+        .await
+        .unwrap();
+
+    // Act
+    let moved = engine
+        .move_entry::<String, String>(
+            "move_entry_queue",
+            "move_entry_active",
+            &key,
+            Duration::from_secs(32),
+        )
+        .await;
+    let missing = engine
+        .move_entry::<String, String>(
+            "move_entry_queue",
+            "move_entry_active",
+            &key,
+            Duration::from_secs(32),
+        )
+        .await;
+
+    // Assert
+    assert_eq!(Ok(expected.clone()), moved);
+    assert_eq!(Ok(None), missing);
+    assert_eq!(Ok(None), queue.get(&key).await);
+    assert_eq!(Ok(expected), active.get(&key).await);
+}
+
 #[actix_rt::test]
 async fn put_get_multiple() {
     // Arrange
@@ -98,6 +282,70 @@ async fn put_get_expired() {
     assert_eq!(Ok(expected), actual);
 }
 
+#[actix_rt::test]
+async fn touch_none() {
+    // Arrange
+    let name = "touch_none".to_string();
+    let engine = engine!();
+    let cache = engine.cache::<String, String>(&name).await.unwrap();
+    let key = "unknown".to_string();
+    let expected = None;
+
+    // Act
+    let actual = cache.touch(&key, Duration::from_secs(32)).await;
+
+    // Assert
+    assert_eq!(Ok(expected), actual);
+}
+
+#[actix_rt::test]
+async fn put_touch_get() {
+    // Arrange
+    let name = "put_touch_get".to_string();
+    let engine = engine!();
+    let cache = engine.cache::<String, String>(&name).await.unwrap();
+    let key = "key".to_string();
+    let expected = Some("expected".to_string());
+
+    // Act
+    cache
+        .put(
+            key.clone(),
+            expected.clone().unwrap(),
+            Duration::from_secs(1),
+        )
+        .await
+        .unwrap();
+    let actual1 = cache.touch(&key, Duration::from_secs(32)).await;
+    sleep(Duration::from_millis(1500));
+    let actual2 = cache.get(&key).await;
+
+    // Assert
+    assert_eq!(Ok(expected.clone()), actual1);
+    assert_eq!(Ok(expected), actual2);
+}
+
+#[actix_rt::test]
+async fn put_touch_expired() {
+    // Arrange
+    let name = "put_touch_expired".to_string();
+    let engine = engine!();
+    let cache = engine.cache::<String, String>(&name).await.unwrap();
+    let key = "key".to_string();
+    let expected = None;
+
+    // Act
+    cache
+        .put(key.clone(), "value".to_string(), Duration::from_secs(1))
+        .await
+        .unwrap();
+    sleep(Duration::from_millis(1500));
+    let actual = cache.touch(&key, Duration::from_secs(32)).await;
+
+    // Assert
+    assert_eq!(Ok(expected), actual);
+}
+
 #[actix_rt::test]
 async fn pop_none() {
     // Arrange
@@ -230,3 +478,331 @@ async fn put_replace_expired() {
     assert_eq!(Ok(expected1), actual1);
     assert_eq!(Ok(expected2), actual2);
 }
+
+#[actix_rt::test]
+async fn entry_and_modify_none() {
+    // Arrange
+    let name = "entry_and_modify_none".to_string();
+    let engine = engine!();
+    let cache = engine.cache::<String, u32>(&name).await.unwrap();
+    let key = "unknown".to_string();
+
+    // Act
+    let modified = cache.entry(key.clone()).and_modify(|v| *v += 1).await;
+    let actual = cache.get(&key).await;
+
+    // Assert
+    assert_eq!(Ok(None), modified);
+    assert_eq!(Ok(None), actual);
+}
+
+#[actix_rt::test]
+async fn entry_or_insert_and_modify() {
+    // Arrange
+    let name = "entry_or_insert_and_modify".to_string();
+    let engine = engine!();
+    let cache = engine.cache::<String, u32>(&name).await.unwrap();
+    let key = "key".to_string();
+
+    // Act
+    let inserted = cache
+        .entry(key.clone())
+        .or_insert(1, Duration::from_secs(32))
+        .await;
+    let existing = cache
+        .entry(key.clone())
+        .or_insert(5, Duration::from_secs(32))
+        .await;
+    let modified = cache.entry(key.clone()).and_modify(|v| *v += 1).await;
+    let actual = cache.get(&key).await;
+
+    // Assert
+    assert_eq!(Ok(1), inserted);
+    assert_eq!(Ok(1), existing);
+    assert_eq!(Ok(Some(2)), modified);
+    assert_eq!(Ok(Some(2)), actual);
+}
+
+#[actix_rt::test]
+async fn entry_and_modify_keeps_ttl() {
+    // Arrange
+    let name = "entry_and_modify_keeps_ttl".to_string();
+    let engine = engine!();
+    let cache = engine.cache::<String, u32>(&name).await.unwrap();
+    let key = "key".to_string();
+
+    // Act
+    cache
+        .put(key.clone(), 1, Duration::from_secs(1))
+        .await
+        .unwrap();
+    cache
+        .entry(key.clone())
+        .and_modify(|v| *v += 1)
+        .await
+        .unwrap();
+    sleep(Duration::from_millis(1500));
+    let actual = cache.get(&key).await;
+
+    // Assert
+    assert_eq!(Ok(None), actual);
+}
+
+#[actix_rt::test]
+async fn rate_limiter_check() {
+    // Arrange
+    let name = "rate_limiter_check".to_string();
+    let engine = engine!();
+    let cache = engine.cache::<String, Window>(&name).await.unwrap();
+    let key = "key".to_string();
+    cache.pop(&key).await.unwrap();
+    let limiter = RateLimiter::new(cache);
+    let window = Duration::from_secs(32);
+
+    // Act
+    let first = limiter.check(&key, 2, window).await.unwrap();
+    let second = limiter.check(&key, 2, window).await.unwrap();
+    let third = limiter.check(&key, 2, window).await.unwrap();
+
+    // Assert
+    assert!(first.allowed);
+    assert_eq!(1, first.remaining);
+    assert!(second.allowed);
+    assert_eq!(0, second.remaining);
+    assert!(!third.allowed);
+    assert_eq!(0, third.remaining);
+    assert!(third.reset <= window);
+}
+
+#[actix_rt::test]
+async fn rate_limiter_reset() {
+    // Arrange
+    let name = "rate_limiter_reset".to_string();
+    let engine = engine!();
+    let cache = engine.cache::<String, Window>(&name).await.unwrap();
+    let key = "key".to_string();
+    cache.pop(&key).await.unwrap();
+    let limiter = RateLimiter::new(cache);
+    let window = Duration::from_secs(1);
+
+    // Act
+    limiter.check(&key, 1, window).await.unwrap();
+    let denied = limiter.check(&key, 1, window).await.unwrap();
+    sleep(Duration::from_millis(1500));
+    let allowed = limiter.check(&key, 1, window).await.unwrap();
+
+    // Assert
+    assert!(!denied.allowed);
+    assert!(allowed.allowed);
+}
+
+#[actix_rt::test]
+async fn once_stores_result() {
+    // Arrange
+    let name = "once_stores_result".to_string();
+    let engine = engine!();
+    let cache = engine
+        .cache::<String, Idempotent<u32>>(&name)
+        .await
+        .unwrap();
+    let key = "key".to_string();
+    cache.pop(&key).await.unwrap();
+    let ttl = Duration::from_secs(32);
+
+    // Act
+    let first = cache.once(&key, ttl, || async { 1 }).await;
+    let second = cache.once(&key, ttl, || async { 2 }).await;
+
+    // Assert
+    assert_eq!(Ok(1), first);
+    assert_eq!(Ok(1), second);
+}
+
+#[actix_rt::test]
+async fn once_in_progress() {
+    // Arrange
+    let name = "once_in_progress".to_string();
+    let engine = engine!();
+    let cache = engine
+        .cache::<String, Idempotent<u32>>(&name)
+        .await
+        .unwrap();
+    let key = "key".to_string();
+    let ttl = Duration::from_secs(32);
+    cache
+        .put(key.clone(), Idempotent::Pending, ttl)
+        .await
+        .unwrap();
+
+    // Act
+    let actual = cache.once(&key, ttl, || async { 1 }).await;
+
+    // Assert
+    assert_eq!(Err(Error::InProgress), actual);
+}
+
+#[actix_rt::test]
+async fn put_default_get() {
+    // Arrange
+    let name = "put_default_get".to_string();
+    let engine = engine!();
+    let cache = engine
+        .cache_with_config::<String, String>(
+            &name,
+            CacheConfig {
+                default_ttl: Some(Duration::from_secs(1)),
+            },
+        )
+        .await
+        .unwrap();
+    let key = "key".to_string();
+    let expected = Some("expected".to_string());
+
+    // Act
+    cache
+        .put_default(key.clone(), expected.clone().unwrap())
+        .await
+        .unwrap();
+    let actual = cache.get(&key).await;
+    sleep(Duration::from_millis(1500));
+    let expired = cache.get(&key).await;
+
+    // Assert
+    assert_eq!(Ok(expected), actual);
+    assert_eq!(Ok(None), expired);
+}
+
+#[actix_rt::test]
+async fn put_default_unconfigured() {
+    // Arrange
+    let name = "put_default_unconfigured".to_string();
+    let engine = engine!();
+    let cache = engine.cache::<String, String>(&name).await.unwrap();
+
+    // Act
+    let actual = cache
+        .put_default("key".to_string(), "value".to_string())
+        .await;
+
+    // Assert
+    assert_eq!(
+        Err(Error::Unsupported(
+            "no default time-to-live configured".into()
+        )),
+        actual,
+    );
+}
+
+#[actix_rt::test]
+async fn put_keys() {
+    // Arrange
+    let name = "put_keys".to_string();
+    let engine = engine!();
+    let cache = engine.cache::<String, String>(&name).await.unwrap();
+    let other = engine
+        .cache::<String, String>("put_keys_other")
+        .await
+        .unwrap();
+    let expected = vec!["key1".to_string(), "key2".to_string()];
+
+    // Act
+    for key in &expected {
+        cache
+            .put(key.clone(), "value".to_string(), Duration::from_secs(32))
+            .await
+            .unwrap();
+    }
+    cache
+        .put(
+            "expired".to_string(),
+            "value".to_string(),
+            Duration::from_secs(1),
+        )
+        .await
+        .unwrap();
+    other
+        .put(
+            "other".to_string(),
+            "value".to_string(),
+            Duration::from_secs(32),
+        )
+        .await
+        .unwrap();
+    sleep(Duration::from_millis(1500));
+    let actual = cache.keys().await.map(|mut keys| {
+        keys.sort();
+        keys
+    });
+
+    // Assert
+    assert_eq!(Ok(expected), actual);
+}
+
+#[actix_rt::test]
+async fn health_check() {
+    // Arrange
+    let engine = engine!();
+
+    // Act
+    let actual = engine.health_check().await;
+
+    // Assert
+    assert_eq!(Ok(()), actual);
+}
+
+#[actix_rt::test]
+async fn key_bytes_encoding() {
+    // Arrange
+    #[derive(
+        Clone, Debug, Eq, Hash, PartialEq, serde::Deserialize, serde::Serialize,
+    )]
+    struct UserId(u64);
+    #[derive(
+        Clone, Debug, Eq, Hash, PartialEq, serde::Deserialize, serde::Serialize,
+    )]
+    struct Named {
+        id: u64,
+    }
+    let name = "key_bytes_encoding".to_string();
+    let engine = engine!();
+    let strings = engine.cache::<String, String>(&name).await.unwrap();
+    let integers = engine.cache::<u64, String>(&name).await.unwrap();
+    let newtypes = engine.cache::<UserId, String>(&name).await.unwrap();
+    let structs = engine.cache::<Named, String>(&name).await.unwrap();
+
+    // Act
+    let string = strings.key_bytes(&"ab".to_string());
+    let integer = integers.key_bytes(&1);
+    let newtype = newtypes.key_bytes(&UserId(1));
+    let named = structs.key_bytes(&Named { id: 1 });
+
+    // Assert
+    assert_eq!(Ok(vec![0x62, b'a', b'b']), string);
+    assert_eq!(Ok(vec![0x01]), integer);
+    assert_eq!(integer, newtype);
+    assert_eq!(Ok(vec![0xa1, 0x62, b'i', b'd', 0x01]), named);
+}
+
+#[actix_rt::test]
+async fn put_scan() {
+    // Arrange
+    let name = "put_scan".to_string();
+    let engine = engine!();
+    let cache = engine.cache::<String, String>(&name).await.unwrap();
+    let expected = vec!["user:42:a".to_string(), "user:42:b".to_string()];
+
+    // Act
+    for key in expected.iter().chain(&["user:43:a".to_string()]) {
+        cache
+            .put(key.clone(), "value".to_string(), Duration::from_secs(32))
+            .await
+            .unwrap();
+    }
+    let actual = cache.scan("*user:42:*").await.map(|mut keys| {
+        keys.sort();
+        keys
+    });
+
+    // Assert
+    assert_eq!(Ok(expected), actual);
+}
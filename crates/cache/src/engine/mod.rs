@@ -3,8 +3,11 @@
 //! This module contains the [`Engine`](Engine) used to construct actual cache
 //! instances. An engine is created from a configuration instance.
 
+use std::time::Duration;
+
 use crate::{
-    configuration, Cache, CacheProducer, Configuration, Error, Key, Name, Value,
+    configuration, Cache, CacheConfig, CacheProducer, Configuration, Error,
+    Key, Name, Value,
 };
 
 pub mod backends;
@@ -43,6 +46,109 @@ impl Engine {
             Engine::Redis(engine) => engine.cache(name).await,
         }
     }
+
+    /// Attempts to create a cache with specific options.
+    ///
+    /// # Arguments
+    /// *  `name` - The cache name.
+    /// *  `config` - The options for the cache.
+    pub async fn cache_with_config<K, V>(
+        &self,
+        name: impl Name,
+        config: CacheConfig,
+    ) -> Result<Box<dyn Cache<K, V>>, Error>
+    where
+        K: Key,
+        V: Value,
+    {
+        match self {
+            #[cfg(feature = "local")]
+            Engine::Local(engine) => {
+                engine.cache_with_config(name, config).await
+            }
+
+            #[cfg(feature = "redis")]
+            Engine::Redis(engine) => {
+                engine.cache_with_config(name, config).await
+            }
+        }
+    }
+
+    /// Registers a callback invoked when expired values are removed from
+    /// caches created by this engine.
+    ///
+    /// Only [local engines](backends::local::Engine) support this; other
+    /// engines are returned unchanged.
+    ///
+    /// # Arguments
+    /// *  `callback` - The callback, receiving the serialised key.
+    #[cfg(feature = "local")]
+    pub fn with_eviction_callback(
+        self,
+        callback: backends::local::EvictionCallback,
+    ) -> Self {
+        match self {
+            Engine::Local(engine) => {
+                Engine::Local(engine.with_eviction_callback(callback))
+            }
+
+            #[cfg(feature = "redis")]
+            Engine::Redis(engine) => Engine::Redis(engine),
+        }
+    }
+
+    /// Atomically moves a value from one cache to another.
+    ///
+    /// The value is removed from the source cache and written to the
+    /// destination cache with a new time-to-live, without any other client
+    /// observing the value in both caches, or in neither. This makes it
+    /// possible to, for example, move work items from a queue to an
+    /// in-progress set without losing them on a crash.
+    ///
+    /// The moved value is returned, or `None` if the source cache had no
+    /// value under the key.
+    ///
+    /// # Arguments
+    /// *  `from` - The name of the source cache.
+    /// *  `to` - The name of the destination cache.
+    /// *  `key` - The key of the value to move.
+    /// *  `ttl` - The time-to-live for the value in the destination cache.
+    pub async fn move_entry<K, V>(
+        &self,
+        from: impl Name,
+        to: impl Name,
+        key: &K,
+        ttl: Duration,
+    ) -> Result<Option<V>, Error>
+    where
+        K: Key,
+        V: Value,
+    {
+        match self {
+            #[cfg(feature = "local")]
+            Engine::Local(engine) => {
+                engine.move_entry(from, to, key, ttl).await
+            }
+
+            #[cfg(feature = "redis")]
+            Engine::Redis(engine) => {
+                engine.move_entry(from, to, key, ttl).await
+            }
+        }
+    }
+
+    /// Verifies that the backend of this engine is reachable.
+    ///
+    /// This is intended for readiness probes, and is cheap to call.
+    pub async fn health_check(&self) -> Result<(), Error> {
+        match self {
+            #[cfg(feature = "local")]
+            Engine::Local(engine) => engine.health_check().await,
+
+            #[cfg(feature = "redis")]
+            Engine::Redis(engine) => engine.health_check().await,
+        }
+    }
 }
 
 impl Configuration {
@@ -56,4 +162,19 @@ impl Configuration {
             Configuration::Redis(c) => c.engine().await,
         }
     }
+
+    /// Verifies this configuration without connecting to any server.
+    ///
+    /// This is intended to be called at startup, and returns all problems
+    /// found rather than only the first one. It does not need to be called
+    /// before [`engine`](Self::engine).
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        match self {
+            #[cfg(feature = "local")]
+            Configuration::Local(c) => c.validate(),
+
+            #[cfg(feature = "redis")]
+            Configuration::Redis(c) => c.validate(),
+        }
+    }
 }
@@ -12,6 +12,38 @@ pub enum Error {
     /// A key or a value could not be encoded or decoded.
     #[error("encoding/decoding a key or value failed: {0}")]
     Encoding(String),
+
+    /// An operation did not complete in time.
+    #[error("operation timed out")]
+    Timeout,
+
+    /// An operation is not supported by the cache as configured.
+    #[error("operation not supported: {0}")]
+    Unsupported(String),
+
+    /// No connection became available in time, because all connections of
+    /// the pool were in use.
+    #[error("no connection available in pool")]
+    ConnectionPoolExhausted,
+
+    /// An operation guarded by an idempotency key is still running for
+    /// another caller.
+    #[error("operation in progress")]
+    InProgress,
+}
+
+impl Error {
+    /// Whether this error is caused by the backend being unavailable, rather
+    /// than by the operation itself.
+    ///
+    /// Operations failing with such errors may succeed if retried later, and
+    /// callers may choose to [fail open](crate::FailOpen) instead.
+    pub fn is_unavailable(&self) -> bool {
+        matches!(
+            self,
+            Self::Connection(_) | Self::Timeout | Self::ConnectionPoolExhausted,
+        )
+    }
 }
 
 #[cfg(feature = "local")]
@@ -58,13 +90,25 @@ pub mod redis {
         E: ::std::fmt::Display,
     {
         fn from(source: PoolError<E>) -> Self {
-            Self::Connection(source.to_string())
+            match source {
+                PoolError::Timeout => Self::ConnectionPoolExhausted,
+                source => Self::Connection(source.to_string()),
+            }
         }
     }
 
     impl From<RedisError> for super::Error {
         fn from(source: RedisError) -> Self {
-            Self::ValueAccess(source.to_string())
+            if source.is_io_error()
+                || source.is_connection_dropped()
+                || source.is_connection_refusal()
+            {
+                Self::Connection(source.to_string())
+            } else if source.is_timeout() {
+                Self::Timeout
+            } else {
+                Self::ValueAccess(source.to_string())
+            }
         }
     }
 }
@@ -0,0 +1,106 @@
+//! # Idempotency keys
+//!
+//! Requests that are not idempotent can be retried safely by recording their
+//! results under an idempotency key: a replayed request then returns the
+//! recorded result instead of running again.
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use weru_cache::{CacheProducer, Engine, Error, Idempotency};
+//!
+//! async fn charge(engine: &Engine, key: String) -> Result<u64, Error> {
+//!     let cache = engine.cache("payments").await?;
+//!     cache
+//!         .once(&key, Duration::from_secs(24 * 60 * 60), || async {
+//!             // Perform the payment, and return its identifier
+//!             42
+//!         })
+//!         .await
+//! }
+//! ```
+
+use std::future::Future;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::{Cache, Error, Key, Value};
+
+/// The state of an operation guarded by an idempotency key, as stored in the
+/// cache.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Idempotent<R> {
+    /// The operation has been started, but has not yet completed.
+    Pending,
+
+    /// The operation has completed with a result.
+    Done(R),
+}
+
+/// Running operations at most once per idempotency key.
+///
+/// This is implemented for all caches storing [`Idempotent`] values,
+/// including boxed ones.
+///
+/// # Argument
+/// *  `K` - The type for keys.
+/// *  `R` - The type for results.
+#[async_trait]
+pub trait Idempotency<K, R>: Cache<K, Idempotent<R>>
+where
+    K: Key,
+    R: Value,
+{
+    /// Runs an operation unless it has already been run for a key.
+    ///
+    /// The first caller for a key atomically claims it, runs the operation
+    /// and stores its result; later callers receive the stored result
+    /// without running the operation. Callers arriving while the operation
+    /// is still running fail with [`Error::InProgress`].
+    ///
+    /// If the operation is cancelled, or storing its result fails, the key
+    /// remains claimed until `ttl` has passed.
+    ///
+    /// # Arguments
+    /// *  `key` - The idempotency key.
+    /// *  `ttl` - The time for which the key and the result are kept.
+    /// *  `f` - The function running the operation.
+    async fn once<F, Fut>(
+        &self,
+        key: &K,
+        ttl: Duration,
+        f: F,
+    ) -> Result<R, Error>
+    where
+        F: FnOnce() -> Fut + Send,
+        Fut: Future<Output = R> + Send,
+    {
+        let mut claimed = false;
+        let current = self
+            .update(key, &mut |current| {
+                claimed = current.is_none();
+                claimed.then_some((Idempotent::Pending, Some(ttl)))
+            })
+            .await?;
+
+        match current {
+            _ if claimed => {
+                let result = f().await;
+                self.put(key.clone(), Idempotent::Done(result.clone()), ttl)
+                    .await?;
+                Ok(result)
+            }
+            Some(Idempotent::Done(result)) => Ok(result),
+            Some(Idempotent::Pending) | None => Err(Error::InProgress),
+        }
+    }
+}
+
+impl<K, R, C> Idempotency<K, R> for C
+where
+    K: Key,
+    R: Value,
+    C: Cache<K, Idempotent<R>> + ?Sized,
+{
+}
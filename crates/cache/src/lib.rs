@@ -1,9 +1,21 @@
+#[cfg(feature = "actix")]
+pub mod actix;
+
+pub mod compression;
+pub use compression::Compression;
+
 pub mod configuration;
 pub use configuration::Configuration;
 
 pub mod engine;
 pub use engine::Engine;
 
+pub mod idempotency;
+pub use idempotency::{Idempotency, Idempotent};
+
+pub mod rate;
+pub use rate::{RateDecision, RateLimiter};
+
 mod error;
 pub use error::Error;
 
@@ -0,0 +1,135 @@
+//! # Rate limiting
+//!
+//! A [`RateLimiter`] counts requests per key in fixed windows, and denies
+//! requests once a limit has been reached within the current window:
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use weru_cache::{CacheProducer, Engine, Error, RateLimiter};
+//!
+//! async fn login(engine: &Engine, user: String) -> Result<bool, Error> {
+//!     let limiter = RateLimiter::new(engine.cache("logins").await?);
+//!     let decision = limiter.check(&user, 5, Duration::from_secs(60)).await?;
+//!     Ok(decision.allowed)
+//! }
+//! ```
+//!
+//! The count is updated with [`update`](crate::Cache::update), so it is
+//! correct across processes sharing a Redis cache. The start of a window is
+//! taken from the system clock of the process opening it.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Cache, Error, Key};
+
+/// The state of the current window for a key, as stored in the cache.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Window {
+    /// The number of requests allowed in this window.
+    count: u32,
+
+    /// The time at which this window ends, in milliseconds since the UNIX
+    /// epoch.
+    reset: u64,
+}
+
+/// The outcome of [`check`](RateLimiter::check).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RateDecision {
+    /// Whether the request is allowed.
+    pub allowed: bool,
+
+    /// The number of requests still allowed in the current window.
+    pub remaining: u32,
+
+    /// The time until the current window ends and the count is reset.
+    pub reset: Duration,
+}
+
+/// A rate limiter allowing a number of requests per key and window.
+///
+/// # Argument
+/// *  `K` - The type for keys.
+pub struct RateLimiter<K>
+where
+    K: Key,
+{
+    /// The cache holding the windows.
+    cache: Box<dyn Cache<K, Window>>,
+}
+
+impl<K> RateLimiter<K>
+where
+    K: Key,
+{
+    /// Creates a rate limiter storing its windows in a cache.
+    ///
+    /// # Arguments
+    /// *  `cache` - The cache holding the windows.
+    pub fn new(cache: Box<dyn Cache<K, Window>>) -> Self {
+        Self { cache }
+    }
+
+    /// Counts a request, and decides whether it is allowed.
+    ///
+    /// A window starts with the first request for a key, and lasts for
+    /// `window`. Denied requests are not counted. A `limit` of `0` denies all
+    /// requests without touching the cache.
+    ///
+    /// # Arguments
+    /// *  `key` - The key for which to count the request.
+    /// *  `limit` - The maximum number of requests allowed in a window.
+    /// *  `window` - The duration of a window.
+    pub async fn check(
+        &self,
+        key: &K,
+        limit: u32,
+        window: Duration,
+    ) -> Result<RateDecision, Error> {
+        if limit == 0 {
+            return Ok(RateDecision {
+                allowed: false,
+                remaining: 0,
+                reset: window,
+            });
+        }
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        let mut allowed = false;
+        let current = self
+            .cache
+            .update(key, &mut |current| match current {
+                Some(current) if current.reset > now => {
+                    allowed = current.count < limit;
+                    allowed.then(|| {
+                        let count = current.count + 1;
+                        (
+                            Window {
+                                count,
+                                reset: current.reset,
+                            },
+                            None,
+                        )
+                    })
+                }
+                _ => {
+                    allowed = true;
+                    let reset = now.saturating_add(window.as_millis() as u64);
+                    Some((Window { count: 1, reset }, Some(window)))
+                }
+            })
+            .await?
+            .ok_or_else(|| Error::ValueAccess("window disappeared".into()))?;
+
+        Ok(RateDecision {
+            allowed,
+            remaining: limit.saturating_sub(current.count),
+            reset: Duration::from_millis(current.reset.saturating_sub(now)),
+        })
+    }
+}
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+use std::future::Future;
 use std::time::Duration;
 
 use async_trait::async_trait;
@@ -5,12 +7,28 @@ use serde::{Deserialize, Serialize};
 
 use crate::Error;
 
+/// The time-to-live of values that never expire.
+///
+/// Passing this to [`put`](Cache::put), [`touch`](Cache::touch),
+/// [`expire`](Cache::expire) or [`replace`](Cache::replace) stores the value
+/// permanently. The local backend
+/// also treats any time-to-live too large to represent as never expiring.
+pub const NEVER_EXPIRES: Duration = Duration::MAX;
+
 /// A cache name.
 pub trait Name: Send + Sync + AsRef<str> {}
 
 impl<T> Name for T where T: Send + Sync + AsRef<str> {}
 
 /// A key in the cache.
+///
+/// Keys are serialised to [CBOR](https://cbor.io/) using their
+/// [`Serialize`] implementation, and two keys are considered equal by the
+/// cache only if their serialised forms are equal. Newtype structs, such as
+/// `struct UserId(u64)`, are encoded as their inner value, so `UserId(1)` and
+/// `1u64` have the same serialised form; structs with named fields are
+/// encoded as maps including the field names. Use
+/// [`key_bytes`](Cache::key_bytes) to inspect the serialised form of a key.
 pub trait Key:
     Clone
     + Eq
@@ -48,6 +66,16 @@ impl<T> Value for T where
 {
 }
 
+/// Options for a single cache.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CacheConfig {
+    /// The time-to-live used by [`put_default`](Cache::put_default).
+    ///
+    /// If this is not set, `put_default` fails, but values can still be
+    /// written with an explicit time-to-live.
+    pub default_ttl: Option<Duration>,
+}
+
 /// A key-value cache.
 ///
 /// # Argument
@@ -65,6 +93,55 @@ where
     /// *  `key` - The key to read.
     async fn get(&self, key: &K) -> Result<Option<V>, Error>;
 
+    /// Reads several values from the cache, keyed by their keys.
+    ///
+    /// This is the object safe form of [`get_map`](CacheMap::get_map), which
+    /// is more convenient to call. Backends able to read several values in
+    /// one request override this; by default the values are read one at a
+    /// time with [`get`](Cache::get).
+    ///
+    /// # Arguments
+    /// *  `keys` - The keys to read.
+    async fn get_many(&self, keys: Vec<K>) -> Result<HashMap<K, V>, Error> {
+        let mut values = HashMap::with_capacity(keys.len());
+        for key in keys {
+            if let Some(value) = self.get(&key).await? {
+                values.insert(key, value);
+            }
+        }
+        Ok(values)
+    }
+
+    /// Reads a value from the cache and resets its time-to-live.
+    ///
+    /// If no value exists under the specified key, nothing is modified.
+    ///
+    /// # Arguments
+    /// *  `key` - The key to read.
+    /// *  `ttl` - The new time-to-live for the value. Pass
+    ///    [`NEVER_EXPIRES`] to make the value permanent.
+    async fn touch(&self, key: &K, ttl: Duration) -> Result<Option<V>, Error>;
+
+    /// Removes the expiry of a value, making it permanent.
+    ///
+    /// The value itself is not rewritten. This returns whether a value exists
+    /// under the specified key.
+    ///
+    /// # Arguments
+    /// *  `key` - The key of the value.
+    async fn persist(&self, key: &K) -> Result<bool, Error>;
+
+    /// Sets a new time-to-live for a value.
+    ///
+    /// The value itself is not rewritten. This returns whether a value exists
+    /// under the specified key.
+    ///
+    /// # Arguments
+    /// *  `key` - The key of the value.
+    /// *  `ttl` - The new time-to-live for the value. Pass
+    ///    [`NEVER_EXPIRES`] to make the value permanent.
+    async fn expire(&self, key: &K, ttl: Duration) -> Result<bool, Error>;
+
     /// Pops a value from the cache.
     ///
     /// # Arguments
@@ -76,9 +153,75 @@ where
     /// # Arguments
     /// *  `key` - The key to write.
     /// *  `value` - The value to write.
-    /// *  `ttl` - The time-to-live for the value.
+    /// *  `ttl` - The time-to-live for the value. Pass [`NEVER_EXPIRES`] to
+    ///    store the value permanently.
     async fn put(&self, key: K, value: V, ttl: Duration) -> Result<(), Error>;
 
+    /// Reads a value written with [`put_swr`](Cache::put_swr), and whether
+    /// it is stale.
+    ///
+    /// A value is stale once its fresh window has passed, but it is still
+    /// returned until its stale window has passed as well. Callers would
+    /// typically serve a stale value and refresh it in the background. Values
+    /// written by other methods are never stale.
+    ///
+    /// This is only supported by the local backend; other backends return
+    /// [`Error::Unsupported`].
+    ///
+    /// # Arguments
+    /// *  `key` - The key to read.
+    async fn get_swr(&self, key: &K) -> Result<Option<(V, bool)>, Error> {
+        let _ = key;
+        Err(Error::Unsupported(
+            "stale values are not supported by this cache".into(),
+        ))
+    }
+
+    /// Writes a value to the cache that becomes stale before it expires.
+    ///
+    /// The value expires once both windows have passed. Please see
+    /// [`get_swr`](Cache::get_swr) for details.
+    ///
+    /// This is only supported by the local backend; other backends return
+    /// [`Error::Unsupported`].
+    ///
+    /// # Arguments
+    /// *  `key` - The key to write.
+    /// *  `value` - The value to write.
+    /// *  `fresh` - The duration during which the value is fresh.
+    /// *  `stale` - The duration after `fresh` during which the value is
+    ///    stale.
+    async fn put_swr(
+        &self,
+        key: K,
+        value: V,
+        fresh: Duration,
+        stale: Duration,
+    ) -> Result<(), Error> {
+        let _ = (key, value, fresh, stale);
+        Err(Error::Unsupported(
+            "stale values are not supported by this cache".into(),
+        ))
+    }
+
+    /// Writes a value to the cache with the default time-to-live of this
+    /// cache.
+    ///
+    /// This fails with [`Error::Unsupported`] unless a default time-to-live
+    /// was configured when the cache was created.
+    ///
+    /// # Arguments
+    /// *  `key` - The key to write.
+    /// *  `value` - The value to write.
+    async fn put_default(&self, key: K, value: V) -> Result<(), Error> {
+        match self.config().default_ttl {
+            Some(ttl) => self.put(key, value, ttl).await,
+            None => Err(Error::Unsupported(
+                "no default time-to-live configured".into(),
+            )),
+        }
+    }
+
     /// Replaces a value in the cache.
     ///
     /// If not value exists under the specific key, none will be written.
@@ -94,6 +237,370 @@ where
         value: V,
         ttl: Option<Duration>,
     ) -> Result<Option<V>, Error>;
+
+    /// Atomically reads and conditionally rewrites a value.
+    ///
+    /// The function receives the current value, if any, and returns the
+    /// value to write along with its time-to-live, or `None` to leave the
+    /// cache unchanged. A time-to-live of `None` keeps the expiry of the
+    /// current value. This returns the value under the key once the update
+    /// is complete.
+    ///
+    /// The local backend holds the lock of the cache while calling the
+    /// function, so it must not access the cache. The Redis backend uses
+    /// `WATCH` and `MULTI`, and calls the function again if the value was
+    /// modified concurrently.
+    ///
+    /// Most callers would use an [`Entry`] instead.
+    ///
+    /// # Arguments
+    /// *  `key` - The key to update.
+    /// *  `f` - The function calculating the new value.
+    async fn update(
+        &self,
+        key: &K,
+        f: &mut Updater<'_, V>,
+    ) -> Result<Option<V>, Error>;
+
+    /// Lists the keys of all values in the cache.
+    ///
+    /// Expired values are excluded. This operation is _O(n)_ in the number of
+    /// values, and is intended for administrative tooling rather than hot
+    /// paths. The order of the keys is unspecified; in particular, the Redis
+    /// backend may return keys in arbitrary order.
+    async fn keys(&self) -> Result<Vec<K>, Error>;
+
+    /// Lists the keys of all values in the cache matching a glob pattern.
+    ///
+    /// The pattern is matched against the [serialised form](Cache::key_bytes)
+    /// of the keys, not their textual representation, using the glob syntax
+    /// of Redis: `*` matches any sequence, `?` matches a single byte, `[...]`
+    /// matches a set of bytes and `\` escapes the following byte. Since the
+    /// serialised form of a string is prefixed with its length, a pattern
+    /// matching strings by prefix must begin with a wildcard, such as
+    /// `"*user:42:*"`.
+    ///
+    /// Like [`keys`](Cache::keys), this operation is _O(n)_ in the number of
+    /// values.
+    ///
+    /// # Arguments
+    /// *  `pattern` - The pattern to match.
+    async fn scan(&self, pattern: &str) -> Result<Vec<K>, Error>;
+
+    /// Serialises a key the way this cache does when accessing values.
+    ///
+    /// This is the CBOR encoding of the key, without any prefix added by the
+    /// backend.
+    ///
+    /// # Arguments
+    /// *  `key` - The key to serialise.
+    fn key_bytes(&self, key: &K) -> Result<Vec<u8>, Error>;
+
+    /// The options for this cache.
+    fn config(&self) -> &CacheConfig;
+}
+
+/// Reading several values from a cache at once.
+///
+/// This is implemented for all caches, including boxed ones.
+///
+/// # Argument
+/// *  `K` - The type for keys.
+/// *  `V` - The type for values.
+#[async_trait]
+pub trait CacheMap<K, V>: Cache<K, V>
+where
+    K: Key,
+    V: Value,
+{
+    /// Reads several values from the cache, keyed by their keys.
+    ///
+    /// Keys without a value are omitted from the map. The _Redis_ backend
+    /// reads all values in one request, while other backends read them one
+    /// at a time, so this is not atomic in general.
+    ///
+    /// # Arguments
+    /// *  `keys` - The keys to read.
+    async fn get_map<I>(&self, keys: I) -> Result<HashMap<K, V>, Error>
+    where
+        I: IntoIterator<Item = K> + Send,
+    {
+        let keys = keys.into_iter().collect();
+        self.get_many(keys).await
+    }
+}
+
+impl<K, V, C> CacheMap<K, V> for C
+where
+    K: Key,
+    V: Value,
+    C: Cache<K, V> + ?Sized,
+{
+}
+
+/// The result of reading a cache used for negative caching.
+///
+/// Caching `Option<V>` directly cannot distinguish a cached `None` from a
+/// cache miss when `V` itself serialises to nothing, such as `()` or another
+/// `Option`. This type is serialised as a tagged enum, so a stored negative
+/// result always round-trips distinctly from a stored value.
+///
+/// Use it as the value type of a cache, and access the cache through
+/// [`NegativeCache`].
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Cached<V> {
+    /// No value is cached.
+    ///
+    /// Storing this explicitly is possible, but it is read back as a miss.
+    Miss,
+
+    /// A value is cached.
+    Present(V),
+
+    /// The absence of a value is cached.
+    NegativeHit,
+}
+
+impl<V> Cached<V> {
+    /// Whether the cache must be consulted for a value.
+    pub fn is_miss(&self) -> bool {
+        matches!(self, Self::Miss)
+    }
+}
+
+impl<V> From<Cached<V>> for Option<V> {
+    fn from(source: Cached<V>) -> Self {
+        match source {
+            Cached::Present(value) => Some(value),
+            Cached::Miss | Cached::NegativeHit => None,
+        }
+    }
+}
+
+/// Negative caching for caches storing [`Cached`] values.
+///
+/// This is implemented for all caches, including boxed ones.
+///
+/// # Argument
+/// *  `K` - The type for keys.
+/// *  `V` - The type for values.
+#[async_trait]
+pub trait NegativeCache<K, V>: Cache<K, Cached<V>>
+where
+    K: Key,
+    V: Value,
+{
+    /// Reads a value from the cache, distinguishing a miss from a cached
+    /// absence of a value.
+    ///
+    /// # Arguments
+    /// *  `key` - The key to read.
+    async fn get_cached(&self, key: &K) -> Result<Cached<V>, Error> {
+        Ok(self.get(key).await?.unwrap_or(Cached::Miss))
+    }
+
+    /// Writes a value to the cache.
+    ///
+    /// # Arguments
+    /// *  `key` - The key to write.
+    /// *  `value` - The value to write.
+    /// *  `ttl` - The time-to-live for the value.
+    async fn put_present(
+        &self,
+        key: K,
+        value: V,
+        ttl: Duration,
+    ) -> Result<(), Error> {
+        self.put(key, Cached::Present(value), ttl).await
+    }
+
+    /// Records that no value exists for a key.
+    ///
+    /// # Arguments
+    /// *  `key` - The key to write.
+    /// *  `ttl` - The time-to-live for the negative result.
+    async fn put_negative(&self, key: K, ttl: Duration) -> Result<(), Error> {
+        self.put(key, Cached::NegativeHit, ttl).await
+    }
+}
+
+impl<K, V, C> NegativeCache<K, V> for C
+where
+    K: Key,
+    V: Value,
+    C: Cache<K, Cached<V>> + ?Sized,
+{
+}
+
+/// Reading from caches that may be unavailable, without failing.
+///
+/// This is implemented for all caches, including boxed ones.
+///
+/// # Argument
+/// *  `K` - The type for keys.
+/// *  `V` - The type for values.
+#[async_trait]
+pub trait FailOpen<K, V>: Cache<K, V>
+where
+    K: Key,
+    V: Value,
+{
+    /// Reads a value from the cache, or from a fallback if the cache is
+    /// unavailable.
+    ///
+    /// The fallback is used only if reading fails with an error for which
+    /// [`is_unavailable`](Error::is_unavailable) holds, so an outage of the
+    /// backend degrades performance rather than breaking callers. Other
+    /// errors, such as values failing to decode, are returned as usual.
+    ///
+    /// # Arguments
+    /// *  `key` - The key to read.
+    /// *  `fallback` - The function loading the value from its source.
+    async fn get_or<F, R>(
+        &self,
+        key: &K,
+        fallback: F,
+    ) -> Result<Option<V>, Error>
+    where
+        F: FnOnce() -> R + Send,
+        R: Future<Output = Option<V>> + Send,
+    {
+        match self.get(key).await {
+            Err(e) if e.is_unavailable() => Ok(fallback().await),
+            result => result,
+        }
+    }
+}
+
+impl<K, V, C> FailOpen<K, V> for C
+where
+    K: Key,
+    V: Value,
+    C: Cache<K, V> + ?Sized,
+{
+}
+
+/// A function calculating the new value in [`update`](Cache::update).
+pub type Updater<'a, V> =
+    dyn FnMut(Option<&V>) -> Option<(V, Option<Duration>)> + Send + 'a;
+
+/// A handle to a single value in a cache, for read-modify-write operations.
+///
+/// This mirrors the entry API of [`HashMap`](std::collections::HashMap), but
+/// every operation is applied atomically using [`update`](Cache::update), so
+/// concurrent writers do not lose updates.
+///
+/// # Argument
+/// *  `K` - The type for keys.
+/// *  `V` - The type for values.
+/// *  `C` - The type of the cache.
+pub struct Entry<'a, K, V, C>
+where
+    C: ?Sized,
+{
+    /// The cache containing the value.
+    cache: &'a C,
+
+    /// The key of the value.
+    key: K,
+
+    _m: ::std::marker::PhantomData<V>,
+}
+
+impl<K, V, C> Entry<'_, K, V, C>
+where
+    K: Key,
+    V: Value,
+    C: Cache<K, V> + ?Sized,
+{
+    /// The key of this entry.
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Modifies the value of this entry, if it exists.
+    ///
+    /// The time-to-live of the value is kept. This returns the modified
+    /// value, or `None` if no value exists.
+    ///
+    /// The function may be called more than once if the value is modified
+    /// concurrently.
+    ///
+    /// # Arguments
+    /// *  `f` - The function modifying the value.
+    pub async fn and_modify<F>(self, mut f: F) -> Result<Option<V>, Error>
+    where
+        F: FnMut(&mut V) + Send,
+    {
+        self.cache
+            .update(&self.key, &mut |current| {
+                current.map(|value| {
+                    let mut value = value.clone();
+                    f(&mut value);
+                    (value, None)
+                })
+            })
+            .await
+    }
+
+    /// Writes a default value to this entry unless it already has one.
+    ///
+    /// This returns the value of this entry, which is `default` only if no
+    /// value existed.
+    ///
+    /// # Arguments
+    /// *  `default` - The value to write.
+    /// *  `ttl` - The time-to-live for the value, if written. Pass
+    ///    [`NEVER_EXPIRES`] to store the value permanently.
+    pub async fn or_insert(
+        self,
+        default: V,
+        ttl: Duration,
+    ) -> Result<V, Error> {
+        let value = self
+            .cache
+            .update(&self.key, &mut |current| match current {
+                Some(_) => None,
+                None => Some((default.clone(), Some(ttl))),
+            })
+            .await?;
+        Ok(value.unwrap_or(default))
+    }
+}
+
+/// Access to single values in a cache through an [`Entry`].
+///
+/// This is implemented for all caches, including boxed ones.
+///
+/// # Argument
+/// *  `K` - The type for keys.
+/// *  `V` - The type for values.
+pub trait CacheEntry<K, V>: Cache<K, V>
+where
+    K: Key,
+    V: Value,
+{
+    /// Creates a handle to the value under a key.
+    ///
+    /// Nothing is read until an operation is called on the entry.
+    ///
+    /// # Arguments
+    /// *  `key` - The key of the value.
+    fn entry(&self, key: K) -> Entry<'_, K, V, Self> {
+        Entry {
+            cache: self,
+            key,
+            _m: ::std::marker::PhantomData,
+        }
+    }
+}
+
+impl<K, V, C> CacheEntry<K, V> for C
+where
+    K: Key,
+    V: Value,
+    C: Cache<K, V> + ?Sized,
+{
 }
 
 /// A cache producing engine.
@@ -107,6 +614,23 @@ pub trait CacheProducer {
         &self,
         name: impl Name,
     ) -> Result<Box<dyn Cache<K, V>>, Error>
+    where
+        K: Key,
+        V: Value,
+    {
+        self.cache_with_config(name, CacheConfig::default()).await
+    }
+
+    /// Attempts to create a cache with specific options.
+    ///
+    /// # Arguments
+    /// *  `name` - The cache name.
+    /// *  `config` - The options for the cache.
+    async fn cache_with_config<K, V>(
+        &self,
+        name: impl Name,
+        config: CacheConfig,
+    ) -> Result<Box<dyn Cache<K, V>>, Error>
     where
         K: Key,
         V: Value;
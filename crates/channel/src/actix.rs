@@ -0,0 +1,415 @@
+//! # Integration with actix
+//!
+//! This module provides the [`Forwarder`](Forwarder) actor, which pipes the
+//! events of a channel to a connected WebSocket client:
+//!
+//! ```no_run
+//! use actix_web::{web, Error, HttpRequest, HttpResponse};
+//! use weru_channel::actix::Forwarder;
+//! use weru_channel::{ChannelProducer, Engine};
+//!
+//! async fn handler(
+//!     req: HttpRequest,
+//!     stream: web::Payload,
+//!     engine: web::Data<Engine>,
+//! ) -> Result<HttpResponse, Error> {
+//!     let channel = engine
+//!         .channel::<String>("greetings")
+//!         .await
+//!         .map_err(actix_web::error::ErrorInternalServerError)?;
+//!     Forwarder::new(channel).start(&req, stream)
+//! }
+//! ```
+//!
+//! Events are read with [`watch`](crate::Channel::watch), so the connection
+//! stays open until the client disconnects or the channel is closed.
+//!
+//! Every event is serialised as JSON and sent as a single frame. Events are
+//! buffered between the channel and the client; if a slow client lets the
+//! buffer fill up, the [`Overflow`](Overflow) policy decides whether new
+//! events are dropped or the connection is closed.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use actix::{Actor, ActorContext, AsyncContext, StreamHandler};
+use actix_web::error::PayloadError;
+use actix_web::web::Bytes;
+use actix_web::{HttpRequest, HttpResponse};
+use actix_web_actors::ws;
+use futures::channel::mpsc;
+use futures::stream::{BoxStream, Stream, StreamExt};
+use tokio::task::JoinHandle;
+
+use crate::{Channel, Error, Event};
+
+/// The default number of events buffered for a client.
+pub const DEFAULT_BUFFER_SIZE: usize = 64;
+
+/// The kind of frame used to send events.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Frame {
+    /// Events are sent as text frames.
+    #[default]
+    Text,
+
+    /// Events are sent as binary frames.
+    Binary,
+}
+
+/// What to do when the buffer for a client is full.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Overflow {
+    /// Events arriving while the buffer is full are dropped.
+    #[default]
+    Drop,
+
+    /// The connection is closed once the buffered events have been sent.
+    Close,
+}
+
+/// An actor forwarding the events of a channel to a WebSocket client.
+///
+/// The channel is subscribed to when the actor starts, and the subscription
+/// is released when the client disconnects. Messages from the client other
+/// than pings and close requests are ignored.
+///
+/// # Argument
+/// *  `T` - The type for events.
+pub struct Forwarder<T>
+where
+    T: Event,
+{
+    /// The channel whose events to forward.
+    channel: Option<Box<dyn Channel<T>>>,
+
+    /// The kind of frame used to send events.
+    frame: Frame,
+
+    /// The number of events buffered for the client.
+    buffer_size: usize,
+
+    /// What to do when the buffer is full.
+    overflow: Overflow,
+
+    /// Whether the buffer has overflowed with the policy
+    /// [`Overflow::Close`](Overflow::Close).
+    overflowed: Arc<AtomicBool>,
+
+    /// The task reading events from the channel.
+    task: Option<JoinHandle<()>>,
+}
+
+impl<T> Forwarder<T>
+where
+    T: Event,
+{
+    /// Creates a forwarder sending text frames and dropping events for slow
+    /// clients.
+    ///
+    /// # Arguments
+    /// *  `channel` - The channel whose events to forward.
+    pub fn new(channel: Box<dyn Channel<T>>) -> Self {
+        Self {
+            channel: Some(channel),
+            frame: Frame::default(),
+            buffer_size: DEFAULT_BUFFER_SIZE,
+            overflow: Overflow::default(),
+            overflowed: Arc::new(AtomicBool::new(false)),
+            task: None,
+        }
+    }
+
+    /// Sets the kind of frame used to send events.
+    ///
+    /// # Arguments
+    /// *  `frame` - The kind of frame.
+    pub fn frame(mut self, frame: Frame) -> Self {
+        self.frame = frame;
+        self
+    }
+
+    /// Sets the number of events buffered for the client.
+    ///
+    /// # Arguments
+    /// *  `buffer_size` - The number of events.
+    pub fn buffer_size(mut self, buffer_size: usize) -> Self {
+        self.buffer_size = buffer_size;
+        self
+    }
+
+    /// Sets what to do when the buffer for the client is full.
+    ///
+    /// # Arguments
+    /// *  `overflow` - The overflow policy.
+    pub fn overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    /// Performs the WebSocket handshake and starts forwarding events.
+    ///
+    /// # Arguments
+    /// *  `req` - The upgrade request.
+    /// *  `stream` - The request payload.
+    pub fn start<S>(
+        self,
+        req: &HttpRequest,
+        stream: S,
+    ) -> Result<HttpResponse, actix_web::Error>
+    where
+        S: Stream<Item = Result<Bytes, PayloadError>> + 'static,
+    {
+        ws::start(self, req, stream)
+    }
+
+    /// Sends an event to the client.
+    ///
+    /// # Arguments
+    /// *  `event` - The event to send.
+    /// *  `ctx` - The actor context.
+    fn send(&self, event: &T, ctx: &mut ws::WebsocketContext<Self>) {
+        let result = match self.frame {
+            Frame::Text => serde_json::to_string(event).map(|s| ctx.text(s)),
+            Frame::Binary => serde_json::to_vec(event).map(|v| ctx.binary(v)),
+        };
+        if result.is_err() {
+            ctx.close(Some(ws::CloseCode::Error.into()));
+            ctx.stop();
+        }
+    }
+}
+
+impl<T> Actor for Forwarder<T>
+where
+    T: Event,
+{
+    type Context = ws::WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        let (mut sender, receiver) = mpsc::channel(self.buffer_size);
+        if let Some(channel) = self.channel.take() {
+            let overflow = self.overflow;
+            let overflowed = self.overflowed.clone();
+            self.task = Some(tokio::spawn(async move {
+                match channel.watch().await {
+                    Ok(stream) => {
+                        if forward(stream, sender, overflow).await {
+                            overflowed.store(true, Ordering::Relaxed);
+                        }
+                    }
+                    Err(e) => {
+                        let _ = sender.try_send(Err(e));
+                    }
+                }
+            }));
+        }
+        ctx.add_stream(receiver);
+    }
+
+    fn stopped(&mut self, _ctx: &mut Self::Context) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+    }
+}
+
+impl<T> StreamHandler<Result<T, Error>> for Forwarder<T>
+where
+    T: Event,
+{
+    fn handle(&mut self, item: Result<T, Error>, ctx: &mut Self::Context) {
+        match item {
+            Ok(event) => self.send(&event, ctx),
+            Err(_) => {
+                ctx.close(Some(ws::CloseCode::Error.into()));
+                ctx.stop();
+            }
+        }
+    }
+
+    fn finished(&mut self, ctx: &mut Self::Context) {
+        let code = if self.overflowed.load(Ordering::Relaxed) {
+            ws::CloseCode::Policy
+        } else {
+            ws::CloseCode::Away
+        };
+        ctx.close(Some(code.into()));
+        ctx.stop();
+    }
+}
+
+impl<T> StreamHandler<Result<ws::Message, ws::ProtocolError>> for Forwarder<T>
+where
+    T: Event,
+{
+    fn handle(
+        &mut self,
+        item: Result<ws::Message, ws::ProtocolError>,
+        ctx: &mut Self::Context,
+    ) {
+        match item {
+            Ok(ws::Message::Ping(data)) => ctx.pong(&data),
+            Ok(ws::Message::Close(reason)) => {
+                ctx.close(reason);
+                ctx.stop();
+            }
+            Ok(_) => {}
+            Err(_) => ctx.stop(),
+        }
+    }
+
+    fn finished(&mut self, ctx: &mut Self::Context) {
+        ctx.stop();
+    }
+}
+
+/// Forwards events from a stream to a bounded buffer.
+///
+/// This function returns when the stream ends, when the receiving end of the
+/// buffer is dropped, or when the buffer is full and `overflow` is
+/// [`Overflow::Close`](Overflow::Close). The return value is whether the
+/// latter happened.
+///
+/// # Arguments
+/// *  `stream` - The stream of events.
+/// *  `sender` - The sending end of the buffer.
+/// *  `overflow` - What to do when the buffer is full.
+async fn forward<T>(
+    mut stream: BoxStream<'static, Result<T, Error>>,
+    mut sender: mpsc::Sender<Result<T, Error>>,
+    overflow: Overflow,
+) -> bool
+where
+    T: Event,
+{
+    while let Some(item) = stream.next().await {
+        match sender.try_send(item) {
+            Ok(()) => {}
+            Err(e) if e.is_disconnected() => return false,
+            Err(_) if overflow == Overflow::Drop => {}
+            Err(_) => return true,
+        }
+    }
+    false
+}
+
+#[cfg(all(test, feature = "local"))]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    use actix_web::body::to_bytes;
+    use actix_web::http::{header, StatusCode};
+    use actix_web::test::TestRequest;
+    use futures::stream;
+
+    use crate::engine::backends::local;
+
+    #[actix_rt::test]
+    async fn forwarder_stays_open() {
+        // Arrange
+        let engine = local::Configuration {
+            queue_size: 8,
+            replay: 0,
+        }
+        .engine()
+        .await
+        .unwrap();
+        let channel = engine.channel::<u32>("forwarder").await.unwrap();
+        let sender = engine.channel::<u32>("forwarder").await.unwrap();
+        let req = TestRequest::default()
+            .insert_header((header::UPGRADE, "websocket"))
+            .insert_header((header::CONNECTION, "upgrade"))
+            .insert_header((header::SEC_WEBSOCKET_VERSION, "13"))
+            .insert_header((
+                header::SEC_WEBSOCKET_KEY,
+                "dGhlIHNhbXBsZSBub25jZQ==",
+            ))
+            .to_http_request();
+        let payload = stream::pending::<Result<Bytes, PayloadError>>();
+
+        // Act
+        let response = Forwarder::new(channel).start(&req, payload).unwrap();
+        let status = response.status();
+        let body = actix_rt::spawn(to_bytes(response.into_body()));
+        actix_rt::time::sleep(Duration::from_millis(50)).await;
+        for i in 0..2 {
+            sender.broadcast(i).await.unwrap();
+        }
+        actix_rt::time::sleep(Duration::from_millis(50)).await;
+        engine.close_topic("forwarder").await;
+        let body = actix_rt::time::timeout(Duration::from_secs(5), body)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+
+        // Assert
+        assert_eq!(StatusCode::SWITCHING_PROTOCOLS, status);
+        assert_eq!(
+            &[
+                0x81, 0x01, b'0', // The first event
+                0x81, 0x01, b'1', // The second event
+                0x88, 0x02, 0x03, 0xE9, // Closed with the code Away
+            ][..],
+            &body[..],
+        );
+    }
+
+    #[actix_rt::test]
+    async fn forward_drop() {
+        // Arrange
+        let channel = channel(8).await;
+        let stream = channel.listen().await.unwrap().take(4).boxed();
+        let (sender, receiver) = mpsc::channel(1);
+        for i in 0..4 {
+            channel.broadcast(i).await.unwrap();
+        }
+
+        // Act
+        let overflowed = forward(stream, sender, Overflow::Drop).await;
+
+        // Assert
+        assert!(!overflowed);
+        let received = receiver.map(Result::unwrap).collect::<Vec<_>>().await;
+        assert_eq!(vec![0, 1], received);
+    }
+
+    #[actix_rt::test]
+    async fn forward_close() {
+        // Arrange
+        let channel = channel(8).await;
+        let stream = channel.listen().await.unwrap().take(4).boxed();
+        let (sender, receiver) = mpsc::channel(1);
+        for i in 0..4 {
+            channel.broadcast(i).await.unwrap();
+        }
+
+        // Act
+        let overflowed = forward(stream, sender, Overflow::Close).await;
+
+        // Assert
+        assert!(overflowed);
+        let received = receiver.map(Result::unwrap).collect::<Vec<_>>().await;
+        assert_eq!(vec![0, 1], received);
+    }
+
+    /// Creates a local channel.
+    ///
+    /// # Arguments
+    /// *  `queue_size` - The queue size for the channel.
+    async fn channel(queue_size: usize) -> Box<dyn Channel<u32>> {
+        local::Configuration {
+            queue_size,
+            replay: 0,
+        }
+        .engine()
+        .await
+        .unwrap()
+        .channel("topic")
+        .await
+        .unwrap()
+    }
+}
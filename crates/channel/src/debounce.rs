@@ -0,0 +1,313 @@
+//! # Debounced channels
+//!
+//! A [`Debounced`] channel wraps another channel and coalesces rapid
+//! broadcasts: only the most recent event broadcast within a window is
+//! forwarded to listeners.
+
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use futures::stream::BoxStream;
+
+use crate::{Channel, Envelope, Error, Event};
+
+/// An event waiting to be forwarded.
+enum Pending<T> {
+    /// An event broadcast with [`broadcast`](Channel::broadcast).
+    Plain(T),
+
+    /// An event broadcast with
+    /// [`broadcast_with_ttl`](Channel::broadcast_with_ttl), and the instant
+    /// at which it expires.
+    Expiring(T, Instant),
+
+    /// An event broadcast with
+    /// [`broadcast_enveloped`](Channel::broadcast_enveloped).
+    Enveloped(Envelope<T>),
+}
+
+impl<T> Pending<T>
+where
+    T: Event,
+{
+    /// Forwards this event to a channel.
+    ///
+    /// Events that have expired while pending are silently dropped.
+    ///
+    /// # Arguments
+    /// *  `channel` - The channel to which to forward the event.
+    async fn forward(self, channel: &dyn Channel<T>) -> Result<(), Error> {
+        match self {
+            Self::Plain(event) => channel.broadcast(event).await,
+            Self::Expiring(event, expires) => {
+                match expires.checked_duration_since(Instant::now()) {
+                    Some(ttl) if !ttl.is_zero() => {
+                        channel.broadcast_with_ttl(event, ttl).await
+                    }
+                    _ => Ok(()),
+                }
+            }
+            Self::Enveloped(envelope) => {
+                channel.broadcast_enveloped(envelope).await
+            }
+        }
+    }
+}
+
+/// The mutable state of a debounced channel.
+struct State<T> {
+    /// The most recent event not yet forwarded.
+    pending: Option<Pending<T>>,
+
+    /// The error of the last failed deferred broadcast.
+    failure: Option<Error>,
+
+    /// Whether the channel has been closed.
+    closed: bool,
+}
+
+/// A channel forwarding only the most recent event broadcast within a
+/// window.
+///
+/// The first broadcast in a window starts a timer; when it fires, the most
+/// recent event broadcast since is forwarded to the wrapped channel. Since
+/// broadcasts are deferred, a failure to forward an event is returned by the
+/// next call to [`broadcast`](Channel::broadcast) or
+/// [`close`](Debounced::close).
+///
+/// [`broadcast_counted`](Channel::broadcast_counted) cannot be deferred,
+/// since it must report the number of receivers. It replaces any pending
+/// event and is forwarded immediately.
+///
+/// Listening is not affected by debouncing.
+pub struct Debounced<T> {
+    /// The wrapped channel.
+    channel: Arc<dyn Channel<T>>,
+
+    /// The window during which events are coalesced.
+    window: Duration,
+
+    /// The mutable state.
+    state: Arc<Mutex<State<T>>>,
+}
+
+impl<T> Debounced<T>
+where
+    T: Event,
+{
+    /// Wraps a channel.
+    ///
+    /// # Arguments
+    /// *  `channel` - The channel to wrap.
+    /// *  `window` - The window during which events are coalesced.
+    pub fn new(channel: Box<dyn Channel<T>>, window: Duration) -> Self {
+        Self {
+            channel: Arc::from(channel),
+            window,
+            state: Arc::new(Mutex::new(State {
+                pending: None,
+                failure: None,
+                closed: false,
+            })),
+        }
+    }
+
+    /// The window during which events are coalesced.
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Closes this channel.
+    ///
+    /// Any pending event is forwarded immediately, so the final state is not
+    /// lost. Broadcasting on this channel afterwards fails with
+    /// [`Error::Connection`].
+    pub async fn close(&self) -> Result<(), Error> {
+        let (pending, failure) = {
+            let mut state = self.lock();
+            state.closed = true;
+            (state.pending.take(), state.failure.take())
+        };
+        if let Some(pending) = pending {
+            pending.forward(self.channel.as_ref()).await?;
+        }
+        failure.map_or(Ok(()), Err)
+    }
+
+    /// Replaces the pending event, and starts a timer unless one is running.
+    ///
+    /// # Arguments
+    /// *  `pending` - The new pending event.
+    fn defer(&self, pending: Pending<T>) -> Result<(), Error> {
+        let mut state = self.lock();
+        if state.closed {
+            return Err(Error::Connection("channel closed".into()));
+        }
+        if let Some(failure) = state.failure.take() {
+            return Err(failure);
+        }
+
+        if state.pending.replace(pending).is_none() {
+            let channel = Arc::clone(&self.channel);
+            let state = Arc::clone(&self.state);
+            let window = self.window;
+            tokio::spawn(async move {
+                tokio::time::sleep(window).await;
+                let pending = state
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .pending
+                    .take();
+                if let Some(pending) = pending {
+                    if let Err(e) = pending.forward(channel.as_ref()).await {
+                        state
+                            .lock()
+                            .unwrap_or_else(PoisonError::into_inner)
+                            .failure = Some(e);
+                    }
+                }
+            });
+        }
+        Ok(())
+    }
+
+    /// Locks the state of this channel.
+    fn lock(&self) -> ::std::sync::MutexGuard<'_, State<T>> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[async_trait]
+impl<T> Channel<T> for Debounced<T>
+where
+    T: Event,
+{
+    async fn broadcast(&self, event: T) -> Result<(), Error> {
+        self.defer(Pending::Plain(event))
+    }
+
+    async fn broadcast_counted(&self, event: T) -> Result<usize, Error> {
+        {
+            let mut state = self.lock();
+            if state.closed {
+                return Err(Error::Connection("channel closed".into()));
+            }
+            state.pending = None;
+        }
+        self.channel.broadcast_counted(event).await
+    }
+
+    async fn broadcast_with_ttl(
+        &self,
+        event: T,
+        ttl: Duration,
+    ) -> Result<(), Error> {
+        match Instant::now().checked_add(ttl) {
+            Some(expires) => self.defer(Pending::Expiring(event, expires)),
+            None => self.defer(Pending::Plain(event)),
+        }
+    }
+
+    async fn broadcast_enveloped(
+        &self,
+        envelope: Envelope<T>,
+    ) -> Result<(), Error> {
+        self.defer(Pending::Enveloped(envelope))
+    }
+
+    async fn listen(
+        &self,
+    ) -> Result<BoxStream<'static, Result<T, Error>>, Error> {
+        self.channel.listen().await
+    }
+
+    async fn watch(
+        &self,
+    ) -> Result<BoxStream<'static, Result<T, Error>>, Error> {
+        self.channel.watch().await
+    }
+
+    async fn listen_with_topic(
+        &self,
+    ) -> Result<BoxStream<'static, Result<(String, T), Error>>, Error> {
+        self.channel.listen_with_topic().await
+    }
+
+    async fn listen_enveloped(
+        &self,
+    ) -> Result<BoxStream<'static, Result<Envelope<T>, Error>>, Error> {
+        self.channel.listen_enveloped().await
+    }
+}
+
+#[cfg(all(test, feature = "local"))]
+mod tests {
+    use super::*;
+
+    use futures::StreamExt;
+
+    use crate::engine::backends::local;
+    use crate::Configuration;
+
+    #[actix_rt::test]
+    async fn coalesces_within_window() {
+        // Arrange
+        let engine = engine().await;
+        let channel = engine
+            .debounced_channel::<u32>("coalesces", Duration::from_millis(50))
+            .await
+            .unwrap();
+        let listener = channel.listen().await.unwrap();
+
+        // Act
+        for event in 1..=3 {
+            channel.broadcast(event).await.unwrap();
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        channel.broadcast(4).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        channel.close().await.unwrap();
+        let actual = listener
+            .take(2)
+            .map(Result::unwrap)
+            .collect::<Vec<_>>()
+            .await;
+
+        // Assert
+        assert_eq!(vec![3, 4], actual);
+    }
+
+    #[actix_rt::test]
+    async fn close_flushes_pending() {
+        // Arrange
+        let engine = engine().await;
+        let channel = engine
+            .debounced_channel::<u32>("flushes", Duration::from_secs(60))
+            .await
+            .unwrap();
+        let mut listener = channel.listen().await.unwrap();
+
+        // Act
+        channel.broadcast(1).await.unwrap();
+        channel.broadcast(2).await.unwrap();
+        channel.close().await.unwrap();
+        let flushed = listener.next().await;
+        let closed = channel.broadcast(3).await;
+
+        // Assert
+        assert_eq!(Some(Ok(2)), flushed);
+        assert_eq!(Err(Error::Connection("channel closed".into())), closed);
+    }
+
+    /// Creates a local engine.
+    async fn engine() -> crate::Engine {
+        Configuration::Local(local::Configuration {
+            queue_size: 10,
+            replay: 0,
+        })
+        .engine()
+        .await
+        .unwrap()
+    }
+}
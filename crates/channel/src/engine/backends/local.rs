@@ -2,24 +2,40 @@
 //!
 //! A local channel is a channel backend by a local bus. It can only be used in
 //! the local process.
+//!
+//! Events are passed as values and never serialised, so local channels have
+//! no encoding to configure, unlike Redis channels.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::sync::{Arc, Mutex};
 use std::task::Poll;
+use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
-use bus::Bus;
-use futures::stream::{poll_fn, BoxStream};
+use bus::{Bus, BusReader};
+use futures::stream::{iter, poll_fn, unfold, BoxStream, StreamExt};
 use serde::{Deserialize, Serialize};
 use type_map::concurrent::TypeMap;
 
+use super::Closed;
 use crate::{configuration, ChannelProducer, Error, Event, Topic};
 
+/// The interval at which streams returned by
+/// [`watch`](crate::Channel::watch) check for new events.
+const WATCH_INTERVAL: Duration = Duration::from_millis(10);
+
 /// The configuration for a local channel.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Configuration {
     /// The size of the queue holding sent messages.
     pub queue_size: usize,
+
+    /// The number of recent events replayed to new listeners.
+    ///
+    /// If this is `0`, new listeners receive only events broadcast after
+    /// they started listening.
+    #[serde(default)]
+    pub replay: usize,
 }
 
 impl Configuration {
@@ -27,9 +43,20 @@ impl Configuration {
     pub async fn engine(&self) -> Result<crate::Engine, configuration::Error> {
         Ok(crate::Engine::Local(Engine {
             queue_size: self.queue_size,
-            channel_types: Arc::new(Mutex::new(TypeMap::new())),
+            replay: self.replay,
+            topics: Arc::new(Mutex::new(HashMap::new())),
+            closed: Closed::new(),
         }))
     }
+
+    /// Verifies this configuration, and returns all problems found.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        if self.queue_size == 0 {
+            Err(vec!["the queue size must be positive".into()])
+        } else {
+            Ok(())
+        }
+    }
 }
 
 /// An engine creating local channel instances.
@@ -38,30 +65,110 @@ pub struct Engine {
     /// The size of the queue holding sent messages.
     queue_size: usize,
 
-    /// A map from channel types to maps of channels.
-    channel_types: Arc<Mutex<TypeMap>>,
+    /// The number of recent events replayed to new listeners.
+    replay: usize,
+
+    /// A map from topic names to the channels of the topics.
+    topics: Arc<Mutex<HashMap<String, TopicChannels>>>,
+
+    /// Raised when this engine is closed.
+    closed: Closed,
 }
 
-#[async_trait]
-impl ChannelProducer for Engine {
-    async fn channel<T>(
+/// The channels of a single topic.
+#[derive(Debug)]
+struct TopicChannels {
+    /// A map from channel types to channels.
+    channels: TypeMap,
+
+    /// Raised when the topic or the engine is closed.
+    closed: Closed,
+}
+
+impl Engine {
+    /// Verifies that this engine is usable.
+    ///
+    /// A local engine is always available.
+    pub async fn health_check(&self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Closes this engine and all its channels.
+    pub async fn close(&self) {
+        self.closed.close();
+        if let Ok(mut topics) = self.topics.lock() {
+            topics.drain().for_each(|(_, topic)| topic.closed.close());
+        }
+    }
+
+    /// Closes a single topic.
+    ///
+    /// The buses of all channels of the topic are dropped, so their listen
+    /// streams end and broadcasting on them fails with
+    /// [`Error::Connection`]. Creating a channel for the topic again yields a
+    /// new, empty channel.
+    ///
+    /// # Arguments
+    /// *  `topic` - The topic name.
+    pub async fn close_topic(&self, topic: &str) {
+        let removed = match self.topics.lock() {
+            Ok(mut topics) => topics.remove(topic),
+            Err(_) => None,
+        };
+        if let Some(removed) = removed {
+            removed.closed.close();
+        }
+    }
+
+    /// Attempts to create a channel, returning the concrete local type.
+    ///
+    /// This provides access to methods available only for local channels,
+    /// such as [`receiver`](Channel::receiver).
+    ///
+    /// # Arguments
+    /// *  `topic` - The topic name.
+    pub fn local_channel<T>(
         &self,
         topic: impl Topic,
-    ) -> Result<Box<dyn crate::Channel<T>>, Error>
+    ) -> Result<Arc<Channel<T>>, Error>
     where
         T: Event,
     {
-        let mut channel_types = self.channel_types.lock()?;
+        let mut topics = self.topics.lock()?;
+        self.closed.check()?;
 
-        let channels = channel_types
-            .entry::<HashMap<String, Arc<Channel<T>>>>()
-            .or_insert_with(HashMap::new);
-        let channel = channels.entry(topic.to_string()).or_insert_with(|| {
+        let entry =
+            topics
+                .entry(topic.to_string())
+                .or_insert_with(|| TopicChannels {
+                    channels: TypeMap::new(),
+                    closed: Closed::new(),
+                });
+        let closed = entry.closed.clone();
+        let channel = entry.channels.entry().or_insert_with(|| {
             Arc::new(Channel {
-                bus: Arc::new(Mutex::new(Bus::new(self.queue_size))),
+                topic: topic.to_string(),
+                bus: Arc::new(Mutex::new(Replaying::new(
+                    Bus::new(self.queue_size),
+                    self.replay,
+                ))),
+                closed,
             })
         });
-        Ok(Box::new(channel.clone()))
+        Ok(Arc::clone(channel))
+    }
+}
+
+#[async_trait]
+impl ChannelProducer for Engine {
+    async fn channel<T>(
+        &self,
+        topic: impl Topic,
+    ) -> Result<Box<dyn crate::Channel<T>>, Error>
+    where
+        T: Event,
+    {
+        Ok(Box::new(self.local_channel(topic)?))
     }
 }
 
@@ -70,8 +177,14 @@ pub struct Channel<T>
 where
     T: Event,
 {
+    /// The topic of this channel.
+    topic: String,
+
     /// The bus for this channel.
-    bus: Arc<Mutex<Bus<T>>>,
+    bus: Arc<Mutex<Replaying<T>>>,
+
+    /// Raised when the topic or the engine is closed.
+    closed: Closed,
 }
 
 #[async_trait]
@@ -80,32 +193,499 @@ where
     T: Event,
 {
     async fn broadcast(&self, event: T) -> Result<(), Error> {
-        let mut bus = self.bus.lock()?;
+        traced!("local", "broadcast", self.topic, {
+            self.send(Envelope {
+                event: crate::Envelope::new(event),
+                expires: None,
+            })
+            .map(|_| ())
+        })
+    }
 
-        bus.try_broadcast(event)
-            .map_err(|_| Error::Connection("queue is full".into()))
+    async fn broadcast_counted(&self, event: T) -> Result<usize, Error> {
+        traced!("local", "broadcast_counted", self.topic, {
+            self.send(Envelope {
+                event: crate::Envelope::new(event),
+                expires: None,
+            })
+        })
+    }
+
+    async fn broadcast_with_ttl(
+        &self,
+        event: T,
+        ttl: Duration,
+    ) -> Result<(), Error> {
+        traced!("local", "broadcast_with_ttl", self.topic, {
+            self.send(Envelope {
+                event: crate::Envelope::new(event),
+                // A time-to-live too large to represent never expires
+                expires: Instant::now().checked_add(ttl),
+            })
+            .map(|_| ())
+        })
+    }
+
+    async fn broadcast_enveloped(
+        &self,
+        envelope: crate::Envelope<T>,
+    ) -> Result<(), Error> {
+        traced!("local", "broadcast_enveloped", self.topic, {
+            self.send(Envelope {
+                event: envelope,
+                expires: None,
+            })
+            .map(|_| ())
+        })
     }
 
     async fn listen(
         &self,
     ) -> Result<BoxStream<'static, Result<T, Error>>, Error> {
-        let mut bus = self.bus.lock()?;
+        Ok(Box::pin(
+            self.listen_enveloped()
+                .await?
+                .map(|envelope| envelope.map(|envelope| envelope.payload)),
+        ))
+    }
+
+    async fn watch(
+        &self,
+    ) -> Result<BoxStream<'static, Result<T, Error>>, Error> {
+        traced!("local", "watch", self.topic, {
+            let receiver = self.receiver()?;
+            let stream: BoxStream<'static, Result<T, Error>> = Box::pin(
+                unfold(receiver, |mut receiver| async move {
+                    loop {
+                        let events = receiver.try_recv_now();
+                        if !events.is_empty() {
+                            break Some((iter(events), receiver));
+                        } else if receiver.closed.check().is_err() {
+                            break None;
+                        }
+                        tokio::time::sleep(WATCH_INTERVAL).await;
+                    }
+                })
+                .flatten()
+                .map(Ok)
+                .take_until(self.closed.wait()),
+            );
+            Ok(stream)
+        })
+    }
 
-        let mut receiver = bus.add_rx();
-        Ok(Box::pin(poll_fn(move |_| match receiver.try_recv() {
-            Ok(event) => Poll::Ready(Some(Ok(event))),
-            Err(_) => Poll::Ready(None),
+    async fn listen_with_topic(
+        &self,
+    ) -> Result<BoxStream<'static, Result<(String, T), Error>>, Error> {
+        let topic = self.topic.clone();
+        Ok(Box::pin(self.listen().await?.map(move |event| {
+            event.map(|event| (topic.clone(), event))
         })))
     }
+
+    async fn listen_enveloped(
+        &self,
+    ) -> Result<BoxStream<'static, Result<crate::Envelope<T>, Error>>, Error>
+    {
+        traced!("local", "listen_enveloped", self.topic, {
+            self.subscribe()
+        })
+    }
+}
+
+impl<T> Channel<T>
+where
+    T: Event,
+{
+    /// Creates a receiver from which events can be read without awaiting.
+    ///
+    /// The receiver is independent of any [`listen`](crate::Channel::listen)
+    /// streams on this channel. Like a listen stream, it occupies a slot in
+    /// the queue of the channel, so it must be drained regularly, or dropped,
+    /// to not make broadcasts fail once the queue is full.
+    pub fn receiver(&self) -> Result<Receiver<T>, Error> {
+        self.closed.check()?;
+        let mut bus = self.bus.lock()?;
+
+        let now = Instant::now();
+        Ok(Receiver {
+            pending: bus
+                .history
+                .iter()
+                .filter(|envelope| envelope.live(now))
+                .map(|envelope| envelope.event.payload.clone())
+                .collect(),
+            reader: bus.bus.add_rx(),
+            closed: self.closed.clone(),
+        })
+    }
+
+    /// Listens on this channel, registering the listener immediately.
+    ///
+    /// [`listen`](crate::Channel::listen) registers its listener only once
+    /// the returned future is polled, so events broadcast before that are
+    /// not received. This method registers the listener before it returns,
+    /// so every event broadcast after the call is received, even if the
+    /// stream is first polled much later. Until then, events are held in the
+    /// queue of the channel, which must not fill up.
+    pub fn listen_buffered(
+        &self,
+    ) -> Result<BoxStream<'static, Result<T, Error>>, Error> {
+        Ok(Box::pin(
+            self.subscribe()?
+                .map(|envelope| envelope.map(|envelope| envelope.payload)),
+        ))
+    }
+
+    /// Registers a listener on the bus, and streams the replayed events
+    /// followed by live events.
+    fn subscribe(
+        &self,
+    ) -> Result<BoxStream<'static, Result<crate::Envelope<T>, Error>>, Error>
+    {
+        self.closed.check()?;
+        let mut bus = self.bus.lock()?;
+
+        let now = Instant::now();
+        let history = bus
+            .history
+            .iter()
+            .filter(|envelope| envelope.live(now))
+            .map(|envelope| Ok(envelope.event.clone()))
+            .collect::<Vec<_>>();
+        let mut receiver = bus.bus.add_rx();
+        let stream: BoxStream<'static, Result<crate::Envelope<T>, Error>> =
+            Box::pin(
+                iter(history)
+                    .chain(poll_fn(move |_| loop {
+                        // Skip expired events
+                        match receiver.try_recv() {
+                            Ok(envelope) if envelope.live(Instant::now()) => {
+                                break Poll::Ready(Some(Ok(envelope.event)))
+                            }
+                            Ok(_) => continue,
+                            Err(_) => break Poll::Ready(None),
+                        }
+                    }))
+                    .take_until(self.closed.wait()),
+            );
+        Ok(stream)
+    }
+
+    /// Sends an event on the bus, and returns the number of receivers.
+    ///
+    /// # Arguments
+    /// *  `envelope` - The event to send.
+    fn send(&self, envelope: Envelope<T>) -> Result<usize, Error> {
+        self.closed.check()?;
+        let mut bus = self.bus.lock()?;
+
+        bus.broadcast(envelope)
+            .map_err(|_| Error::Connection("queue is full".into()))
+    }
+}
+
+/// A receiver of events on a local channel, usable from synchronous code.
+///
+/// Use [`Channel::receiver`] to create a receiver.
+pub struct Receiver<T> {
+    /// Events replayed, but not yet returned.
+    pending: Vec<T>,
+
+    /// The reader of the bus.
+    reader: BusReader<Envelope<T>>,
+
+    /// Raised when the topic or the engine is closed.
+    closed: Closed,
+}
+
+impl<T> Receiver<T>
+where
+    T: Event,
+{
+    /// Reads all events immediately available, without blocking.
+    ///
+    /// Expired events are skipped. Once the topic or the engine is closed, no
+    /// more events are returned.
+    pub fn try_recv_now(&mut self) -> Vec<T> {
+        let mut events = ::std::mem::take(&mut self.pending);
+        if self.closed.check().is_ok() {
+            while let Ok(envelope) = self.reader.try_recv() {
+                if envelope.live(Instant::now()) {
+                    events.push(envelope.event.payload);
+                }
+            }
+        }
+        events
+    }
+}
+
+/// An event sent on a bus.
+#[derive(Clone)]
+struct Envelope<T> {
+    /// The actual event and its metadata.
+    event: crate::Envelope<T>,
+
+    /// The time after which the event is discarded, if any.
+    expires: Option<Instant>,
+}
+
+impl<T> Envelope<T> {
+    /// Whether this event has not yet expired.
+    ///
+    /// # Arguments
+    /// *  `now` - The current time.
+    fn live(&self, now: Instant) -> bool {
+        self.expires.map(|expires| now < expires).unwrap_or(true)
+    }
+}
+
+/// A bus retaining recent events.
+struct Replaying<T> {
+    /// The actual bus.
+    bus: Bus<Envelope<T>>,
+
+    /// The most recent events, oldest first.
+    history: VecDeque<Envelope<T>>,
+
+    /// The maximum number of events to retain.
+    replay: usize,
+}
+
+impl<T> Replaying<T>
+where
+    T: Event,
+{
+    /// Wraps a bus.
+    ///
+    /// # Arguments
+    /// *  `bus` - The actual bus.
+    /// *  `replay` - The maximum number of events to retain.
+    fn new(bus: Bus<Envelope<T>>, replay: usize) -> Self {
+        Self {
+            bus,
+            history: VecDeque::with_capacity(replay),
+            replay,
+        }
+    }
+
+    /// Broadcasts an event, and retains it if replay is enabled.
+    ///
+    /// The number of live receivers is returned.
+    ///
+    /// # Arguments
+    /// *  `event` - The event to broadcast.
+    fn broadcast(&mut self, event: Envelope<T>) -> Result<usize, Envelope<T>> {
+        if self.replay > 0 {
+            self.bus.try_broadcast(event.clone())?;
+            if self.history.len() == self.replay {
+                self.history.pop_front();
+            }
+            self.history.push_back(event);
+        } else {
+            self.bus.try_broadcast(event)?;
+        }
+        Ok(self.bus.rx_count())
+    }
 }
 
 #[cfg(test)]
 fn configuration() -> Option<crate::Configuration> {
     Some(crate::Configuration::Local(Configuration {
         queue_size: 10,
+        replay: 0,
     }))
 }
 
 #[cfg(test)]
 #[path = "tests.rs"]
 mod tests;
+
+#[cfg(test)]
+mod local_tests {
+    use super::*;
+
+    use crate::Channel as _;
+
+    #[actix_rt::test]
+    async fn receiver_try_recv_now() {
+        // Arrange
+        let engine = Configuration {
+            queue_size: 10,
+            replay: 1,
+        }
+        .engine()
+        .await
+        .unwrap();
+        let channel = match &engine {
+            crate::Engine::Local(engine) => {
+                engine.local_channel::<String>("receiver").unwrap()
+            }
+            #[cfg(feature = "redis")]
+            _ => unreachable!(),
+        };
+        let listener = channel.listen().await.unwrap();
+
+        // Act
+        channel.broadcast("a".to_string()).await.unwrap();
+        let mut receiver = channel.receiver().unwrap();
+        let replayed = receiver.try_recv_now();
+        channel.broadcast("b".to_string()).await.unwrap();
+        channel.broadcast("c".to_string()).await.unwrap();
+        let received = receiver.try_recv_now();
+        let empty = receiver.try_recv_now();
+        let listened = listener
+            .take(3)
+            .map(Result::unwrap)
+            .collect::<Vec<_>>()
+            .await;
+
+        // Assert
+        assert_eq!(vec!["a".to_string()], replayed);
+        assert_eq!(vec!["b".to_string(), "c".to_string()], received);
+        assert!(empty.is_empty());
+        assert_eq!(
+            vec!["a".to_string(), "b".to_string(), "c".to_string()],
+            listened,
+        );
+    }
+
+    #[actix_rt::test]
+    async fn close_topic() {
+        // Arrange
+        let engine = crate::Configuration::Local(Configuration {
+            queue_size: 10,
+            replay: 1,
+        })
+        .engine()
+        .await
+        .unwrap();
+        let closed = engine.channel::<String>("closed").await.unwrap();
+        let open = engine.channel::<String>("open").await.unwrap();
+        closed.broadcast("a".to_string()).await.unwrap();
+        let listener = closed.listen().await.unwrap();
+
+        // Act
+        engine.close_topic("closed").await;
+        let reopened = engine.channel::<String>("closed").await.unwrap();
+
+        // Assert
+        assert_eq!(
+            Vec::<String>::new(),
+            listener.map(Result::unwrap).collect::<Vec<_>>().await,
+        );
+        assert_eq!(
+            Err(Error::Connection("channel closed".into())),
+            closed.broadcast("b".to_string()).await,
+        );
+        assert_eq!(Ok(()), open.broadcast("c".to_string()).await);
+        assert_eq!(Ok(()), reopened.broadcast("d".to_string()).await);
+        assert_eq!(
+            vec!["d".to_string()],
+            reopened
+                .listen()
+                .await
+                .unwrap()
+                .take(1)
+                .map(Result::unwrap)
+                .collect::<Vec<_>>()
+                .await,
+        );
+    }
+
+    #[actix_rt::test]
+    async fn listen_buffered() {
+        // Arrange
+        let engine = Configuration {
+            queue_size: 10,
+            replay: 0,
+        }
+        .engine()
+        .await
+        .unwrap();
+        let channel = match &engine {
+            crate::Engine::Local(engine) => {
+                engine.local_channel::<String>("buffered").unwrap()
+            }
+            #[cfg(feature = "redis")]
+            _ => unreachable!(),
+        };
+        let expected = vec!["a".to_string(), "b".to_string()];
+
+        // Act
+        let listener = channel.listen_buffered().unwrap();
+        channel.broadcast("a".to_string()).await.unwrap();
+        channel.broadcast("b".to_string()).await.unwrap();
+        let actual = listener
+            .take(expected.len())
+            .map(Result::unwrap)
+            .collect::<Vec<_>>()
+            .await;
+
+        // Assert
+        assert_eq!(expected, actual);
+    }
+
+    #[actix_rt::test]
+    async fn watch_waits() {
+        // Arrange
+        let engine = crate::Configuration::Local(Configuration {
+            queue_size: 10,
+            replay: 0,
+        })
+        .engine()
+        .await
+        .unwrap();
+        let channel = engine.channel::<String>("watch").await.unwrap();
+        let sender = engine.channel::<String>("watch").await.unwrap();
+        let expected = vec!["a".to_string(), "b".to_string()];
+
+        // Act
+        let mut listener = channel.watch().await.unwrap();
+        actix_rt::spawn(async move {
+            for event in ["a", "b"] {
+                actix_rt::time::sleep(Duration::from_millis(30)).await;
+                sender.broadcast(event.to_string()).await.unwrap();
+            }
+        });
+        let actual = (&mut listener)
+            .take(expected.len())
+            .map(Result::unwrap)
+            .collect::<Vec<_>>()
+            .await;
+        engine.close_topic("watch").await;
+
+        // Assert
+        assert_eq!(expected, actual);
+        assert!(listener.next().await.is_none());
+    }
+
+    #[actix_rt::test]
+    async fn listen_replay() {
+        // Arrange
+        let engine = crate::Configuration::Local(Configuration {
+            queue_size: 10,
+            replay: 2,
+        })
+        .engine()
+        .await
+        .unwrap();
+        let channel = engine.channel::<String>("replay").await.unwrap();
+        let expected = vec!["b".to_string(), "c".to_string(), "d".to_string()];
+
+        // Act
+        channel.broadcast("a".to_string()).await.unwrap();
+        channel.broadcast("b".to_string()).await.unwrap();
+        channel.broadcast("c".to_string()).await.unwrap();
+        let listener = channel.listen().await.unwrap();
+        channel.broadcast("d".to_string()).await.unwrap();
+        let actual = listener
+            .take(expected.len())
+            .map(Result::unwrap)
+            .collect::<Vec<_>>()
+            .await;
+
+        // Assert
+        assert_eq!(expected, actual);
+    }
+}
@@ -1,3 +1,70 @@
+/// Runs a channel operation, and logs it at the debug level if the `trace`
+/// feature is enabled.
+///
+/// The record reads `<backend> <operation> <topic>: <duration>`, followed by
+/// `(failed)` for errors. Events are never logged. For listening operations
+/// the duration covers setting up the stream, not receiving events from it.
+///
+/// # Arguments
+/// *  `backend` - The name of the backend.
+/// *  `operation` - The name of the channel method.
+/// *  `target` - The topic, or the _Redis_ channel it is mapped to.
+/// *  `body` - The operation.
+macro_rules! traced {
+    ($backend:literal, $operation:literal, $target:expr, $body:expr) => {{
+        #[cfg(feature = "trace")]
+        let start = ::std::time::Instant::now();
+        let result: Result<_, crate::Error> = async { $body }.await;
+        #[cfg(feature = "trace")]
+        ::log::debug!(
+            "{} {} {}: {:?}{}",
+            $backend,
+            $operation,
+            $target,
+            start.elapsed(),
+            if result.is_ok() { "" } else { " (failed)" },
+        );
+        result
+    }};
+}
+
+/// A signal shared between an engine and its channels, raised when the
+/// engine is closed.
+#[derive(Clone, Debug)]
+struct Closed(::std::sync::Arc<::tokio::sync::watch::Sender<bool>>);
+
+impl Closed {
+    /// Creates a new signal that has not been raised.
+    fn new() -> Self {
+        Self(::std::sync::Arc::new(::tokio::sync::watch::Sender::new(
+            false,
+        )))
+    }
+
+    /// Raises this signal.
+    fn close(&self) {
+        self.0.send_replace(true);
+    }
+
+    /// Fails with a connection error if this signal has been raised.
+    fn check(&self) -> Result<(), crate::Error> {
+        if *self.0.borrow() {
+            Err(crate::Error::Connection("channel closed".into()))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// A future completing once this signal has been raised.
+    fn wait(&self) -> impl ::std::future::Future<Output = ()> + Send + 'static {
+        let mut receiver = self.0.subscribe();
+        async move {
+            // An error means that the engine is gone, so we are closed
+            let _ = receiver.wait_for(|closed| *closed).await;
+        }
+    }
+}
+
 #[cfg(feature = "local")]
 pub mod local;
 
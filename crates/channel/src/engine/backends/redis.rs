@@ -2,32 +2,213 @@
 //!
 //! A Redis channel is a channel backed by Redis. It can be shared by multiple
 //! processes, or even multiple computers.
+//!
+//! ## Compatibility
+//!
+//! Events are published wrapped in an envelope carrying their expiry and
+//! [metadata](crate::Envelope). Earlier versions published bare CBOR
+//! encoded events; listeners still accept those, and treat them as events
+//! without metadata that never expire. Earlier versions cannot decode
+//! enveloped events however, so when upgrading processes sharing a topic,
+//! upgrade the listeners before the broadcasters.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use async_trait::async_trait;
+use bincode::Options;
 use futures::stream::BoxStream;
 use futures::StreamExt;
 use mobc::{Manager, Pool};
 use redis::aio::MultiplexedConnection;
 use redis::{AsyncCommands, Client};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use uuid::Uuid;
 
+use super::Closed;
 use crate::{configuration, ChannelProducer, Error, Event, Topic};
 
 /// The configuration for a Redis channel.
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize)]
 pub struct Configuration {
     /// The prefix used for channels.
     pub prefix: String,
 
     /// The Redis host.
     pub connection_string: String,
+
+    /// The TLS settings, if any.
+    #[serde(default)]
+    pub tls: Option<Tls>,
+
+    /// How listeners handle messages that cannot be decoded.
+    #[serde(default)]
+    pub on_decode_error: DecodeErrorPolicy,
+
+    /// The maximum size in bytes of a serialised event.
+    ///
+    /// Larger events are rejected when broadcast, and incoming larger events
+    /// are handled like messages that cannot be decoded. If this is not set,
+    /// the size is not limited.
+    #[serde(default)]
+    pub max_event_bytes: Option<usize>,
+
+    /// The encoding of events.
+    ///
+    /// All processes sharing a topic must use the same encoding.
+    #[serde(default)]
+    pub encoding: Encoding,
+}
+
+/// The encoding of events published on channels.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Encoding {
+    /// [CBOR](https://cbor.io/), which is self-describing.
+    #[default]
+    Cbor,
+
+    /// [Bincode](https://docs.rs/bincode), which is smaller and faster to
+    /// encode and decode, but not self-describing.
+    ///
+    /// Events encoded with different versions of their types are generally
+    /// not compatible, and messages encoded as CBOR are not reliably
+    /// rejected.
+    Bincode,
+}
+
+impl Encoding {
+    /// Encodes a value.
+    ///
+    /// # Arguments
+    /// *  `value` - The value to encode.
+    fn encode<T>(self, value: &T) -> Result<Vec<u8>, Error>
+    where
+        T: Serialize,
+    {
+        match self {
+            Self::Cbor => Ok(cbor4ii::serde::to_vec(Vec::new(), value)?),
+            Self::Bincode => {
+                Ok(bincode::DefaultOptions::new().serialize(value)?)
+            }
+        }
+    }
+
+    /// Decodes a published event.
+    ///
+    /// CBOR encoded messages that are not envelopes are decoded as bare
+    /// events, as published by earlier versions.
+    ///
+    /// # Arguments
+    /// *  `bytes` - The encoded event.
+    fn decode_envelope<T>(self, bytes: &[u8]) -> Result<Envelope<T>, String>
+    where
+        T: DeserializeOwned,
+    {
+        match self {
+            Self::Cbor => self.decode(bytes).or_else(|e| {
+                self.decode(bytes).map(Envelope::bare).map_err(|_| e)
+            }),
+            Self::Bincode => self.decode(bytes),
+        }
+    }
+
+    /// Decodes a value.
+    ///
+    /// The size of values decoded from Bincode is limited by the size of the
+    /// encoded data, so invalid data cannot cause large allocations.
+    ///
+    /// # Arguments
+    /// *  `bytes` - The encoded value.
+    fn decode<T>(self, bytes: &[u8]) -> Result<T, String>
+    where
+        T: DeserializeOwned,
+    {
+        match self {
+            Self::Cbor => {
+                cbor4ii::serde::from_slice(bytes).map_err(|e| e.to_string())
+            }
+            Self::Bincode => bincode::DefaultOptions::new()
+                .with_limit(bytes.len() as u64)
+                .deserialize(bytes)
+                .map_err(|e| e.to_string()),
+        }
+    }
+}
+
+/// How listeners handle messages that cannot be decoded.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DecodeErrorPolicy {
+    /// The message is discarded.
+    Skip,
+
+    /// The listen stream yields an [encoding error](Error::Encoding).
+    #[default]
+    Error,
+
+    /// The message is sent to the [dead letter stream](Engine::dead_letters)
+    /// of the engine.
+    DeadLetter,
+}
+
+/// A message that could not be decoded.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeadLetter {
+    /// The topic on which the message was received, without prefix.
+    pub topic: String,
+
+    /// The raw payload of the message.
+    pub payload: Vec<u8>,
+
+    /// A description of the decoding error.
+    pub error: String,
+}
+
+/// The TLS settings for connections to Redis.
+///
+/// These apply only to connection strings with the `rediss://` scheme, and
+/// require the `tls` feature.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Tls {
+    /// The path to a PEM file containing the root certificate used to verify
+    /// the server.
+    ///
+    /// If this is not set, the root certificates of the system are used.
+    #[serde(default)]
+    pub ca_cert: Option<PathBuf>,
+
+    /// Whether to skip verifying the certificate of the server.
+    ///
+    /// This is intended for self-signed certificates in test environments.
+    #[serde(default)]
+    pub insecure: bool,
+}
+
+impl ::std::fmt::Debug for Configuration {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        f.debug_struct("Configuration")
+            .field("prefix", &self.prefix)
+            .field(
+                "connection_string",
+                &configuration::redis::redact(&self.connection_string),
+            )
+            .field("tls", &self.tls)
+            .field("on_decode_error", &self.on_decode_error)
+            .field("max_event_bytes", &self.max_event_bytes)
+            .field("encoding", &self.encoding)
+            .finish()
+    }
 }
 
 impl Configuration {
     /// Constructs a channel engine from this configuration.
     pub async fn engine(&self) -> Result<crate::Engine, configuration::Error> {
         let prefix = self.prefix.clone();
-        let client = Client::open(self.connection_string.clone())?;
+        let client = self.client()?;
         let pool = Pool::builder().build(ConnectionManager {
             client: client.clone(),
         });
@@ -35,10 +216,89 @@ impl Configuration {
             prefix,
             pool,
             client,
+            on_decode_error: self.on_decode_error,
+            max_event_bytes: self.max_event_bytes,
+            encoding: self.encoding,
+            dead_letters: broadcast::Sender::new(DEAD_LETTER_CAPACITY),
+            closed: Closed::new(),
         }))
     }
+
+    /// Verifies this configuration, and returns all problems found.
+    ///
+    /// No connection to the server is attempted.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut problems = Vec::new();
+        if self.connection_string.is_empty() {
+            problems.push("the connection string must not be empty".into());
+        }
+        if self.max_event_bytes == Some(0) {
+            problems.push("the maximum event size must be positive".into());
+        }
+        if let Some(ca_cert) =
+            self.tls.as_ref().and_then(|tls| tls.ca_cert.as_ref())
+        {
+            if !ca_cert.is_file() {
+                problems.push(format!(
+                    "the CA certificate {} does not exist",
+                    ca_cert.display(),
+                ));
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems)
+        }
+    }
+
+    /// Creates a client for the Redis server, applying any TLS settings.
+    fn client(&self) -> Result<Client, configuration::Error> {
+        match &self.tls {
+            None => Ok(Client::open(self.connection_string.clone())?),
+
+            #[cfg(feature = "tls")]
+            Some(tls) => {
+                use redis::{ConnectionAddr, IntoConnectionInfo};
+
+                let mut info =
+                    self.connection_string.as_str().into_connection_info()?;
+                if let ConnectionAddr::TcpTls { insecure, .. } = &mut info.addr
+                {
+                    *insecure |= tls.insecure;
+                }
+                let root_cert = tls
+                    .ca_cert
+                    .as_ref()
+                    .map(std::fs::read)
+                    .transpose()
+                    .map_err(redis::RedisError::from)?;
+                Ok(Client::build_with_tls(
+                    info,
+                    redis::TlsCertificates {
+                        client_tls: None,
+                        root_cert,
+                    },
+                )?)
+            }
+
+            #[cfg(not(feature = "tls"))]
+            Some(_) => Err(redis::RedisError::from((
+                redis::ErrorKind::InvalidClientConfig,
+                "TLS support is not enabled",
+            ))
+            .into()),
+        }
+    }
 }
 
+/// The number of dead letters retained for slow dead letter listeners.
+const DEAD_LETTER_CAPACITY: usize = 64;
+
+/// The description of the error for events larger than the maximum size.
+const OVERSIZED: &str = "event exceeds max size";
+
 /// An engine creating Redis channel instances.
 pub struct Engine {
     /// The prefix used for channels.
@@ -49,6 +309,21 @@ pub struct Engine {
 
     /// The connection pool.
     pool: Pool<ConnectionManager>,
+
+    /// How listeners handle messages that cannot be decoded.
+    on_decode_error: DecodeErrorPolicy,
+
+    /// The maximum size in bytes of a serialised event, if any.
+    max_event_bytes: Option<usize>,
+
+    /// The encoding of events.
+    encoding: Encoding,
+
+    /// The sender of dead letters.
+    dead_letters: broadcast::Sender<DeadLetter>,
+
+    /// Raised when this engine is closed.
+    closed: Closed,
 }
 
 impl ::std::fmt::Debug for Engine {
@@ -57,21 +332,98 @@ impl ::std::fmt::Debug for Engine {
     }
 }
 
-#[async_trait]
-impl ChannelProducer for Engine {
-    async fn channel<T>(
+impl Engine {
+    /// Verifies that the Redis server is reachable by sending `PING`.
+    pub async fn health_check(&self) -> Result<(), Error> {
+        let mut conn = self.pool.get().await?;
+        redis::cmd("PING").query_async::<()>(&mut *conn).await?;
+        Ok(())
+    }
+
+    /// Closes this engine and all its channels.
+    ///
+    /// Subscriptions are dropped when the listen streams end, and idle
+    /// pooled connections are dropped immediately.
+    pub async fn close(&self) {
+        self.closed.close();
+        self.pool.set_max_idle_conns(0).await;
+    }
+
+    /// Closes a single topic.
+    ///
+    /// This does nothing, since subscriptions are scoped to the connections
+    /// of listen streams, and end when the streams are dropped.
+    ///
+    /// # Arguments
+    /// *  `_topic` - The topic name.
+    pub async fn close_topic(&self, _topic: &str) {}
+
+    /// Listens for messages that could not be decoded by any listener of a
+    /// channel created by this engine.
+    ///
+    /// Messages are only sent here when the engine is configured with
+    /// [`DecodeErrorPolicy::DeadLetter`]. Only messages received after this
+    /// method is called are yielded, and a listener falling too far behind
+    /// misses messages.
+    pub fn dead_letters(&self) -> BoxStream<'static, DeadLetter> {
+        futures::stream::unfold(
+            self.dead_letters.subscribe(),
+            |mut receiver| async move {
+                loop {
+                    match receiver.recv().await {
+                        Ok(dead_letter) => break Some((dead_letter, receiver)),
+                        Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(broadcast::error::RecvError::Closed) => break None,
+                    }
+                }
+            },
+        )
+        .take_until(self.closed.wait())
+        .boxed()
+    }
+}
+
+impl Engine {
+    /// Attempts to create a channel, returning the concrete Redis type.
+    ///
+    /// This provides access to methods available only for Redis channels,
+    /// such as [`broadcast_json`](Channel::broadcast_json).
+    ///
+    /// # Arguments
+    /// *  `topic` - The topic name.
+    pub fn redis_channel<T>(
         &self,
         topic: impl Topic,
-    ) -> Result<Box<dyn crate::Channel<T>>, Error>
+    ) -> Result<Channel<T>, Error>
     where
         T: Event,
     {
-        Ok(Box::new(Channel {
+        self.closed.check()?;
+        Ok(Channel {
             client: self.client.clone(),
+            prefix: self.prefix.clone(),
             channel: format!("{}{}", self.prefix, topic),
+            on_decode_error: self.on_decode_error,
+            max_event_bytes: self.max_event_bytes,
+            encoding: self.encoding,
+            dead_letters: self.dead_letters.clone(),
             pool: self.pool.clone(),
+            closed: self.closed.clone(),
             _m: ::std::marker::PhantomData,
-        }))
+        })
+    }
+}
+
+#[async_trait]
+impl ChannelProducer for Engine {
+    async fn channel<T>(
+        &self,
+        topic: impl Topic,
+    ) -> Result<Box<dyn crate::Channel<T>>, Error>
+    where
+        T: Event,
+    {
+        Ok(Box::new(self.redis_channel(topic)?))
     }
 }
 
@@ -84,12 +436,30 @@ where
     // The Redis client.
     client: Client,
 
+    /// The prefix used for channels.
+    prefix: String,
+
     /// The name of the channel.
     channel: String,
 
+    /// How listeners handle messages that cannot be decoded.
+    on_decode_error: DecodeErrorPolicy,
+
+    /// The maximum size in bytes of a serialised event, if any.
+    max_event_bytes: Option<usize>,
+
+    /// The encoding of events.
+    encoding: Encoding,
+
+    /// The sender of dead letters.
+    dead_letters: broadcast::Sender<DeadLetter>,
+
     /// The connection pool.
     pool: Pool<ConnectionManager>,
 
+    /// Raised when the engine is closed.
+    closed: Closed,
+
     _m: ::std::marker::PhantomData<T>,
 }
 
@@ -99,19 +469,339 @@ where
     T: Event,
 {
     async fn broadcast(&self, event: T) -> Result<(), Error> {
-        let mut conn = self.pool.get().await?;
-        let bytes = cbor4ii::serde::to_vec(Vec::new(), &event)?;
-        Ok(conn.publish(&self.channel, bytes).await?)
+        traced!("redis", "broadcast", self.channel, {
+            self.send(Envelope::wrap(&crate::Envelope::new(event), None))
+                .await
+                .map(|_| ())
+        })
+    }
+
+    async fn broadcast_counted(&self, event: T) -> Result<usize, Error> {
+        traced!("redis", "broadcast_counted", self.channel, {
+            self.send(Envelope::wrap(&crate::Envelope::new(event), None))
+                .await
+        })
+    }
+
+    async fn broadcast_with_ttl(
+        &self,
+        event: T,
+        ttl: Duration,
+    ) -> Result<(), Error> {
+        traced!("redis", "broadcast_with_ttl", self.channel, {
+            // A time-to-live too large to represent never expires
+            let expires = u64::try_from(ttl.as_millis())
+                .ok()
+                .and_then(|ttl| Envelope::<T>::now().checked_add(ttl));
+            self.send(Envelope::wrap(&crate::Envelope::new(event), expires))
+                .await
+                .map(|_| ())
+        })
+    }
+
+    async fn broadcast_enveloped(
+        &self,
+        envelope: crate::Envelope<T>,
+    ) -> Result<(), Error> {
+        traced!("redis", "broadcast_enveloped", self.channel, {
+            self.send(Envelope::wrap(&envelope, None)).await.map(|_| ())
+        })
     }
 
     async fn listen(
         &self,
     ) -> Result<BoxStream<'static, Result<T, Error>>, Error> {
+        traced!("redis", "listen", self.channel, {
+            let stream: BoxStream<'static, Result<T, Error>> = Box::pin(
+                self.subscribe(self.decoder())
+                    .await?
+                    .map(|event| event.map(|(_, event)| event.payload)),
+            );
+            Ok(stream)
+        })
+    }
+
+    async fn listen_with_topic(
+        &self,
+    ) -> Result<BoxStream<'static, Result<(String, T), Error>>, Error> {
+        traced!("redis", "listen_with_topic", self.channel, {
+            let stream: BoxStream<'static, Result<(String, T), Error>> =
+                Box::pin(self.subscribe(self.decoder()).await?.map(|event| {
+                    event.map(|(topic, event)| (topic, event.payload))
+                }));
+            Ok(stream)
+        })
+    }
+
+    async fn listen_enveloped(
+        &self,
+    ) -> Result<BoxStream<'static, Result<crate::Envelope<T>, Error>>, Error>
+    {
+        traced!("redis", "listen_enveloped", self.channel, {
+            let stream: BoxStream<'static, Result<crate::Envelope<T>, Error>> =
+                Box::pin(
+                    self.subscribe(self.decoder())
+                        .await?
+                        .map(|event| event.map(|(_, event)| event)),
+                );
+            Ok(stream)
+        })
+    }
+}
+
+impl<T> Channel<T>
+where
+    T: Event,
+{
+    /// Publishes an event, and returns the number of subscribers receiving
+    /// it.
+    ///
+    /// Events larger than the maximum size are rejected before a connection
+    /// is acquired.
+    ///
+    /// # Arguments
+    /// *  `envelope` - The event to publish.
+    async fn send(&self, envelope: Envelope<&T>) -> Result<usize, Error> {
+        self.closed.check()?;
+        self.publish(self.encoding.encode(&envelope)?).await
+    }
+
+    /// Publishes a serialised event, and returns the number of subscribers
+    /// receiving it.
+    ///
+    /// Events larger than the maximum size are rejected before a connection
+    /// is acquired.
+    ///
+    /// # Arguments
+    /// *  `bytes` - The serialised event to publish.
+    async fn publish(&self, bytes: Vec<u8>) -> Result<usize, Error> {
+        if self.max_event_bytes.is_some_and(|max| bytes.len() > max) {
+            return Err(Error::Encoding(OVERSIZED.into()));
+        }
+        let mut conn = self.pool.get().await?;
+        Ok(conn.publish(&self.channel, bytes).await?)
+    }
+
+    /// The function decoding the payload of messages using the encoding of
+    /// this channel.
+    fn decoder(
+        &self,
+    ) -> impl Fn(&[u8]) -> Result<Envelope<T>, String> + Copy + Send + 'static
+    {
+        let encoding = self.encoding;
+        move |payload| encoding.decode_envelope(payload)
+    }
+
+    /// Subscribes to this channel, and pairs each live event with the topic
+    /// on which it was received.
+    ///
+    /// # Arguments
+    /// *  `decode` - The function decoding the payload of messages.
+    async fn subscribe<D>(
+        &self,
+        decode: D,
+    ) -> Result<
+        BoxStream<'static, Result<(String, crate::Envelope<T>), Error>>,
+        Error,
+    >
+    where
+        D: Fn(&[u8]) -> Result<Envelope<T>, String> + Copy + Send + 'static,
+    {
+        self.closed.check()?;
         let (mut sink, stream) = self.client.get_async_pubsub().await?.split();
         sink.subscribe(&self.channel).await?;
-        Ok(Box::pin(stream.map(|msg| {
-            Ok(cbor4ii::serde::from_slice(msg.get_payload_bytes())?)
-        })))
+        let prefix = self.prefix.clone();
+        let on_decode_error = self.on_decode_error;
+        let max_event_bytes = self.max_event_bytes;
+        let dead_letters = self.dead_letters.clone();
+        Ok(Box::pin(
+            stream
+                .filter_map(move |msg| {
+                    let topic = msg
+                        .get_channel_name()
+                        .strip_prefix(prefix.as_str())
+                        .unwrap_or(msg.get_channel_name())
+                        .to_string();
+                    let dead_letters = dead_letters.clone();
+                    async move {
+                        let payload = msg.get_payload_bytes();
+                        let decoded = if max_event_bytes
+                            .is_some_and(|max| payload.len() > max)
+                        {
+                            Err(OVERSIZED.to_string())
+                        } else {
+                            decode(payload)
+                        };
+
+                        // Skip expired events
+                        match decoded {
+                            Ok(envelope) if envelope.live() => {
+                                Some(Ok((topic, envelope.into_envelope())))
+                            }
+                            Ok(_) => None,
+                            Err(e) => match on_decode_error {
+                                DecodeErrorPolicy::Skip => None,
+                                DecodeErrorPolicy::Error => {
+                                    Some(Err(Error::Encoding(e)))
+                                }
+                                DecodeErrorPolicy::DeadLetter => {
+                                    // Dead letters are dropped if no one is
+                                    // listening
+                                    let _ = dead_letters.send(DeadLetter {
+                                        topic,
+                                        payload: payload.to_vec(),
+                                        error: e,
+                                    });
+                                    None
+                                }
+                            },
+                        }
+                    }
+                })
+                .take_until(self.closed.wait()),
+        ))
+    }
+}
+
+#[cfg(feature = "json")]
+impl<T> Channel<T>
+where
+    T: Event,
+{
+    /// Broadcasts an event encoded as JSON.
+    ///
+    /// Events are normally encoded as CBOR, which is efficient but not
+    /// readily decoded by services not written in Rust. A JSON event is an
+    /// object whose `event` field contains the event; it may also contain
+    /// the metadata fields `expires` and `timestamp`, in milliseconds since
+    /// the UNIX epoch, `id` and `headers`.
+    ///
+    /// Events broadcast this way can only be received by
+    /// [`listen_json`](Self::listen_json).
+    ///
+    /// # Arguments
+    /// *  `event` - The event to broadcast.
+    pub async fn broadcast_json(&self, event: T) -> Result<(), Error> {
+        traced!("redis", "broadcast_json", self.channel, {
+            self.closed.check()?;
+            let envelope = crate::Envelope::new(event);
+            self.publish(serde_json::to_vec(&Envelope::wrap(&envelope, None))?)
+                .await
+                .map(|_| ())
+        })
+    }
+
+    /// Listens for events encoded as JSON.
+    ///
+    /// Messages that are not JSON events, including events broadcast with
+    /// [`broadcast`](crate::Channel::broadcast), are handled according to
+    /// the [decode error policy](DecodeErrorPolicy). Please see
+    /// [`broadcast_json`](Self::broadcast_json) for the format of events.
+    pub async fn listen_json(
+        &self,
+    ) -> Result<BoxStream<'static, Result<T, Error>>, Error> {
+        traced!("redis", "listen_json", self.channel, {
+            let stream: BoxStream<'static, Result<T, Error>> = Box::pin(
+                self.subscribe(|payload| {
+                    serde_json::from_slice(payload).map_err(|e| e.to_string())
+                })
+                .await?
+                .map(|event| event.map(|(_, event)| event.payload)),
+            );
+            Ok(stream)
+        })
+    }
+}
+
+/// An event published on a channel.
+///
+/// The metadata fields default to empty values for events published before
+/// they were introduced.
+#[derive(Deserialize, Serialize)]
+struct Envelope<T> {
+    /// The actual event.
+    event: T,
+
+    /// The time, in milliseconds since the UNIX epoch, after which the event
+    /// is discarded, if any.
+    expires: Option<u64>,
+
+    /// The unique identifier of the event.
+    #[serde(default)]
+    id: Uuid,
+
+    /// The time, in milliseconds since the UNIX epoch, at which the event
+    /// was created.
+    #[serde(default)]
+    timestamp: u64,
+
+    /// The headers of the event.
+    #[serde(default)]
+    headers: HashMap<String, String>,
+}
+
+impl<'a, T> Envelope<&'a T> {
+    /// Wraps an event and its metadata for publishing.
+    ///
+    /// # Arguments
+    /// *  `envelope` - The event and its metadata.
+    /// *  `expires` - The time, in milliseconds since the UNIX epoch, after
+    ///    which the event is discarded, if any.
+    fn wrap(envelope: &'a crate::Envelope<T>, expires: Option<u64>) -> Self {
+        Self {
+            event: &envelope.payload,
+            expires,
+            id: envelope.id,
+            timestamp: envelope
+                .timestamp
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_millis() as u64)
+                .unwrap_or_default(),
+            headers: envelope.headers.clone(),
+        }
+    }
+}
+
+impl<T> Envelope<T> {
+    /// Wraps an event published without an envelope.
+    ///
+    /// The event never expires, and its metadata is empty.
+    ///
+    /// # Arguments
+    /// *  `event` - The event.
+    fn bare(event: T) -> Self {
+        Self {
+            event,
+            expires: None,
+            id: Uuid::default(),
+            timestamp: 0,
+            headers: HashMap::new(),
+        }
+    }
+
+    /// Converts a received event to an envelope with its metadata.
+    fn into_envelope(self) -> crate::Envelope<T> {
+        crate::Envelope {
+            id: self.id,
+            timestamp: UNIX_EPOCH + Duration::from_millis(self.timestamp),
+            headers: self.headers,
+            payload: self.event,
+        }
+    }
+
+    /// The current time, in milliseconds since the UNIX epoch.
+    fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_millis() as u64)
+            .unwrap_or_default()
+    }
+
+    /// Whether this event has not yet expired.
+    fn live(&self) -> bool {
+        self.expires
+            .map(|expires| Self::now() < expires)
+            .unwrap_or(true)
     }
 }
 
@@ -136,7 +826,7 @@ impl Manager for ConnectionManager {
         &self,
         mut conn: Self::Connection,
     ) -> Result<Self::Connection, Self::Error> {
-        redis::cmd("PING").query_async(&mut conn).await?;
+        redis::cmd("PING").query_async::<()>(&mut conn).await?;
         Ok(conn)
     }
 }
@@ -147,10 +837,299 @@ fn configuration() -> Option<crate::Configuration> {
         crate::Configuration::Redis(Configuration {
             connection_string: connection_string.into(),
             prefix: "test".to_string(),
+            tls: None,
+            on_decode_error: DecodeErrorPolicy::Error,
+            max_event_bytes: None,
+            encoding: Encoding::Cbor,
         })
     })
 }
 
 #[cfg(test)]
+#[allow(clippy::duplicate_mod)]
 #[path = "tests.rs"]
 mod tests;
+
+#[cfg(test)]
+mod redis_tests {
+    use super::*;
+
+    /// A configuration for a server that is never contacted.
+    ///
+    /// # Arguments
+    /// *  `connection_string` - The connection string.
+    /// *  `tls` - The TLS settings.
+    fn configuration(
+        connection_string: &str,
+        tls: Option<Tls>,
+    ) -> Configuration {
+        Configuration {
+            prefix: "test".into(),
+            connection_string: connection_string.into(),
+            tls,
+            on_decode_error: DecodeErrorPolicy::Error,
+            max_event_bytes: None,
+            encoding: Encoding::Cbor,
+        }
+    }
+
+    #[test]
+    fn client_plain() {
+        assert!(configuration("redis://localhost", None).client().is_ok());
+    }
+
+    #[cfg(not(feature = "tls"))]
+    #[test]
+    fn client_tls_disabled() {
+        assert!(configuration("rediss://localhost", Some(Tls::default()))
+            .client()
+            .is_err());
+    }
+
+    #[cfg(feature = "tls")]
+    #[test]
+    fn client_tls() {
+        assert!(configuration(
+            "rediss://localhost",
+            Some(Tls {
+                ca_cert: None,
+                insecure: true,
+            }),
+        )
+        .client()
+        .is_ok());
+    }
+
+    #[cfg(feature = "tls")]
+    #[test]
+    fn client_tls_plain_url() {
+        assert!(configuration("redis://localhost", Some(Tls::default()))
+            .client()
+            .is_err());
+    }
+
+    #[cfg(feature = "tls")]
+    #[test]
+    fn client_tls_missing_ca_cert() {
+        assert!(configuration(
+            "rediss://localhost",
+            Some(Tls {
+                ca_cert: Some("/nonexistent/ca.pem".into()),
+                insecure: false,
+            }),
+        )
+        .client()
+        .is_err());
+    }
+
+    #[test]
+    fn validate_valid() {
+        assert_eq!(Ok(()), configuration("redis://localhost", None).validate(),);
+    }
+
+    #[test]
+    fn validate_invalid() {
+        let configuration = Configuration {
+            max_event_bytes: Some(0),
+            ..configuration(
+                "",
+                Some(Tls {
+                    ca_cert: Some("/nonexistent/ca.pem".into()),
+                    insecure: false,
+                }),
+            )
+        };
+
+        assert_eq!(
+            Err(vec![
+                "the connection string must not be empty".to_string(),
+                "the maximum event size must be positive".to_string(),
+                "the CA certificate /nonexistent/ca.pem does not exist"
+                    .to_string(),
+            ]),
+            configuration.validate(),
+        );
+    }
+
+    #[test]
+    fn encoding_round_trip() {
+        for encoding in [Encoding::Cbor, Encoding::Bincode] {
+            // Arrange
+            let bytes = encoding.encode(&"event".to_string()).unwrap();
+
+            // Act
+            let actual = encoding.decode::<String>(&bytes);
+
+            // Assert
+            assert_eq!(Ok("event".to_string()), actual);
+        }
+    }
+
+    #[test]
+    fn encoding_mismatch() {
+        // Arrange
+        let bytes = Encoding::Bincode.encode(&"event".to_string()).unwrap();
+
+        // Act
+        let actual = Encoding::Cbor.decode::<String>(&bytes);
+
+        // Assert
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn decode_envelope_bare() {
+        // Arrange
+        let bytes = Encoding::Cbor.encode(&"event".to_string()).unwrap();
+
+        // Act
+        let actual = Encoding::Cbor.decode_envelope::<String>(&bytes);
+
+        // Assert
+        let actual = actual.unwrap();
+        assert_eq!("event", actual.event);
+        assert!(actual.live());
+    }
+
+    #[test]
+    fn decode_envelope_wrapped() {
+        // Arrange
+        let envelope = crate::Envelope::new("event".to_string());
+        let bytes = Encoding::Cbor
+            .encode(&Envelope::wrap(&envelope, Some(0)))
+            .unwrap();
+
+        // Act
+        let actual = Encoding::Cbor.decode_envelope::<String>(&bytes);
+
+        // Assert
+        let actual = actual.unwrap();
+        assert_eq!("event", actual.event);
+        assert_eq!(envelope.id, actual.id);
+        assert!(!actual.live());
+    }
+
+    #[actix_rt::test]
+    async fn broadcast_oversized() {
+        // Arrange
+        let engine = Configuration {
+            max_event_bytes: Some(64),
+            ..configuration("redis://localhost", None)
+        };
+        let crate::Engine::Redis(engine) = engine.engine().await.unwrap()
+        else {
+            unreachable!();
+        };
+        let channel = engine.channel::<String>("oversized").await.unwrap();
+
+        // Act
+        let actual = channel.broadcast("x".repeat(64)).await;
+
+        // Assert
+        assert_eq!(Err(Error::Encoding(OVERSIZED.into())), actual);
+    }
+
+    #[cfg(feature = "json")]
+    #[actix_rt::test]
+    async fn broadcast_json_oversized() {
+        // Arrange
+        let engine = Configuration {
+            max_event_bytes: Some(64),
+            ..configuration("redis://localhost", None)
+        };
+        let crate::Engine::Redis(engine) = engine.engine().await.unwrap()
+        else {
+            unreachable!();
+        };
+        let channel = engine.redis_channel::<String>("oversized").unwrap();
+
+        // Act
+        let actual = channel.broadcast_json("x".repeat(64)).await;
+
+        // Assert
+        assert_eq!(Err(Error::Encoding(OVERSIZED.into())), actual);
+    }
+
+    #[cfg(feature = "json")]
+    #[actix_rt::test]
+    async fn listen_json() {
+        // Arrange
+        let Some(connection_string) = option_env!("WERU_CHANNEL_REDIS") else {
+            return;
+        };
+        let engine = Configuration {
+            on_decode_error: DecodeErrorPolicy::Skip,
+            ..configuration(connection_string, None)
+        };
+        let crate::Engine::Redis(engine) = engine.engine().await.unwrap()
+        else {
+            unreachable!();
+        };
+        let channel = engine.redis_channel::<String>("json").unwrap();
+        let listener = channel.listen_json().await.unwrap();
+
+        // Act
+        crate::Channel::broadcast(&channel, "cbor".to_string())
+            .await
+            .unwrap();
+        engine
+            .pool
+            .get()
+            .await
+            .unwrap()
+            .publish::<_, _, ()>("testjson", r#"{"event":"foreign"}"#)
+            .await
+            .unwrap();
+        channel.broadcast_json("json".to_string()).await.unwrap();
+        let events = listener.take(2).collect::<Vec<_>>().await;
+
+        // Assert
+        assert_eq!(
+            vec![Ok("foreign".to_string()), Ok("json".to_string())],
+            events,
+        );
+    }
+
+    #[actix_rt::test]
+    async fn listen_dead_letter() {
+        // Arrange
+        let Some(connection_string) = option_env!("WERU_CHANNEL_REDIS") else {
+            return;
+        };
+        let engine = Configuration {
+            on_decode_error: DecodeErrorPolicy::DeadLetter,
+            ..configuration(connection_string, None)
+        };
+        let crate::Engine::Redis(engine) = engine.engine().await.unwrap()
+        else {
+            unreachable!();
+        };
+        let channel = engine.channel::<String>("dead_letter").await.unwrap();
+        let listener = channel.listen().await.unwrap();
+        let dead_letters = engine.dead_letters();
+        let payload = b"invalid".to_vec();
+
+        // Act
+        engine
+            .pool
+            .get()
+            .await
+            .unwrap()
+            .publish::<_, _, ()>("testdead_letter", payload.clone())
+            .await
+            .unwrap();
+        channel.broadcast("valid".to_string()).await.unwrap();
+        let events = listener.take(1).collect::<Vec<_>>().await;
+        let letters = dead_letters.take(1).collect::<Vec<_>>().await;
+
+        // Assert
+        assert_eq!(vec![Ok("valid".to_string())], events);
+        assert_eq!(
+            vec![("dead_letter".to_string(), payload)],
+            letters
+                .into_iter()
+                .map(|letter| (letter.topic, letter.payload))
+                .collect::<Vec<_>>(),
+        );
+    }
+}
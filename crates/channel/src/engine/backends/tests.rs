@@ -1,5 +1,7 @@
 use super::*;
 
+use std::time::Duration;
+
 use futures::StreamExt;
 
 use crate::traits::Channel;
@@ -145,3 +147,159 @@ async fn listen_receive_mpmc() {
     assert_eq!(expected, actual1);
     assert_eq!(expected, actual2);
 }
+
+#[actix_rt::test]
+async fn listen_with_topic_receive() {
+    // Arrange
+    let topic = "listen_with_topic_receive".to_string();
+    let engine = engine!();
+    let sender = engine.channel(topic.clone()).await.unwrap();
+    let channel = engine.channel::<String>(topic.clone()).await.unwrap();
+    let expected = vec![
+        (topic.clone(), "a".to_string()),
+        (topic.clone(), "b".to_string()),
+    ];
+
+    // Act
+    let listener = channel.listen_with_topic().await.unwrap();
+    write(sender.as_ref(), &["a".to_string(), "b".to_string()]).await;
+    let actual = listener
+        .take(expected.len())
+        .map(Result::unwrap)
+        .collect::<Vec<_>>()
+        .await;
+
+    // Assert
+    assert_eq!(expected, actual);
+}
+
+#[actix_rt::test]
+async fn listen_enveloped_receive() {
+    // Arrange
+    let topic = "listen_enveloped_receive".to_string();
+    let engine = engine!();
+    let sender = engine.channel(topic.clone()).await.unwrap();
+    let channel = engine.channel::<String>(topic.clone()).await.unwrap();
+    let envelope =
+        crate::Envelope::new("a".to_string()).with_header("trace", "42");
+
+    // Act
+    let enveloped = channel.listen_enveloped().await.unwrap();
+    let plain = channel.listen().await.unwrap();
+    sender.broadcast_enveloped(envelope.clone()).await.unwrap();
+    sender.broadcast("b".to_string()).await.unwrap();
+    let actual = enveloped
+        .take(2)
+        .map(Result::unwrap)
+        .collect::<Vec<_>>()
+        .await;
+    let actual_plain = read(plain, 2).await;
+
+    // Assert
+    assert_eq!(envelope.id, actual[0].id);
+    assert_eq!(envelope.headers, actual[0].headers);
+    assert_eq!(envelope.payload, actual[0].payload);
+    assert_ne!(envelope.id, actual[1].id);
+    assert!(actual[1].headers.is_empty());
+    assert_eq!("b", actual[1].payload);
+    assert_eq!(vec!["a".to_string(), "b".to_string()], actual_plain);
+}
+
+#[actix_rt::test]
+async fn broadcast_counted() {
+    // Arrange
+    let topic = "broadcast_counted".to_string();
+    let engine = engine!();
+    let sender = engine.channel(topic.clone()).await.unwrap();
+    let channel = engine.channel::<String>(topic.clone()).await.unwrap();
+
+    // Act
+    let before = sender.broadcast_counted("a".to_string()).await;
+    let listener1 = channel.listen().await.unwrap();
+    let listener2 = channel.listen().await.unwrap();
+    let during = sender.broadcast_counted("b".to_string()).await;
+    drop(listener1);
+    drop(listener2);
+
+    // Assert
+    assert_eq!(Ok(0), before);
+    assert_eq!(Ok(2), during);
+}
+
+#[actix_rt::test]
+async fn health_check() {
+    // Arrange
+    let engine = engine!();
+
+    // Act
+    let actual = engine.health_check().await;
+
+    // Assert
+    assert_eq!(Ok(()), actual);
+}
+
+#[actix_rt::test]
+async fn close() {
+    // Arrange
+    let topic = "close".to_string();
+    let engine = engine!();
+    let channel = engine.channel::<String>(topic.clone()).await.unwrap();
+    let mut listener = channel.listen().await.unwrap();
+
+    // Act
+    engine.close().await;
+    let actual = channel.broadcast("a".to_string()).await;
+
+    // Assert
+    assert_eq!(Err(Error::Connection("channel closed".into())), actual);
+    assert!(listener.next().await.is_none());
+    assert!(engine.channel::<String>(topic).await.is_err());
+}
+
+#[actix_rt::test]
+async fn listen_receive_ttl() {
+    // Arrange
+    let topic = "listen_receive_ttl".to_string();
+    let engine = engine!();
+    let sender = engine.channel(topic.clone()).await.unwrap();
+    let channel = engine.channel::<String>(topic.clone()).await.unwrap();
+    let expected = vec!["b".to_string(), "c".to_string()];
+
+    // Act
+    let listener = channel.listen().await.unwrap();
+    sender
+        .broadcast_with_ttl("a".to_string(), Duration::from_millis(50))
+        .await
+        .unwrap();
+    sender
+        .broadcast_with_ttl("b".to_string(), Duration::from_secs(32))
+        .await
+        .unwrap();
+    actix_rt::time::sleep(Duration::from_millis(100)).await;
+    sender.broadcast("c".to_string()).await.unwrap();
+    let actual = read(listener, expected.len()).await;
+
+    // Assert
+    assert_eq!(expected, actual);
+}
+
+#[actix_rt::test]
+async fn listen_receive_ttl_max() {
+    // Arrange
+    let topic = "listen_receive_ttl_max".to_string();
+    let engine = engine!();
+    let sender = engine.channel(topic.clone()).await.unwrap();
+    let channel = engine.channel::<String>(topic.clone()).await.unwrap();
+    let expected = vec!["a".to_string()];
+
+    // Act
+    let listener = channel.listen().await.unwrap();
+    sender
+        .broadcast_with_ttl("a".to_string(), Duration::MAX)
+        .await
+        .unwrap();
+    let actual = read(listener, expected.len()).await;
+
+    // Assert
+    assert_eq!(expected, actual);
+}
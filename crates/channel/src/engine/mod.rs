@@ -3,8 +3,11 @@
 //! This module contains the [`Engine`](Engine) used to construct actual
 //! channel instances. An engine is created from a configuration instance.
 
+use std::time::Duration;
+
 use crate::{
-    configuration, Channel, ChannelProducer, Configuration, Error, Event, Topic,
+    configuration, Channel, ChannelProducer, Configuration, Debounced, Error,
+    Event, Topic,
 };
 
 pub mod backends;
@@ -42,6 +45,77 @@ impl Engine {
             Engine::Redis(engine) => engine.channel(topic).await,
         }
     }
+
+    /// Attempts to create a channel coalescing rapid broadcasts.
+    ///
+    /// Only the most recent event broadcast within `window` is forwarded to
+    /// listeners. Please see [`Debounced`] for details.
+    ///
+    /// # Arguments
+    /// *  `topic` - The channel topic.
+    /// *  `window` - The window during which events are coalesced.
+    pub async fn debounced_channel<T>(
+        &self,
+        topic: impl Topic,
+        window: Duration,
+    ) -> Result<Debounced<T>, Error>
+    where
+        T: Event,
+    {
+        Ok(Debounced::new(self.channel(topic).await?, window))
+    }
+
+    /// Verifies that the backend of this engine is reachable.
+    ///
+    /// This is intended for readiness probes, and is cheap to call.
+    pub async fn health_check(&self) -> Result<(), Error> {
+        match self {
+            #[cfg(feature = "local")]
+            Engine::Local(engine) => engine.health_check().await,
+
+            #[cfg(feature = "redis")]
+            Engine::Redis(engine) => engine.health_check().await,
+        }
+    }
+
+    /// Closes this engine.
+    ///
+    /// All listen streams of channels created by this engine end, and
+    /// broadcasting on them fails with
+    /// [`Error::Connection`](Error::Connection). Idle connections to remote
+    /// servers are dropped.
+    pub async fn close(&self) {
+        match self {
+            #[cfg(feature = "local")]
+            Engine::Local(engine) => engine.close().await,
+
+            #[cfg(feature = "redis")]
+            Engine::Redis(engine) => engine.close().await,
+        }
+    }
+
+    /// Closes a single topic.
+    ///
+    /// For local engines, the channels of the topic are removed, so their
+    /// listen streams end and broadcasting on them fails with
+    /// [`Error::Connection`](Error::Connection); creating a channel for the
+    /// topic again yields a new channel. Without this, a local engine retains
+    /// every topic for which a channel has been created.
+    ///
+    /// For Redis engines this does nothing, since subscriptions are scoped to
+    /// connections.
+    ///
+    /// # Arguments
+    /// *  `topic` - The topic name.
+    pub async fn close_topic(&self, topic: &str) {
+        match self {
+            #[cfg(feature = "local")]
+            Engine::Local(engine) => engine.close_topic(topic).await,
+
+            #[cfg(feature = "redis")]
+            Engine::Redis(engine) => engine.close_topic(topic).await,
+        }
+    }
 }
 
 impl Configuration {
@@ -55,4 +129,18 @@ impl Configuration {
             Configuration::Redis(c) => c.engine().await,
         }
     }
+
+    /// Verifies this configuration, and returns all problems found.
+    ///
+    /// No connection is attempted, so this is suitable for reporting
+    /// misconfigurations at startup, before any channel is created.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        match self {
+            #[cfg(feature = "local")]
+            Configuration::Local(c) => c.validate(),
+
+            #[cfg(feature = "redis")]
+            Configuration::Redis(c) => c.validate(),
+        }
+    }
 }
@@ -0,0 +1,65 @@
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// An event with metadata.
+///
+/// Envelopes are broadcast with
+/// [`broadcast_enveloped`](crate::Channel::broadcast_enveloped) and received
+/// with [`listen_enveloped`](crate::Channel::listen_enveloped). Events
+/// broadcast without an envelope are received with a fresh identifier, the
+/// time of broadcast and no headers, and envelopes listened to without
+/// metadata yield only their payload.
+///
+/// # Argument
+/// *  `T` - The type for events.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Envelope<T> {
+    /// A unique identifier for this event.
+    ///
+    /// Consumers may use this to skip events already seen.
+    pub id: Uuid,
+
+    /// The time at which this event was created.
+    ///
+    /// The Redis backend transmits this with millisecond precision.
+    pub timestamp: SystemTime,
+
+    /// Arbitrary headers.
+    pub headers: HashMap<String, String>,
+
+    /// The actual event.
+    pub payload: T,
+}
+
+impl<T> Envelope<T> {
+    /// Wraps an event in an envelope with a fresh identifier, the current time
+    /// and no headers.
+    ///
+    /// # Arguments
+    /// *  `payload` - The event.
+    pub fn new(payload: T) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            timestamp: SystemTime::now(),
+            headers: HashMap::new(),
+            payload,
+        }
+    }
+
+    /// Adds a header to this envelope.
+    ///
+    /// # Arguments
+    /// *  `name` - The header name.
+    /// *  `value` - The header value.
+    pub fn with_header(
+        mut self,
+        name: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        self.headers.insert(name.into(), value.into());
+        self
+    }
+}
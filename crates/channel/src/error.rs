@@ -33,6 +33,7 @@ pub mod local {
 
 #[cfg(feature = "redis")]
 pub mod redis {
+    use bincode::ErrorKind as BincodeError;
     use cbor4ii::serde::{DecodeError, EncodeError};
     use mobc::Error as PoolError;
     use redis::RedisError;
@@ -55,6 +56,12 @@ pub mod redis {
         }
     }
 
+    impl From<Box<BincodeError>> for super::Error {
+        fn from(source: Box<BincodeError>) -> Self {
+            Self::Encoding(source.to_string())
+        }
+    }
+
     impl<E> From<PoolError<E>> for super::Error
     where
         E: ::std::fmt::Display,
@@ -70,3 +77,12 @@ pub mod redis {
         }
     }
 }
+
+#[cfg(any(feature = "actix", feature = "json"))]
+pub mod json {
+    impl From<serde_json::Error> for super::Error {
+        fn from(source: serde_json::Error) -> Self {
+            Self::Encoding(source.to_string())
+        }
+    }
+}
@@ -1,9 +1,18 @@
+#[cfg(feature = "actix")]
+pub mod actix;
+
 pub mod configuration;
 pub use configuration::Configuration;
 
+pub mod debounce;
+pub use debounce::Debounced;
+
 pub mod engine;
 pub use engine::Engine;
 
+mod envelope;
+pub use envelope::Envelope;
+
 mod error;
 pub use error::Error;
 
@@ -1,8 +1,10 @@
+use std::time::Duration;
+
 use async_trait::async_trait;
 use futures::stream::BoxStream;
 use serde::{Deserialize, Serialize};
 
-use crate::Error;
+use crate::{Envelope, Error};
 
 /// A channel topic.
 pub trait Topic: Send + Sync + ::std::fmt::Display {}
@@ -35,10 +37,86 @@ where
     /// *  `event` - The event to broadcast.
     async fn broadcast(&self, event: T) -> Result<(), Error>;
 
+    /// Broadcasts an event on this channel, and returns the number of
+    /// receivers.
+    ///
+    /// A count of `0` means that no one is listening on this channel. For
+    /// the Redis backend, this is the number of subscribers across all
+    /// processes.
+    ///
+    /// # Arguments
+    /// *  `event` - The event to broadcast.
+    async fn broadcast_counted(&self, event: T) -> Result<usize, Error>;
+
+    /// Broadcasts an event on this channel that expires after a while.
+    ///
+    /// Listeners discard the event if it is delivered after it has expired.
+    ///
+    /// # Arguments
+    /// *  `event` - The event to broadcast.
+    /// *  `ttl` - The time to live for the event.
+    async fn broadcast_with_ttl(
+        &self,
+        event: T,
+        ttl: Duration,
+    ) -> Result<(), Error>;
+
+    /// Broadcasts an event with metadata on this channel.
+    ///
+    /// # Arguments
+    /// *  `envelope` - The event and its metadata.
+    async fn broadcast_enveloped(
+        &self,
+        envelope: Envelope<T>,
+    ) -> Result<(), Error>;
+
     /// Listens on this channel.
+    ///
+    /// The listener is registered while the returned future runs, so only
+    /// events broadcast after it has completed are guaranteed to be
+    /// received; events broadcast between creating the channel and that
+    /// point are lost, unless the local backend replays them. Await this
+    /// before triggering anything whose events must not be missed. Local
+    /// channels also provide `listen_buffered`, which registers the listener
+    /// without awaiting.
     async fn listen(
         &self,
     ) -> Result<BoxStream<'static, Result<T, Error>>, Error>;
+
+    /// Listens on this channel for events broadcast from now on.
+    ///
+    /// This is the same as [`listen`](Channel::listen), but makes the
+    /// semantics explicit at the call site.
+    async fn listen_from_now(
+        &self,
+    ) -> Result<BoxStream<'static, Result<T, Error>>, Error> {
+        self.listen().await
+    }
+
+    /// Listens on this channel until it is closed.
+    ///
+    /// Streams returned by [`listen`](Channel::listen) on local channels end
+    /// once all pending events have been read, whereas this stream waits
+    /// for new events, and ends only when the channel is closed. This is the
+    /// same as `listen` for backends whose listen streams already wait.
+    async fn watch(
+        &self,
+    ) -> Result<BoxStream<'static, Result<T, Error>>, Error> {
+        self.listen().await
+    }
+
+    /// Listens on this channel, pairing each event with the topic on which
+    /// it was received.
+    ///
+    /// The topic does not include any prefix configured for the backend.
+    async fn listen_with_topic(
+        &self,
+    ) -> Result<BoxStream<'static, Result<(String, T), Error>>, Error>;
+
+    /// Listens on this channel, receiving each event with its metadata.
+    async fn listen_enveloped(
+        &self,
+    ) -> Result<BoxStream<'static, Result<Envelope<T>, Error>>, Error>;
 }
 
 /// A channel producing engine.
@@ -0,0 +1,227 @@
+//! # Integration with actix
+//!
+//! This module provides the [`WeruEngine`](WeruEngine) and
+//! [`WeruConnection`](WeruConnection) extractors, which let handlers take the
+//! database engine registered in the application data, or a connection
+//! acquired from it, as an argument:
+//!
+//! ```no_run
+//! use actix_web::{web, App, HttpResponse};
+//! use weru_database::actix::WeruConnection;
+//! use weru_database::sqlx::Executor;
+//! use weru_database::Engine;
+//!
+//! async fn handler(mut connection: WeruConnection) -> HttpResponse {
+//!     match connection.execute("SELECT 1").await {
+//!         Ok(_) => HttpResponse::Ok().finish(),
+//!         Err(_) => HttpResponse::InternalServerError().finish(),
+//!     }
+//! }
+//!
+//! fn app(engine: Engine) {
+//!     App::new()
+//!         .app_data(web::Data::new(engine))
+//!         .route("/", web::get().to(handler));
+//! }
+//! ```
+
+use std::future::{ready, Ready};
+use std::ops::{Deref, DerefMut};
+
+use actix_web::dev::Payload;
+use actix_web::http::StatusCode;
+use actix_web::{web, FromRequest, HttpRequest, ResponseError};
+use futures::future::LocalBoxFuture;
+
+use crate::{Connection, Engine};
+
+/// An extractor for the database engine.
+///
+/// The engine must be registered in the application data as a
+/// `web::Data<Engine>`; otherwise extraction fails with
+/// [`ExtractError::NotRegistered`](ExtractError::NotRegistered).
+#[derive(Clone)]
+pub struct WeruEngine(web::Data<Engine>);
+
+impl Deref for WeruEngine {
+    type Target = Engine;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.as_ref()
+    }
+}
+
+impl FromRequest for WeruEngine {
+    type Error = ExtractError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(
+            req.app_data::<web::Data<Engine>>()
+                .cloned()
+                .map(WeruEngine)
+                .ok_or(ExtractError::NotRegistered),
+        )
+    }
+}
+
+/// An extractor for a database connection.
+///
+/// The connection is acquired from the engine registered in the application
+/// data when the request is extracted, and returned to the pool once this
+/// value is dropped. If no connection can be acquired, extraction fails with
+/// [`ExtractError::Unavailable`](ExtractError::Unavailable).
+pub struct WeruConnection(Connection);
+
+impl WeruConnection {
+    /// Extracts the pooled connection.
+    pub fn into_inner(self) -> Connection {
+        self.0
+    }
+}
+
+impl Deref for WeruConnection {
+    type Target = Connection;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for WeruConnection {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl FromRequest for WeruConnection {
+    type Error = ExtractError;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let engine = req.app_data::<web::Data<Engine>>().cloned();
+        Box::pin(async move {
+            engine
+                .ok_or(ExtractError::NotRegistered)?
+                .connection()
+                .await
+                .map(WeruConnection)
+                .map_err(ExtractError::Unavailable)
+        })
+    }
+}
+
+/// An error occurring when extracting database values from a request.
+#[derive(Debug, thiserror::Error)]
+pub enum ExtractError {
+    /// No engine is registered in the application data.
+    ///
+    /// This results in an _Internal Server Error_ response.
+    #[error("no database engine is registered in the application data")]
+    NotRegistered,
+
+    /// No connection could be acquired, for example because the pool is
+    /// exhausted.
+    ///
+    /// This results in a _Service Unavailable_ response.
+    #[error("failed to acquire a database connection: {0}")]
+    Unavailable(crate::Error),
+}
+
+impl ResponseError for ExtractError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::NotRegistered => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test::TestRequest;
+
+    use super::*;
+
+    #[cfg(feature = "sqlite")]
+    #[actix_rt::test]
+    async fn extract_engine_registered() {
+        let (req, mut payload) = TestRequest::default()
+            .app_data(web::Data::new(engine(1).await))
+            .to_http_parts();
+
+        assert!(WeruEngine::from_request(&req, &mut payload).await.is_ok());
+    }
+
+    #[actix_rt::test]
+    async fn extract_engine_not_registered() {
+        let (req, mut payload) = TestRequest::default().to_http_parts();
+
+        assert_eq!(
+            Some(StatusCode::INTERNAL_SERVER_ERROR),
+            WeruEngine::from_request(&req, &mut payload)
+                .await
+                .err()
+                .map(|e| e.status_code()),
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[actix_rt::test]
+    async fn extract_connection_registered() {
+        let (req, mut payload) = TestRequest::default()
+            .app_data(web::Data::new(engine(1).await))
+            .to_http_parts();
+
+        assert!(WeruConnection::from_request(&req, &mut payload)
+            .await
+            .is_ok());
+    }
+
+    #[actix_rt::test]
+    async fn extract_connection_not_registered() {
+        let (req, mut payload) = TestRequest::default().to_http_parts();
+
+        assert_eq!(
+            Some(StatusCode::INTERNAL_SERVER_ERROR),
+            WeruConnection::from_request(&req, &mut payload)
+                .await
+                .err()
+                .map(|e| e.status_code()),
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[actix_rt::test]
+    async fn extract_connection_exhausted() {
+        let (req, mut payload) = TestRequest::default()
+            .app_data(web::Data::new(engine(1).await))
+            .to_http_parts();
+        let _held = WeruConnection::from_request(&req, &mut payload)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            Some(StatusCode::SERVICE_UNAVAILABLE),
+            WeruConnection::from_request(&req, &mut payload)
+                .await
+                .err()
+                .map(|e| e.status_code()),
+        );
+    }
+
+    /// An in-memory engine with a bounded pool.
+    ///
+    /// # Arguments
+    /// *  `max_connections` - The maximum number of connections.
+    #[cfg(feature = "sqlite")]
+    async fn engine(max_connections: u32) -> Engine {
+        sqlx::pool::PoolOptions::new()
+            .max_connections(max_connections)
+            .acquire_timeout(std::time::Duration::from_millis(10))
+            .connect("sqlite::memory:")
+            .await
+            .unwrap()
+            .into()
+    }
+}
@@ -12,10 +12,141 @@ use serde::{Deserialize, Serialize};
 pub struct Error(String);
 
 /// A serialised configuration.
-#[derive(Clone, Debug, Deserialize, Serialize)]
+///
+/// The database is specified either by a connection string, or by its
+/// individual [parts](Parts). The parts take precedence if both are set.
+#[derive(Clone, Deserialize, Serialize)]
 pub struct Configuration {
     /// The connection string.
+    #[serde(default)]
     pub connection_string: String,
+
+    /// The parts of the connection, used instead of the connection string.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parts: Option<Parts>,
+
+    /// The time in milliseconds after which statements are logged as slow.
+    ///
+    /// Slow statements are logged at the warning level with their SQL and
+    /// the time they took, but never with their bound values. This requires
+    /// the `trace` feature, and is ignored otherwise.
+    #[serde(default)]
+    pub slow_threshold: Option<u64>,
+
+    /// The time in milliseconds after which acquiring a connection from the
+    /// pool is logged as slow.
+    ///
+    /// This is logged at the warning level, and requires the `trace`
+    /// feature like [`slow_threshold`](Self::slow_threshold).
+    #[serde(default)]
+    pub acquire_threshold: Option<u64>,
+}
+
+/// The parts of a connection, as an alternative to a connection string.
+///
+/// Unlike in a connection string, no part needs to be URL encoded. For
+/// _SQLite_, only `database` is used, and names the database file.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Parts {
+    /// The host name of the server.
+    ///
+    /// This must be set for all backends except _SQLite_.
+    #[serde(default)]
+    pub host: String,
+
+    /// The port of the server, if not the default one.
+    #[serde(default)]
+    pub port: Option<u16>,
+
+    /// The user name.
+    #[serde(default)]
+    pub user: Option<String>,
+
+    /// The password.
+    #[serde(default)]
+    pub password: Option<String>,
+
+    /// The name of the database.
+    #[serde(default)]
+    pub database: Option<String>,
+
+    /// Whether to use a secure connection, if not the default of the backend.
+    #[serde(default)]
+    pub ssl_mode: Option<SslMode>,
+}
+
+/// Whether to use a secure connection.
+///
+/// _MySQL_ does not distinguish between verifying the host name and the
+/// certificate authority, so [`VerifyFull`](SslMode::VerifyFull) verifies the
+/// identity of the server.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SslMode {
+    /// Never use a secure connection.
+    Disable,
+
+    /// Use a secure connection if the server supports it.
+    Prefer,
+
+    /// Always use a secure connection.
+    Require,
+
+    /// Always use a secure connection, and verify the certificate authority.
+    VerifyCa,
+
+    /// Always use a secure connection, and verify the certificate authority
+    /// and the host name.
+    VerifyFull,
+}
+
+impl ::std::fmt::Debug for Parts {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        f.debug_struct("Parts")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("user", &self.user)
+            .field(
+                "password",
+                &self.password.as_ref().map(|_| Configuration::MASK),
+            )
+            .field("database", &self.database)
+            .field("ssl_mode", &self.ssl_mode)
+            .finish()
+    }
+}
+
+impl Configuration {
+    /// The replacement for redacted parts of connection strings.
+    const MASK: &'static str = "***";
+
+    /// The connection string with its password redacted.
+    ///
+    /// If the connection string is not a valid URL with a host, it is masked
+    /// entirely.
+    fn redacted_connection_string(&self) -> String {
+        match url::Url::parse(&self.connection_string) {
+            Ok(mut url) if !url.cannot_be_a_base() => {
+                if url.password().is_some() {
+                    // This only fails for URLs that cannot have a password
+                    let _ = url.set_password(Some(Self::MASK));
+                }
+                url.to_string()
+            }
+            _ => Self::MASK.into(),
+        }
+    }
+}
+
+impl ::std::fmt::Debug for Configuration {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        f.debug_struct("Configuration")
+            .field("connection_string", &self.redacted_connection_string())
+            .field("parts", &self.parts)
+            .field("slow_threshold", &self.slow_threshold)
+            .field("acquire_threshold", &self.acquire_threshold)
+            .finish()
+    }
 }
 
 impl From<sqlx::Error> for Error {
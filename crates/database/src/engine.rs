@@ -5,7 +5,9 @@
 
 use std::str::FromStr;
 
-use crate::{configuration, Configuration, Error};
+use futures::future::BoxFuture;
+
+use crate::{configuration, Configuration, Entity, Error};
 
 #[cfg(feature = "mysql")]
 mod backend {
@@ -15,12 +17,109 @@ mod backend {
     };
     pub use sqlx::MySql as Database;
 
+    /// The maximum number of parameters bound to a single statement.
+    pub const MAX_PARAMETERS: usize = 65535;
+
+    /// The backend compiled into this crate.
+    pub const BACKEND: super::Backend = super::Backend::MySql;
+
     #[macro_export]
     macro_rules! parameter {
         ($index:expr) => {
             "?"
         };
     }
+
+    /// A placeholder for a query parameter; the runtime version of
+    /// [`parameter!`](crate::parameter).
+    ///
+    /// Parameters are positional, so the index is ignored.
+    ///
+    /// # Arguments
+    /// *  `_index` - The 1-based index of the parameter.
+    pub fn placeholder(_index: usize) -> String {
+        "?".into()
+    }
+
+    /// The start of a statement creating an index.
+    ///
+    /// MySQL does not support `IF NOT EXISTS` for indexes, so
+    /// [`index_exists`] is used to ignore existing indexes instead.
+    #[macro_export]
+    macro_rules! create_index {
+        () => {
+            "CREATE INDEX "
+        };
+        (unique) => {
+            "CREATE UNIQUE INDEX "
+        };
+    }
+
+    /// The clause appended to a `SELECT` statement to lock the selected rows
+    /// until the end of the transaction.
+    #[macro_export]
+    macro_rules! for_update {
+        () => {
+            " FOR UPDATE"
+        };
+    }
+
+    /// Whether an error was caused by creating an index that already exists.
+    ///
+    /// # Arguments
+    /// *  `error` - The error to check.
+    pub fn index_exists(error: &crate::Error) -> bool {
+        /// The error number of a duplicate key name.
+        const ER_DUP_KEYNAME: u16 = 1061;
+
+        match error {
+            crate::Error::Database(e) => e
+                .try_downcast_ref::<sqlx::mysql::MySqlDatabaseError>()
+                .is_some_and(|e| e.number() == ER_DUP_KEYNAME),
+            _ => false,
+        }
+    }
+
+    /// Generates connect options from the parts of a configuration.
+    ///
+    /// # Arguments
+    /// *  `parts` - The parts of the configuration.
+    pub fn connect_options(
+        parts: &crate::configuration::Parts,
+    ) -> Result<ConnectOptions, crate::Error> {
+        use crate::configuration::SslMode;
+        use sqlx::mysql::MySqlSslMode;
+
+        if parts.host.is_empty() {
+            return Err(crate::Error::Configuration(
+                "the host is not set".into(),
+            ));
+        }
+
+        let mut options = ConnectOptions::new().host(&parts.host);
+        if let Some(port) = parts.port {
+            options = options.port(port);
+        }
+        if let Some(user) = &parts.user {
+            options = options.username(user);
+        }
+        if let Some(password) = &parts.password {
+            options = options.password(password);
+        }
+        if let Some(database) = &parts.database {
+            options = options.database(database);
+        }
+        if let Some(ssl_mode) = parts.ssl_mode {
+            options = options.ssl_mode(match ssl_mode {
+                SslMode::Disable => MySqlSslMode::Disabled,
+                SslMode::Prefer => MySqlSslMode::Preferred,
+                SslMode::Require => MySqlSslMode::Required,
+                SslMode::VerifyCa => MySqlSslMode::VerifyCa,
+                SslMode::VerifyFull => MySqlSslMode::VerifyIdentity,
+            });
+        }
+        Ok(options)
+    }
 }
 
 #[cfg(feature = "postgres")]
@@ -31,12 +130,99 @@ mod backend {
     };
     pub use sqlx::Postgres as Database;
 
+    /// The maximum number of parameters bound to a single statement.
+    pub const MAX_PARAMETERS: usize = 65535;
+
+    /// The backend compiled into this crate.
+    pub const BACKEND: super::Backend = super::Backend::Postgres;
+
     #[macro_export]
     macro_rules! parameter {
-        ($index:expre) => {
+        ($index:expr) => {
             concat!("$", stringify!($index))
         };
     }
+
+    /// A placeholder for a query parameter; the runtime version of
+    /// [`parameter!`](crate::parameter).
+    ///
+    /// # Arguments
+    /// *  `index` - The 1-based index of the parameter.
+    pub fn placeholder(index: usize) -> String {
+        format!("${}", index)
+    }
+
+    /// The start of a statement creating an index unless it exists.
+    #[macro_export]
+    macro_rules! create_index {
+        () => {
+            "CREATE INDEX IF NOT EXISTS "
+        };
+        (unique) => {
+            "CREATE UNIQUE INDEX IF NOT EXISTS "
+        };
+    }
+
+    /// The clause appended to a `SELECT` statement to lock the selected rows
+    /// until the end of the transaction.
+    #[macro_export]
+    macro_rules! for_update {
+        () => {
+            " FOR UPDATE"
+        };
+    }
+
+    /// Whether an error was caused by creating an index that already exists.
+    ///
+    /// Since indexes are created with `IF NOT EXISTS`, this is never the
+    /// case.
+    ///
+    /// # Arguments
+    /// *  `_error` - The error to check.
+    pub fn index_exists(_error: &crate::Error) -> bool {
+        false
+    }
+
+    /// Generates connect options from the parts of a configuration.
+    ///
+    /// # Arguments
+    /// *  `parts` - The parts of the configuration.
+    pub fn connect_options(
+        parts: &crate::configuration::Parts,
+    ) -> Result<ConnectOptions, crate::Error> {
+        use crate::configuration::SslMode;
+        use sqlx::postgres::PgSslMode;
+
+        if parts.host.is_empty() {
+            return Err(crate::Error::Configuration(
+                "the host is not set".into(),
+            ));
+        }
+
+        let mut options = ConnectOptions::new().host(&parts.host);
+        if let Some(port) = parts.port {
+            options = options.port(port);
+        }
+        if let Some(user) = &parts.user {
+            options = options.username(user);
+        }
+        if let Some(password) = &parts.password {
+            options = options.password(password);
+        }
+        if let Some(database) = &parts.database {
+            options = options.database(database);
+        }
+        if let Some(ssl_mode) = parts.ssl_mode {
+            options = options.ssl_mode(match ssl_mode {
+                SslMode::Disable => PgSslMode::Disable,
+                SslMode::Prefer => PgSslMode::Prefer,
+                SslMode::Require => PgSslMode::Require,
+                SslMode::VerifyCa => PgSslMode::VerifyCa,
+                SslMode::VerifyFull => PgSslMode::VerifyFull,
+            });
+        }
+        Ok(options)
+    }
 }
 
 #[cfg(feature = "sqlite")]
@@ -47,20 +233,364 @@ mod backend {
     };
     pub use sqlx::Sqlite as Database;
 
+    /// The maximum number of parameters bound to a single statement.
+    pub const MAX_PARAMETERS: usize = 32766;
+
+    /// The backend compiled into this crate.
+    pub const BACKEND: super::Backend = super::Backend::Sqlite;
+
     #[macro_export]
     macro_rules! parameter {
         ($index:expr) => {
             "?"
         };
     }
+
+    /// A placeholder for a query parameter; the runtime version of
+    /// [`parameter!`](crate::parameter).
+    ///
+    /// Parameters are positional, so the index is ignored.
+    ///
+    /// # Arguments
+    /// *  `_index` - The 1-based index of the parameter.
+    pub fn placeholder(_index: usize) -> String {
+        "?".into()
+    }
+
+    /// The start of a statement creating an index unless it exists.
+    #[macro_export]
+    macro_rules! create_index {
+        () => {
+            "CREATE INDEX IF NOT EXISTS "
+        };
+        (unique) => {
+            "CREATE UNIQUE INDEX IF NOT EXISTS "
+        };
+    }
+
+    /// The clause appended to a `SELECT` statement to lock the selected rows
+    /// until the end of the transaction.
+    ///
+    /// SQLite does not support row locks, so this is empty; start the
+    /// transaction with `BEGIN IMMEDIATE` to take the write lock up front.
+    #[macro_export]
+    macro_rules! for_update {
+        () => {
+            ""
+        };
+    }
+
+    /// Whether an error was caused by creating an index that already exists.
+    ///
+    /// Since indexes are created with `IF NOT EXISTS`, this is never the
+    /// case.
+    ///
+    /// # Arguments
+    /// *  `_error` - The error to check.
+    pub fn index_exists(_error: &crate::Error) -> bool {
+        false
+    }
+
+    /// Generates connect options from the parts of a configuration.
+    ///
+    /// Only the database, which is the name of the database file, is used.
+    ///
+    /// # Arguments
+    /// *  `parts` - The parts of the configuration.
+    pub fn connect_options(
+        parts: &crate::configuration::Parts,
+    ) -> Result<ConnectOptions, crate::Error> {
+        match &parts.database {
+            Some(database) => Ok(ConnectOptions::new().filename(database)),
+            None => Err(crate::Error::Configuration(
+                "the database file is not set".into(),
+            )),
+        }
+    }
 }
 
-pub use backend::{ConnectOptions, Database, Row, Statement};
+pub(crate) use backend::index_exists;
+pub use backend::{
+    placeholder, ConnectOptions, Database, Row, Statement, MAX_PARAMETERS,
+};
+
+/// A database backend.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    /// _MySQL_, enabled by the `mysql` feature.
+    MySql,
+
+    /// _PostgreSQL_, enabled by the `postgres` feature.
+    Postgres,
+
+    /// _SQLite_, enabled by the `sqlite` feature.
+    Sqlite,
+}
+
+/// The backend compiled into this crate.
+///
+/// This lets code building SQL dynamically branch on the capabilities of
+/// the backend.
+pub fn backend() -> Backend {
+    backend::BACKEND
+}
+
+/// Quotes an identifier, such as a table name, for use in a statement.
+///
+/// The identifier is enclosed in double quotes, and any double quotes in it
+/// are doubled, so it cannot end the identifier early. This is the form used
+/// for table names by statements generated for entities.
+///
+/// # Arguments
+/// *  `identifier` - The identifier to quote.
+pub fn quote_identifier(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
 
 pub type Pool = sqlx::pool::Pool<Database>;
 pub type Connection = sqlx::pool::PoolConnection<Database>;
 pub type Transaction<'a> = sqlx::Transaction<'a, Database>;
 
+/// A transaction isolation level.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IsolationLevel {
+    /// Transactions may see uncommitted changes of other transactions.
+    ReadUncommitted,
+
+    /// Transactions see only committed changes of other transactions.
+    ReadCommitted,
+
+    /// Repeated reads within a transaction return the same data.
+    RepeatableRead,
+
+    /// Transactions behave as if executed one after another.
+    Serializable,
+}
+
+impl ::std::fmt::Display for IsolationLevel {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        use IsolationLevel::*;
+        f.write_str(match self {
+            ReadUncommitted => "READ UNCOMMITTED",
+            ReadCommitted => "READ COMMITTED",
+            RepeatableRead => "REPEATABLE READ",
+            Serializable => "SERIALIZABLE",
+        })
+    }
+}
+
+/// Begins a transaction with a specific isolation level.
+///
+/// _SQLite_ transactions are always serialisable, so any other level is
+/// reported as an error for that backend.
+///
+/// # Arguments
+/// *  `connection` - The connection on which to begin the transaction.
+/// *  `level` - The isolation level of the transaction.
+pub async fn begin_with_isolation(
+    connection: &mut Connection,
+    level: IsolationLevel,
+) -> Result<Transaction<'_>, Error> {
+    use sqlx::Connection as _;
+
+    #[cfg(feature = "mysql")]
+    {
+        // The level applies to the next transaction started
+        sqlx::query(&format!("SET TRANSACTION ISOLATION LEVEL {}", level))
+            .execute(&mut **connection)
+            .await?;
+        connection.begin().await
+    }
+
+    #[cfg(feature = "postgres")]
+    {
+        // The level must be set before any other statement is executed
+        let mut tx = connection.begin().await?;
+        sqlx::query(&format!("SET TRANSACTION ISOLATION LEVEL {}", level))
+            .execute(&mut *tx)
+            .await?;
+        Ok(tx)
+    }
+
+    #[cfg(feature = "sqlite")]
+    {
+        if level == IsolationLevel::Serializable {
+            connection.begin().await
+        } else {
+            Err(Error::Configuration(
+                format!("isolation level {} is not supported by SQLite", level)
+                    .into(),
+            ))
+        }
+    }
+}
+
+/// A value bound to a query parameter.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Bind {
+    /// A `NULL` value.
+    Null,
+
+    /// A boolean value.
+    Bool(bool),
+
+    /// An integer value.
+    Int(i64),
+
+    /// A floating point value.
+    Float(f64),
+
+    /// A string value.
+    Text(String),
+
+    /// A binary value.
+    Bytes(Vec<u8>),
+}
+
+macro_rules! bind_from {
+    ($variant:ident: $($type:ty),*) => {
+        $(
+            impl From<$type> for Bind {
+                fn from(source: $type) -> Self {
+                    Self::$variant(source.into())
+                }
+            }
+        )*
+    };
+}
+
+bind_from!(Bool: bool);
+bind_from!(Int: i8, i16, i32, i64, u8, u16, u32);
+bind_from!(Float: f32, f64);
+bind_from!(Text: &str, String);
+bind_from!(Bytes: &[u8], Vec<u8>);
+
+impl<T> From<Option<T>> for Bind
+where
+    T: Into<Bind>,
+{
+    fn from(source: Option<T>) -> Self {
+        source.map(Into::into).unwrap_or(Self::Null)
+    }
+}
+
+/// The query type used by the typed query helpers.
+type QueryAs<'q, T> = sqlx::query::QueryAs<
+    'q,
+    Database,
+    T,
+    <Database as sqlx::Database>::Arguments<'q>,
+>;
+
+/// Prepares the statements used to access an entity type on a connection.
+///
+/// The statements are kept in the statement cache of the connection, so
+/// later operations on entities of this kind using the same connection do not
+/// have to prepare them again. This is intended to warm up connections at
+/// startup. The table of the entity must exist.
+///
+/// # Arguments
+/// *  `connection` - The connection on which to prepare the statements.
+pub async fn prepare<T>(connection: &mut Connection) -> Result<(), Error>
+where
+    T: Entity,
+{
+    use sqlx::Executor as _;
+
+    for sql in [T::CREATE, T::READ, T::READ_ALL, T::UPDATE, T::DELETE] {
+        (&mut **connection).prepare(sql).await?;
+    }
+    Ok(())
+}
+
+/// The number of statements in the statement cache of a connection.
+///
+/// # Arguments
+/// *  `connection` - The connection to inspect.
+pub fn cached_statements(connection: &Connection) -> usize {
+    use sqlx::Connection as _;
+
+    connection.cached_statements_size()
+}
+
+/// Creates a query returning typed rows with bound parameters.
+///
+/// # Arguments
+/// *  `sql` - The SQL statement.
+/// *  `binds` - The values to bind to the parameters, in order.
+fn bound<'q, T>(sql: &'q str, binds: &'q [Bind]) -> QueryAs<'q, T>
+where
+    T: for<'r> sqlx::FromRow<'r, Row>,
+{
+    binds
+        .iter()
+        .fold(sqlx::query_as(sql), |query, bind| match bind {
+            Bind::Null => query.bind(None::<String>),
+            Bind::Bool(value) => query.bind(*value),
+            Bind::Int(value) => query.bind(*value),
+            Bind::Float(value) => query.bind(*value),
+            Bind::Text(value) => query.bind(value.as_str()),
+            Bind::Bytes(value) => query.bind(value.as_slice()),
+        })
+}
+
+/// Runs a query and converts all returned rows.
+///
+/// # Arguments
+/// *  `e` - The database executor.
+/// *  `sql` - The SQL statement.
+/// *  `binds` - The values to bind to the parameters, in order.
+pub async fn query_as<'a, T, E>(
+    e: E,
+    sql: &str,
+    binds: &[Bind],
+) -> Result<Vec<T>, Error>
+where
+    T: for<'r> sqlx::FromRow<'r, Row> + Send + Unpin,
+    E: sqlx::Executor<'a, Database = Database>,
+{
+    bound(sql, binds).fetch_all(e).await
+}
+
+/// Runs a query and converts the first returned row, if any.
+///
+/// # Arguments
+/// *  `e` - The database executor.
+/// *  `sql` - The SQL statement.
+/// *  `binds` - The values to bind to the parameters, in order.
+pub async fn query_as_optional<'a, T, E>(
+    e: E,
+    sql: &str,
+    binds: &[Bind],
+) -> Result<Option<T>, Error>
+where
+    T: for<'r> sqlx::FromRow<'r, Row> + Send + Unpin,
+    E: sqlx::Executor<'a, Database = Database>,
+{
+    bound(sql, binds).fetch_optional(e).await
+}
+
+/// The columns of a row, as pairs of name and type name.
+///
+/// The type names are those reported by the backend, such as `"TEXT"` or
+/// `"INTEGER"`. This is intended for tooling displaying arbitrary rows.
+///
+/// # Arguments
+/// *  `row` - The row to inspect.
+pub fn columns(row: &Row) -> Vec<(String, String)> {
+    use sqlx::{Column as _, Row as _, TypeInfo as _};
+
+    row.columns()
+        .iter()
+        .map(|column| {
+            (
+                column.name().to_string(),
+                column.type_info().name().to_string(),
+            )
+        })
+        .collect()
+}
+
 /// An engine that produces pooled database connections.
 #[derive(Debug)]
 pub struct Engine {
@@ -73,6 +603,78 @@ impl Engine {
     pub async fn connection(&self) -> Result<Connection, Error> {
         self.pool.acquire().await
     }
+
+    /// Runs a function in a transaction.
+    ///
+    /// A connection is acquired and a transaction begun. If the function
+    /// succeeds, the transaction is committed, and otherwise it is rolled
+    /// back. Since the future returned by the function borrows the
+    /// transaction, it must be boxed, typically using
+    /// `Box::pin(async move { ... })`.
+    ///
+    /// # Arguments
+    /// *  `f` - The function to run.
+    pub async fn transaction<F, T>(&self, f: F) -> Result<T, Error>
+    where
+        F: for<'c> FnOnce(
+            &'c mut Transaction<'static>,
+        ) -> BoxFuture<'c, Result<T, Error>>,
+    {
+        let mut tx = self.pool.begin().await?;
+        match f(&mut tx).await {
+            Ok(value) => {
+                tx.commit().await?;
+                Ok(value)
+            }
+            Err(e) => {
+                tx.rollback().await?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Opens connections in the pool ahead of time.
+    ///
+    /// At least `n` connections, or the minimum number of connections of the
+    /// pool if larger, are acquired at once and then released, so that they
+    /// are idle in the pool when traffic arrives. The number is limited by
+    /// the maximum number of connections of the pool.
+    ///
+    /// # Arguments
+    /// *  `n` - The number of connections to open.
+    pub async fn warmup(&self, n: usize) -> Result<(), Error> {
+        let options = self.pool.options();
+        let n = n
+            .max(options.get_min_connections() as usize)
+            .min(options.get_max_connections() as usize);
+        futures::future::try_join_all((0..n).map(|_| self.pool.acquire()))
+            .await?;
+        Ok(())
+    }
+
+    /// Verifies that the database is reachable by running `SELECT 1`.
+    ///
+    /// This is intended for readiness probes, and is cheap to call.
+    pub async fn health_check(&self) -> Result<(), Error> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+        Ok(())
+    }
+
+    /// Closes this engine.
+    ///
+    /// This waits for all connections currently in use to be returned, and
+    /// then closes them. Once closed, attempts to acquire a connection fail
+    /// with [`Error::PoolClosed`](Error::PoolClosed).
+    ///
+    /// Calling this method more than once has no further effect.
+    pub async fn close(&self) {
+        self.pool.close().await
+    }
+
+    /// Whether this engine has been closed.
+    pub fn is_closed(&self) -> bool {
+        self.pool.is_closed()
+    }
 }
 
 impl From<Pool> for Engine {
@@ -84,13 +686,271 @@ impl From<Pool> for Engine {
 impl Configuration {
     /// Constructs a database engine from this configuration.
     pub async fn engine(&self) -> Result<Engine, configuration::Error> {
-        Ok(sqlx::Pool::connect_with(self.connect_options()?)
-            .await?
-            .into())
+        #[cfg_attr(not(feature = "trace"), allow(unused_mut))]
+        let mut options = self.connect_options()?;
+        #[cfg_attr(not(feature = "trace"), allow(unused_mut))]
+        let mut pool_options = sqlx::pool::PoolOptions::new();
+
+        #[cfg(feature = "trace")]
+        {
+            use log::LevelFilter;
+            use sqlx::ConnectOptions as _;
+            use std::time::Duration;
+
+            if let Some(threshold) = self.slow_threshold {
+                options = options.log_slow_statements(
+                    LevelFilter::Warn,
+                    Duration::from_millis(threshold),
+                );
+            }
+            if let Some(threshold) = self.acquire_threshold {
+                pool_options = pool_options
+                    .acquire_slow_level(LevelFilter::Warn)
+                    .acquire_slow_threshold(Duration::from_millis(threshold));
+            }
+        }
+
+        Ok(pool_options.connect_with(options).await?.into())
+    }
+
+    /// Verifies this configuration without connecting to the database.
+    ///
+    /// The connection string or parts are parsed the same way as by
+    /// [`engine`](Self::engine), but no connection is made.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        if self.parts.is_none() && self.connection_string.is_empty() {
+            return Err(vec!["the connection string must not be empty".into()]);
+        }
+        self.connect_options()
+            .map(|_| ())
+            .map_err(|e| vec![e.to_string()])
     }
 
     /// Generates database connect options.
+    ///
+    /// The parts are used if set, and otherwise the connection string.
     fn connect_options(&self) -> Result<ConnectOptions, Error> {
-        FromStr::from_str(&self.connection_string)
+        match &self.parts {
+            Some(parts) => backend::connect_options(parts),
+            None => FromStr::from_str(&self.connection_string),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "postgres")]
+    #[test]
+    fn parameter_postgres() {
+        assert_eq!("$1", crate::parameter!(1));
+        assert_eq!("$2", crate::parameter!(2));
+    }
+
+    #[cfg(feature = "postgres")]
+    #[test]
+    fn placeholder_postgres() {
+        assert_eq!(crate::Backend::Postgres, crate::backend());
+        assert_eq!(crate::parameter!(1), crate::placeholder(1));
+        assert_eq!(crate::parameter!(12), crate::placeholder(12));
+    }
+
+    #[cfg(any(feature = "mysql", feature = "sqlite"))]
+    #[test]
+    fn parameter_positional() {
+        assert_eq!("?", crate::parameter!(1));
+        assert_eq!("?", crate::parameter!(2));
+    }
+
+    #[cfg(any(feature = "mysql", feature = "sqlite"))]
+    #[test]
+    fn placeholder_positional() {
+        assert_ne!(crate::Backend::Postgres, crate::backend());
+        assert_eq!(crate::parameter!(1), crate::placeholder(1));
+        assert_eq!(crate::parameter!(12), crate::placeholder(12));
+    }
+
+    #[test]
+    fn quote_identifier_plain() {
+        assert_eq!("\"MyTable\"", crate::quote_identifier("MyTable"));
+        assert_eq!(stringify!("MyTable"), crate::quote_identifier("MyTable"));
+    }
+
+    #[test]
+    fn quote_identifier_quotes() {
+        assert_eq!(
+            "\"a\"\"; DROP TABLE b; --\"",
+            crate::quote_identifier("a\"; DROP TABLE b; --"),
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[actix_rt::test]
+    async fn engine_from_parts() {
+        let path = ::std::env::temp_dir().join("weru_engine_from_parts.db");
+        ::std::fs::File::create(&path).unwrap();
+        let configuration = crate::Configuration {
+            connection_string: String::new(),
+            parts: Some(crate::configuration::Parts {
+                host: String::new(),
+                port: None,
+                user: None,
+                password: Some("p@ss/word".into()),
+                database: Some(path.to_string_lossy().into_owned()),
+                ssl_mode: None,
+            }),
+            slow_threshold: None,
+            acquire_threshold: None,
+        };
+
+        let engine = configuration.engine().await.unwrap();
+
+        assert!(engine.health_check().await.is_ok());
+        assert!(!format!("{:?}", configuration).contains("p@ss/word"));
+        engine.close().await;
+        ::std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[actix_rt::test]
+    async fn warmup() {
+        let engine = crate::Configuration {
+            connection_string: "sqlite::memory:".into(),
+            parts: None,
+            slow_threshold: None,
+            acquire_threshold: None,
+        }
+        .engine()
+        .await
+        .unwrap();
+        let max = engine.pool.options().get_max_connections();
+
+        engine.warmup(3).await.unwrap();
+        assert_eq!(3, engine.pool.size());
+        engine.warmup(max as usize + 1).await.unwrap();
+        assert_eq!(max, engine.pool.size());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[actix_rt::test]
+    async fn engine_with_thresholds() {
+        let engine = crate::Configuration {
+            connection_string: "sqlite::memory:".into(),
+            parts: None,
+            slow_threshold: Some(0),
+            acquire_threshold: Some(0),
+        }
+        .engine()
+        .await
+        .unwrap();
+
+        assert!(engine.health_check().await.is_ok());
+    }
+
+    #[cfg(all(feature = "sqlite", feature = "trace"))]
+    #[actix_rt::test]
+    async fn engine_with_thresholds_logs() {
+        use std::sync::Mutex;
+
+        /// A logger recording the targets of warnings.
+        struct Logger(Mutex<Vec<String>>);
+
+        impl log::Log for Logger {
+            fn enabled(&self, metadata: &log::Metadata) -> bool {
+                metadata.level() <= log::Level::Warn
+            }
+
+            fn log(&self, record: &log::Record) {
+                if self.enabled(record.metadata()) {
+                    self.0.lock().unwrap().push(record.target().into());
+                }
+            }
+
+            fn flush(&self) {}
+        }
+
+        static LOGGER: Logger = Logger(Mutex::new(Vec::new()));
+        log::set_logger(&LOGGER).unwrap();
+        log::set_max_level(log::LevelFilter::Warn);
+
+        let engine = crate::Configuration {
+            connection_string: "sqlite::memory:".into(),
+            parts: None,
+            slow_threshold: Some(0),
+            acquire_threshold: None,
+        }
+        .engine()
+        .await
+        .unwrap();
+        engine.health_check().await.unwrap();
+
+        assert!(LOGGER
+            .0
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|target| target == "sqlx::query"));
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[actix_rt::test]
+    async fn engine_from_parts_without_database() {
+        let configuration = crate::Configuration {
+            connection_string: "sqlite::memory:".into(),
+            parts: Some(crate::configuration::Parts {
+                host: String::new(),
+                port: None,
+                user: None,
+                password: None,
+                database: None,
+                ssl_mode: None,
+            }),
+            slow_threshold: None,
+            acquire_threshold: None,
+        };
+
+        assert!(configuration.engine().await.is_err());
+    }
+
+    #[cfg(any(feature = "mysql", feature = "postgres"))]
+    #[test]
+    fn validate_parts_without_host() {
+        let configuration = crate::Configuration {
+            connection_string: String::new(),
+            parts: Some(crate::configuration::Parts {
+                host: String::new(),
+                port: None,
+                user: None,
+                password: None,
+                database: Some("database".into()),
+                ssl_mode: None,
+            }),
+            slow_threshold: None,
+            acquire_threshold: None,
+        };
+
+        assert!(configuration.validate().is_err());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn validate() {
+        let valid = crate::Configuration {
+            connection_string: "sqlite::memory:".into(),
+            parts: None,
+            slow_threshold: None,
+            acquire_threshold: None,
+        };
+        let empty = crate::Configuration {
+            connection_string: String::new(),
+            parts: None,
+            slow_threshold: None,
+            acquire_threshold: None,
+        };
+
+        assert_eq!(Ok(()), valid.validate());
+        assert_eq!(
+            Err(vec!["the connection string must not be empty".to_string()]),
+            empty.validate(),
+        );
     }
 }
@@ -1,14 +1,21 @@
+#[cfg(feature = "actix")]
+pub mod actix;
+
 pub mod configuration;
 pub use configuration::Configuration;
 
 pub mod engine;
-pub use engine::{Connection, Database, Engine, Row, Statement, Transaction};
+pub use engine::{
+    backend, begin_with_isolation, cached_statements, columns, placeholder,
+    prepare, query_as, query_as_optional, quote_identifier, Backend, Bind,
+    Connection, Database, Engine, IsolationLevel, Row, Statement, Transaction,
+};
 
 pub mod error;
 pub use error::Error;
 
 pub mod traits;
-pub use traits::Entity;
+pub use traits::{Entity, EntityHooks};
 
 pub use sqlx;
 
@@ -1,7 +1,25 @@
 use async_trait::async_trait;
+use futures::stream::{BoxStream, StreamExt};
 use sqlx;
 
-use super::{Database, Error};
+use super::{Database, Error, Transaction};
+
+/// Hooks run when entities are changed.
+///
+/// Implement this trait for an entity defined with
+/// `#[entity(Table, hooks)]` to override the hooks of
+/// [`Entity`](Entity::on_created). All hooks do nothing by default.
+#[async_trait]
+pub trait EntityHooks: Sync {
+    /// Called after this item has been inserted.
+    async fn on_created(&self) {}
+
+    /// Called after this item has been updated.
+    async fn on_updated(&self) {}
+
+    /// Called after this item has been deleted.
+    async fn on_deleted(&self) {}
+}
 
 /// A database entity.
 #[async_trait]
@@ -22,12 +40,76 @@ pub trait Entity:
     /// The SQL statement used to read a single item of this kind.
     const READ: &'static str;
 
+    /// The SQL statement used to read all items of this kind.
+    const READ_ALL: &'static str;
+
     /// The SQL statement used to update an item of this kind.
     const UPDATE: &'static str;
 
     /// The SQL statement used to delete an item of this kind.
     const DELETE: &'static str;
 
+    /// The SQL statements used to create the indexes of this kind.
+    ///
+    /// There is one statement for every field marked with `#[index]` or
+    /// `#[index(unique)]`.
+    const INDEXES: &'static [&'static str];
+
+    /// The SQL statement used to insert an item of this kind into a specific
+    /// table.
+    ///
+    /// # Arguments
+    /// *  `table` - The name of the table.
+    fn create_sql(table: &str) -> String;
+
+    /// The SQL statement used to read a single item of this kind from a
+    /// specific table.
+    ///
+    /// # Arguments
+    /// *  `table` - The name of the table.
+    fn read_sql(table: &str) -> String;
+
+    /// The SQL statement used to read all items of this kind from a specific
+    /// table.
+    ///
+    /// # Arguments
+    /// *  `table` - The name of the table.
+    fn read_all_sql(table: &str) -> String;
+
+    /// The SQL statement used to update an item of this kind in a specific
+    /// table.
+    ///
+    /// # Arguments
+    /// *  `table` - The name of the table.
+    fn update_sql(table: &str) -> String;
+
+    /// The SQL statement used to delete an item of this kind from a specific
+    /// table.
+    ///
+    /// # Arguments
+    /// *  `table` - The name of the table.
+    fn delete_sql(table: &str) -> String;
+
+    /// Creates the indexes of this kind, unless they already exist.
+    ///
+    /// This is intended to be called at startup.
+    ///
+    /// # Arguments
+    /// *  `e` - The connection, pool or transaction to use.
+    async fn ensure_indexes<'a, E>(e: E) -> Result<(), Error>
+    where
+        E: ::sqlx::Acquire<'a, Database = Database> + Send,
+    {
+        let mut connection = e.acquire().await?;
+        for sql in Self::INDEXES {
+            match sqlx::query(sql).execute(&mut *connection).await {
+                Err(e) if !crate::engine::index_exists(&e) => return Err(e),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
     /// Inserts this item to the database.
     ///
     /// # Arguments
@@ -36,6 +118,29 @@ pub trait Entity:
     where
         E: ::sqlx::Executor<'a, Database = Database>;
 
+    /// Inserts this item to a specific table.
+    ///
+    /// # Arguments
+    /// *  `e` - The database executor.
+    /// *  `table` - The name of the table.
+    async fn create_in<'a, E>(&self, e: E, table: &str) -> Result<(), Error>
+    where
+        E: ::sqlx::Executor<'a, Database = Database>;
+
+    /// Inserts several items to the database.
+    ///
+    /// The items are inserted with as few statements as the parameter limit
+    /// of the database allows, in a single transaction. The number of
+    /// inserted rows is returned.
+    ///
+    /// # Arguments
+    /// *  `e` - The connection, pool or transaction to use.
+    /// *  `items` - The items to insert.
+    async fn create_many<'a, E>(e: E, items: &[Self]) -> Result<u64, Error>
+    where
+        Self: Sync,
+        E: ::sqlx::Acquire<'a, Database = Database> + Send;
+
     /// Loads an item of this kind from the database.
     ///
     /// If no item corresponding to the keys exists, `Ok(None)` is
@@ -50,6 +155,120 @@ pub trait Entity:
         sqlx::query_as(Self::READ).bind(key).fetch_optional(e).await
     }
 
+    /// Loads an item of this kind and locks it until the end of a
+    /// transaction.
+    ///
+    /// Other transactions attempting to lock or modify the item will wait
+    /// until `tx` is committed or rolled back, which makes read-modify-write
+    /// sequences safe under concurrency. If no item corresponding to the key
+    /// exists, `Ok(None)` is returned.
+    ///
+    /// SQLite does not support row locks, so this is a plain read with that
+    /// backend; concurrent writers are only prevented if the transaction
+    /// already holds the write lock, for instance after `BEGIN IMMEDIATE`.
+    ///
+    /// # Arguments
+    /// *  `tx` - The transaction holding the lock.
+    /// *  `key` - The key of the item.
+    async fn read_for_update<'a>(
+        tx: &mut Transaction<'a>,
+        key: &Self::Key,
+    ) -> Result<Option<Self>, Error>;
+
+    /// Loads an item of this kind from a specific table.
+    ///
+    /// If no item corresponding to the keys exists, `Ok(None)` is
+    /// returned.
+    ///
+    /// # Arguments
+    /// *  `e` - The database executor.
+    /// *  `table` - The name of the table.
+    async fn read_in<'a, E>(
+        e: E,
+        table: &str,
+        key: &Self::Key,
+    ) -> Result<Option<Self>, Error>
+    where
+        E: ::sqlx::Executor<'a, Database = Database>,
+    {
+        let sql = Self::read_sql(table);
+        sqlx::query_as(&sql).bind(key).fetch_optional(e).await
+    }
+
+    /// Loads several items of this kind from the database.
+    ///
+    /// The items are read with as few statements as the parameter limit of
+    /// the database allows. They are returned in no particular order, and
+    /// keys without a corresponding item are ignored. If `keys` is empty,
+    /// the database is not queried.
+    ///
+    /// # Arguments
+    /// *  `e` - The connection, pool or transaction to use.
+    /// *  `keys` - The keys of the items to load.
+    async fn read_many<'a, E>(
+        e: E,
+        keys: &[Self::Key],
+    ) -> Result<Vec<Self>, Error>
+    where
+        Self: Send,
+        E: ::sqlx::Acquire<'a, Database = Database> + Send;
+
+    /// Reloads this item from the database.
+    ///
+    /// All fields are overwritten with the values currently stored, which is
+    /// useful for columns maintained by the database. If the item no longer
+    /// exists, [`Error::RowNotFound`](Error::RowNotFound) is returned and this
+    /// item is left unmodified.
+    ///
+    /// # Arguments
+    /// *  `e` - The database executor.
+    async fn refresh<'a, E>(&mut self, e: E) -> Result<(), Error>
+    where
+        Self: Send,
+        E: ::sqlx::Executor<'a, Database = Database>,
+    {
+        let current = Self::read(e, self.key()).await?;
+        *self = current.ok_or(Error::RowNotFound)?;
+        Ok(())
+    }
+
+    /// Streams all items of this kind from the database.
+    ///
+    /// Rows are decoded lazily as the stream is polled, so the entire table
+    /// is never kept in memory. Rows failing to decode are yielded as errors.
+    ///
+    /// # Arguments
+    /// *  `e` - The database executor.
+    fn stream<'e, E>(e: E) -> BoxStream<'e, Result<Self, Error>>
+    where
+        Self: Send + 'e,
+        E: ::sqlx::Executor<'e, Database = Database> + 'e,
+    {
+        sqlx::query(Self::READ_ALL)
+            .fetch(e)
+            .map(|row| row.and_then(|row| Self::from_row(&row)))
+            .boxed()
+    }
+
+    /// Lists a page of items of this kind, ordered by key.
+    ///
+    /// Only items with a key greater than `after` are listed, so passing the
+    /// key of the last item of a page yields the next page. This remains
+    /// stable when items are inserted or removed concurrently.
+    ///
+    /// # Arguments
+    /// *  `e` - The database executor.
+    /// *  `after` - The key after which to start listing. If this is `None`,
+    ///    listing starts with the first item.
+    /// *  `limit` - The maximum number of items to list.
+    async fn list_after<'a, E>(
+        e: E,
+        after: Option<Self::Key>,
+        limit: i64,
+    ) -> Result<Vec<Self>, Error>
+    where
+        E: ::sqlx::Executor<'a, Database = Database>;
+
     /// Updates this item in the database.
     ///
     /// # Arguments
@@ -58,11 +277,64 @@ pub trait Entity:
     where
         E: ::sqlx::Executor<'a, Database = Database>;
 
+    /// Updates only the columns of an item set in a description.
+    ///
+    /// Columns not set in the description are left as stored, so concurrent
+    /// changes to them are not overwritten. If no columns are set, nothing is
+    /// done.
+    ///
+    /// Unlike [`update`](Entity::update), this does not call
+    /// [`on_updated`](Entity::on_updated), since the updated item is never
+    /// loaded; read it back and call the hook explicitly if required.
+    ///
+    /// # Arguments
+    /// *  `e` - The database executor.
+    /// *  `key` - The key of the item to update.
+    /// *  `description` - The values to set.
+    async fn update_partial<'a, E>(
+        e: E,
+        key: &Self::Key,
+        description: &Self::Description,
+    ) -> Result<(), Error>
+    where
+        Self::Description: Sync,
+        E: ::sqlx::Executor<'a, Database = Database>;
+
+    /// Updates this item in a specific table.
+    ///
+    /// # Arguments
+    /// *  `e` - The database executor.
+    /// *  `table` - The name of the table.
+    async fn update_in<'a, E>(&self, e: E, table: &str) -> Result<(), Error>
+    where
+        E: ::sqlx::Executor<'a, Database = Database>;
+
     /// Deletes this item from the database.
     ///
+    /// If the item does not exist, [`Error::RowNotFound`](Error::RowNotFound)
+    /// is returned; use [`delete_if_exists`](Entity::delete_if_exists) for
+    /// idempotent deletion.
+    ///
     /// # Arguments
     /// *  `e` - The database executor.
     async fn delete<'a, E>(&self, e: E) -> Result<(), Error>
+    where
+        E: sqlx::Executor<'a, Database = Database>,
+    {
+        if self.delete_if_exists(e).await? {
+            Ok(())
+        } else {
+            Err(Error::RowNotFound)
+        }
+    }
+
+    /// Deletes this item from the database if it exists.
+    ///
+    /// This returns whether the item existed.
+    ///
+    /// # Arguments
+    /// *  `e` - The database executor.
+    async fn delete_if_exists<'a, E>(&self, e: E) -> Result<bool, Error>
     where
         E: sqlx::Executor<'a, Database = Database>,
     {
@@ -72,6 +344,28 @@ pub trait Entity:
             .await?
             .rows_affected();
         if count > 0 {
+            self.on_deleted().await;
+        }
+        Ok(count > 0)
+    }
+
+    /// Deletes this item from a specific table.
+    ///
+    /// # Arguments
+    /// *  `e` - The database executor.
+    /// *  `table` - The name of the table.
+    async fn delete_in<'a, E>(&self, e: E, table: &str) -> Result<(), Error>
+    where
+        E: sqlx::Executor<'a, Database = Database>,
+    {
+        let sql = Self::delete_sql(table);
+        let count = ::sqlx::query(&sql)
+            .bind(self.key())
+            .execute(e)
+            .await?
+            .rows_affected();
+        if count > 0 {
+            self.on_deleted().await;
             Ok(())
         } else {
             Err(Error::RowNotFound)
@@ -88,4 +382,43 @@ pub trait Entity:
 
     /// The key of this item.
     fn key(&self) -> &Self::Key;
+
+    /// Called after this item has been inserted.
+    ///
+    /// This is called by [`create`](Entity::create),
+    /// [`create_in`](Entity::create_in) and, once the transaction has been
+    /// committed, [`create_many`](Entity::create_many), but only if the
+    /// operation succeeded. It does nothing unless the entity is defined
+    /// with hooks; please see [`EntityHooks`].
+    async fn on_created(&self)
+    where
+        Self: Sync,
+    {
+    }
+
+    /// Called after this item has been updated.
+    ///
+    /// This is called by [`update`](Entity::update) and
+    /// [`update_in`](Entity::update_in), but only if the operation succeeded;
+    /// [`update_partial`](Entity::update_partial) does not call it. It does
+    /// nothing unless the entity is defined with hooks; please see
+    /// [`EntityHooks`].
+    async fn on_updated(&self)
+    where
+        Self: Sync,
+    {
+    }
+
+    /// Called after this item has been deleted.
+    ///
+    /// This is called by [`delete`](Entity::delete),
+    /// [`delete_if_exists`](Entity::delete_if_exists) and
+    /// [`delete_in`](Entity::delete_in), but only if the item existed. It
+    /// does nothing unless the entity is defined with hooks; please see
+    /// [`EntityHooks`].
+    async fn on_deleted(&self)
+    where
+        Self: Sync,
+    {
+    }
 }
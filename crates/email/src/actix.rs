@@ -0,0 +1,141 @@
+//! # Integration with actix
+//!
+//! This module provides the [`WeruSender`](WeruSender) extractor, which lets
+//! handlers take the e-mail sender registered in the application data as an
+//! argument:
+//!
+//! ```no_run
+//! use std::collections::HashMap;
+//!
+//! use actix_web::{web, App, HttpResponse};
+//! use weru_email::actix::WeruSender;
+//! use weru_email::Engine;
+//!
+//! async fn handler(sender: WeruSender) -> HttpResponse {
+//!     match sender
+//!         .send(
+//!             "user@example.com".parse().unwrap(),
+//!             &["en".into()],
+//!             &"welcome".into(),
+//!             &HashMap::new(),
+//!         )
+//!         .await
+//!     {
+//!         Ok(_) => HttpResponse::Ok().finish(),
+//!         Err(_) => HttpResponse::InternalServerError().finish(),
+//!     }
+//! }
+//!
+//! async fn app(engine: &Engine) {
+//!     App::new()
+//!         .app_data(web::Data::new(engine.sender().await))
+//!         .route("/", web::post().to(handler));
+//! }
+//! ```
+
+use std::future::{ready, Ready};
+use std::ops::Deref;
+
+use actix_web::dev::Payload;
+use actix_web::{web, FromRequest, HttpRequest, ResponseError};
+
+use crate::Sender;
+
+/// An extractor for the e-mail sender.
+///
+/// The sender must be registered in the application data as a
+/// `web::Data<Box<dyn Sender>>`; otherwise extraction fails with
+/// [`NotRegistered`](NotRegistered).
+#[derive(Clone)]
+pub struct WeruSender(web::Data<Box<dyn Sender>>);
+
+impl Deref for WeruSender {
+    type Target = dyn Sender;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.as_ref().as_ref()
+    }
+}
+
+impl FromRequest for WeruSender {
+    type Error = NotRegistered;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(
+            req.app_data::<web::Data<Box<dyn Sender>>>()
+                .cloned()
+                .map(WeruSender)
+                .ok_or(NotRegistered),
+        )
+    }
+}
+
+/// The error returned when no e-mail sender is registered.
+///
+/// This results in an _Internal Server Error_ response.
+#[derive(Debug, thiserror::Error)]
+#[error("no e-mail sender is registered in the application data")]
+pub struct NotRegistered;
+
+impl ResponseError for NotRegistered {}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use actix_web::http::StatusCode;
+    use actix_web::test::TestRequest;
+
+    use super::*;
+    use crate::configuration::{Templates, Transport};
+    use crate::Configuration;
+
+    #[cfg(feature = "drop")]
+    #[actix_rt::test]
+    async fn extract_registered() {
+        // Arrange
+        let engine = Configuration {
+            from: "Sender <sender@domain>".parse().unwrap(),
+            from_names: Default::default(),
+            templates: Templates {
+                default_language: "l1".into(),
+                path: PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                    .join("resources/test/email/template/valid.toml")
+                    .to_string_lossy()
+                    .into(),
+            },
+            transport: Transport::Drop(
+                crate::engine::backends::drop::Configuration,
+            ),
+            retry: None,
+        }
+        .engine()
+        .await
+        .unwrap();
+        let (req, mut payload) = TestRequest::default()
+            .app_data(web::Data::new(engine.sender().await))
+            .to_http_parts();
+
+        // Act
+        let actual = WeruSender::from_request(&req, &mut payload).await;
+
+        // Assert
+        assert!(actual.is_ok());
+    }
+
+    #[actix_rt::test]
+    async fn extract_not_registered() {
+        // Arrange
+        let (req, mut payload) = TestRequest::default().to_http_parts();
+
+        // Act
+        let actual = WeruSender::from_request(&req, &mut payload).await;
+
+        // Assert
+        assert_eq!(
+            Some(StatusCode::INTERNAL_SERVER_ERROR),
+            actual.err().map(|e| e.status_code()),
+        );
+    }
+}
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use lettre::message::Mailbox;
 use lettre::transport::smtp::Error as SMTPError;
 use serde::{Deserialize, Serialize};
@@ -14,11 +16,45 @@ pub struct Configuration {
     /// The sender of e-mails.
     pub from: Mailbox,
 
+    /// The display names of the sender for specific languages.
+    ///
+    /// The address of [`from`](Self::from) is always used, but its name is
+    /// replaced with the one for the language of the template sent, if any.
+    #[serde(default)]
+    pub from_names: HashMap<Language, String>,
+
     /// The templates.
     pub templates: Templates,
 
     /// The transport backend to use.
     pub transport: Transport,
+
+    /// How to retry sending e-mails failing with transient errors.
+    ///
+    /// If this is not set, sending is attempted only once.
+    #[serde(default)]
+    pub retry: Option<Retry>,
+}
+
+/// Contains information about how to retry failed attempts.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Retry {
+    /// The maximum number of attempts, including the first one.
+    pub attempts: u32,
+
+    /// The delay in milliseconds before the first retry.
+    ///
+    /// The delay is doubled for every subsequent retry.
+    pub backoff: u64,
+}
+
+impl Default for Retry {
+    fn default() -> Self {
+        Self {
+            attempts: 1,
+            backoff: 0,
+        }
+    }
 }
 
 /// Contains information about templates.
@@ -44,9 +80,31 @@ pub enum Transport {
     #[cfg(feature = "drop")]
     Drop(crate::engine::backends::drop::Configuration),
 
+    /// A transport recording e-mails.
+    ///
+    /// Using this will keep all e-mails in memory; this is intended for
+    /// tests.
+    #[cfg(feature = "mock")]
+    Mock(crate::engine::backends::mock::Configuration),
+
     /// An SMTP transport.
     #[cfg(feature = "smtp")]
     SMTP(crate::engine::backends::smtp::Configuration),
+
+    /// A chain of transports tried in order.
+    ///
+    /// The next transport is tried only if the previous one cannot be
+    /// reached; other failures are returned immediately.
+    Fallback {
+        /// The transports, in the order they are tried.
+        transports: Vec<Transport>,
+    },
+}
+
+impl From<&str> for Error {
+    fn from(source: &str) -> Self {
+        Self(source.to_string())
+    }
 }
 
 impl From<TemplateError> for Error {
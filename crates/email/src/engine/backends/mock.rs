@@ -0,0 +1,356 @@
+//! # The recording e-mail sender
+//!
+//! A mock e-mail sender that records all e-mails instead of sending them.
+//! This is intended for tests that need to verify what would have been sent,
+//! so it is only available with the `mock` feature, which is not enabled by
+//! default; enable it for dev-dependencies only.
+
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use lettre::address::Envelope;
+use lettre::{AsyncTransport, Message};
+use serde::{Deserialize, Serialize};
+
+use crate::configuration;
+
+/// The configuration for the mock sender.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Configuration;
+
+impl Configuration {
+    /// Constructs a transport from this configuration.
+    pub async fn transport(&self) -> Result<Transport, configuration::Error> {
+        Ok(Transport::default())
+    }
+}
+
+/// An error occurring when sending using the mock transport.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// The message log could not be accessed.
+    #[error("failed to access message log")]
+    Poisoned,
+
+    /// Only complete messages can be recorded.
+    #[error("raw messages are not supported")]
+    Raw,
+}
+
+/// A transport recording all sent messages.
+///
+/// Messages are shared between all clones of a transport.
+#[derive(Clone, Debug, Default)]
+pub struct Transport {
+    /// The messages sent so far.
+    messages: Arc<Mutex<Vec<Message>>>,
+}
+
+impl Transport {
+    /// Retrieves and clears the messages sent so far.
+    pub fn take(&self) -> Vec<Message> {
+        self.messages
+            .lock()
+            .map(|mut messages| messages.drain(..).collect())
+            .unwrap_or_default()
+    }
+}
+
+#[async_trait]
+impl AsyncTransport for Transport {
+    type Ok = ();
+    type Error = Error;
+
+    async fn send(&self, message: Message) -> Result<Self::Ok, Self::Error> {
+        self.messages
+            .lock()
+            .map_err(|_| Error::Poisoned)?
+            .push(message);
+        Ok(())
+    }
+
+    async fn send_raw(
+        &self,
+        _envelope: &Envelope,
+        _email: &[u8],
+    ) -> Result<Self::Ok, Self::Error> {
+        Err(Error::Raw)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    use lettre::message::header::ContentType;
+    use lettre::message::Mailbox;
+
+    use crate::configuration::{
+        Templates, Transport as TransportConfiguration,
+    };
+    use crate::engine::Transport as EngineTransport;
+    use crate::template::{Attachment, Disposition};
+
+    #[actix_rt::test]
+    async fn send_take() {
+        // Arrange
+        let engine = configuration().engine().await.unwrap();
+        let sender = engine.sender().await;
+
+        // Act
+        sender
+            .send_one(
+                "Tester <test@test.com>".parse().unwrap(),
+                &["l1".into()],
+                &"t1".into(),
+                &HashMap::new(),
+            )
+            .await
+            .unwrap();
+        let actual = engine.take_messages().unwrap();
+
+        // Assert
+        assert_eq!(1, actual.len());
+        assert_eq!(
+            vec!["test@test.com".parse::<lettre::Address>().unwrap()],
+            actual[0].envelope().to(),
+        );
+        assert!(matches!(engine.transport, EngineTransport::Mock(_)));
+        assert!(engine.take_messages().unwrap().is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn send_from_take() {
+        // Arrange
+        let engine = configuration().engine().await.unwrap();
+        let sender = engine.sender().await;
+        let expected = "billing@domain".parse::<lettre::Address>().unwrap();
+
+        // Act
+        sender
+            .send_from(
+                Some("Billing <billing@domain>".parse().unwrap()),
+                "Tester <test@test.com>".parse::<Mailbox>().unwrap().into(),
+                &["l1".into()],
+                &"t1".into(),
+                &HashMap::new(),
+            )
+            .await
+            .unwrap();
+        let actual = engine.take_messages().unwrap();
+
+        // Assert
+        assert_eq!(1, actual.len());
+        assert_eq!(Some(&expected), actual[0].envelope().from());
+    }
+
+    #[actix_rt::test]
+    async fn send_attachments() {
+        // Arrange
+        let engine = configuration().engine().await.unwrap();
+        let sender = engine.sender().await;
+
+        // Act
+        sender
+            .send_one(
+                "Tester <test@test.com>".parse().unwrap(),
+                &["l1".into()],
+                &"t1".into(),
+                &HashMap::new(),
+            )
+            .await
+            .unwrap();
+        let actual = engine.take_messages().unwrap();
+
+        // Assert
+        let formatted = String::from_utf8(actual[0].formatted()).unwrap();
+        assert!(formatted.contains("Content-Type: multipart/mixed"));
+        assert!(formatted
+            .contains("Content-Disposition: attachment; filename=\"file1\""));
+        assert!(formatted.contains("Content-Disposition: inline\r\n"));
+        assert!(formatted.contains("Content-ID: <file2>"));
+    }
+
+    #[actix_rt::test]
+    async fn message_take() {
+        // Arrange
+        let engine = configuration().engine().await.unwrap();
+        let sender = engine.sender().await;
+
+        // Act
+        sender
+            .message()
+            .to("Tester <test@test.com>".parse().unwrap())
+            .cc("Copy <copy@test.com>".parse().unwrap())
+            .bcc("Hidden <hidden@test.com>".parse().unwrap())
+            .reply_to("Support <support@domain>".parse().unwrap())
+            .languages(["l1".into()])
+            .template("t1")
+            .replace("replace", "replaced value")
+            .attach(
+                "extra",
+                Attachment::new(
+                    ContentType::TEXT_PLAIN,
+                    Disposition::Attachment,
+                    b"extra data".to_vec(),
+                ),
+            )
+            .send()
+            .await
+            .unwrap();
+        let actual = engine.take_messages().unwrap();
+
+        // Assert
+        assert_eq!(1, actual.len());
+        assert_eq!(
+            vec![
+                "test@test.com".parse::<lettre::Address>().unwrap(),
+                "copy@test.com".parse().unwrap(),
+                "hidden@test.com".parse().unwrap(),
+            ],
+            actual[0].envelope().to(),
+        );
+        let formatted = String::from_utf8(actual[0].formatted()).unwrap();
+        assert!(formatted.contains("Cc: Copy <copy@test.com>\r\n"));
+        assert!(!formatted.contains("hidden@test.com"));
+        assert!(formatted.contains("Reply-To: Support <support@domain>\r\n"));
+        assert!(formatted.contains("Replaced: replaced value"));
+        assert!(formatted
+            .contains("Content-Disposition: attachment; filename=\"extra\""));
+    }
+
+    #[actix_rt::test]
+    async fn message_missing_template() {
+        // Arrange
+        let engine = configuration().engine().await.unwrap();
+        let sender = engine.sender().await;
+
+        // Act
+        let actual = sender
+            .message()
+            .to("Tester <test@test.com>".parse().unwrap())
+            .send()
+            .await;
+
+        // Assert
+        assert!(matches!(actual, Err(crate::Error::MissingTemplate)));
+        assert!(engine.take_messages().unwrap().is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn health_check() {
+        // Arrange
+        let engine = configuration().engine().await.unwrap();
+
+        // Act
+        let actual = engine.health_check().await;
+
+        // Assert
+        assert!(actual.is_ok());
+    }
+
+    #[actix_rt::test]
+    async fn send_fallback() {
+        // Arrange
+        let engine = crate::Configuration {
+            transport: TransportConfiguration::Fallback {
+                transports: vec![TransportConfiguration::Mock(Configuration)],
+            },
+            ..configuration()
+        }
+        .engine()
+        .await
+        .unwrap();
+        let sender = engine.sender().await;
+
+        // Act
+        sender
+            .send_one(
+                "Tester <test@test.com>".parse().unwrap(),
+                &["l1".into()],
+                &"t1".into(),
+                &HashMap::new(),
+            )
+            .await
+            .unwrap();
+        let actual = engine.take_messages().unwrap();
+
+        // Assert
+        assert_eq!(1, actual.len());
+        assert!(engine.health_check().await.is_ok());
+    }
+
+    #[actix_rt::test]
+    async fn fallback_empty() {
+        // Arrange
+        let configuration = crate::Configuration {
+            transport: TransportConfiguration::Fallback {
+                transports: Vec::new(),
+            },
+            ..configuration()
+        };
+
+        // Act
+        let actual = configuration.engine().await;
+
+        // Assert
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn validate_valid() {
+        assert_eq!(Ok(()), configuration().validate());
+    }
+
+    #[test]
+    fn validate_invalid() {
+        let configuration = crate::Configuration {
+            templates: Templates {
+                default_language: "l1".into(),
+                path: "does/not/exist.toml".into(),
+            },
+            transport: TransportConfiguration::Fallback {
+                transports: vec![TransportConfiguration::Fallback {
+                    transports: Vec::new(),
+                }],
+            },
+            retry: Some(crate::configuration::Retry {
+                attempts: 0,
+                backoff: 0,
+            }),
+            ..configuration()
+        };
+
+        assert_eq!(
+            Err(vec![
+                "the template description file does/not/exist.toml does not \
+                 exist"
+                    .to_string(),
+                "the number of attempts must be positive".to_string(),
+                "a fallback chain must contain at least one transport"
+                    .to_string(),
+            ]),
+            configuration.validate(),
+        );
+    }
+
+    /// A configuration using the mock transport.
+    fn configuration() -> crate::Configuration {
+        crate::Configuration {
+            from: "Sender <sender@domain>".parse().unwrap(),
+            from_names: Default::default(),
+            templates: Templates {
+                default_language: "l1".into(),
+                path: PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                    .join("resources/test/email/template/valid.toml")
+                    .to_string_lossy()
+                    .into(),
+            },
+            transport: TransportConfiguration::Mock(Configuration),
+            retry: None,
+        }
+    }
+}
@@ -1,5 +1,8 @@
 #[cfg(feature = "drop")]
 pub mod drop;
 
+#[cfg(feature = "mock")]
+pub mod mock;
+
 #[cfg(feature = "smtp")]
 pub mod smtp;
@@ -54,6 +54,15 @@ impl Configuration {
             .authentication(self.mechanisms.clone())
             .build())
     }
+
+    /// Verifies this configuration, and returns all problems found.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        if self.server.is_empty() {
+            Err(vec!["the SMTP server must not be empty".into()])
+        } else {
+            Ok(())
+        }
+    }
 }
 
 impl ConnectMethod {
@@ -3,11 +3,19 @@
 //! This module contains the [`Engine`](Engine) used to construct actual email
 //! senders. An engine is created from a configuration instance.
 
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::Path;
+use std::pin::Pin;
+
 use lettre::message::Mailbox;
 
+use crate::configuration::Retry;
+use crate::fallback::FallbackTransport;
+use crate::retry::RetryTransport;
 use crate::sender::LettreSender;
 use crate::template::{Language, Templates};
-use crate::{configuration, Configuration, Sender};
+use crate::{configuration, Configuration, Error, Sender};
 
 pub mod backends;
 
@@ -17,6 +25,9 @@ pub struct Engine {
     /// The sender of e-mails.
     pub from: Mailbox,
 
+    /// The display names of the sender for specific languages.
+    pub from_names: HashMap<Language, String>,
+
     /// The default language to use.
     pub default_language: Language,
 
@@ -25,6 +36,9 @@ pub struct Engine {
 
     /// The transport backend to use.
     pub transport: Transport,
+
+    /// How to retry sending e-mails failing with transient errors.
+    pub retry: Retry,
 }
 
 #[derive(Debug)]
@@ -33,9 +47,103 @@ pub enum Transport {
     #[cfg(feature = "drop")]
     Drop(backends::drop::Transport),
 
+    /// A transport recording e-mails.
+    #[cfg(feature = "mock")]
+    Mock(backends::mock::Transport),
+
     /// An SMTP transport.
     #[cfg(feature = "smtp")]
     SMTP(backends::smtp::Transport),
+
+    /// A chain of transports tried in order.
+    Fallback(Vec<Transport>),
+}
+
+impl Transport {
+    /// Appends this transport to a fallback chain.
+    ///
+    /// Every transport is retried individually before the next one is
+    /// tried, and nested chains are flattened.
+    ///
+    /// # Arguments
+    /// *  `chain` - The chain to which to append this transport.
+    /// *  `retry` - How to retry sending e-mails failing with transient
+    ///    errors.
+    fn chain(
+        &self,
+        chain: FallbackTransport,
+        retry: &Retry,
+    ) -> FallbackTransport {
+        use Transport::*;
+        match self {
+            #[cfg(feature = "drop")]
+            Drop(c) => {
+                chain.with(RetryTransport::new(c.clone(), retry.clone()))
+            }
+            #[cfg(feature = "mock")]
+            Mock(c) => {
+                chain.with(RetryTransport::new(c.clone(), retry.clone()))
+            }
+            #[cfg(feature = "smtp")]
+            SMTP(c) => {
+                chain.with(RetryTransport::new(c.clone(), retry.clone()))
+            }
+            Fallback(transports) => transports
+                .iter()
+                .fold(chain, |chain, transport| transport.chain(chain, retry)),
+        }
+    }
+
+    /// Verifies that this transport is reachable.
+    ///
+    /// A chain is reachable if any of its transports is.
+    async fn health_check(&self) -> Result<(), Error> {
+        use Transport::*;
+        match self {
+            #[cfg(feature = "drop")]
+            Drop(_) => Ok(()),
+            #[cfg(feature = "mock")]
+            Mock(_) => Ok(()),
+            #[cfg(feature = "smtp")]
+            SMTP(c) => match c.test_connection().await {
+                Ok(true) => Ok(()),
+                Ok(false) => {
+                    Err(Error::Transport("failed to connect".to_string()))
+                }
+                Err(e) => Err(Error::Transport(e.to_string())),
+            },
+            Fallback(transports) => {
+                let mut result =
+                    Err(Error::Transport("no transports available".into()));
+                for transport in transports {
+                    result = Box::pin(transport.health_check()).await;
+                    if result.is_ok() {
+                        break;
+                    }
+                }
+                result
+            }
+        }
+    }
+
+    /// Retrieves and clears the e-mails sent using this transport.
+    ///
+    /// For chains, the e-mails of all mock transports are returned.
+    #[cfg(feature = "mock")]
+    #[allow(unreachable_patterns)]
+    fn take_messages(&self) -> Option<Vec<lettre::Message>> {
+        match self {
+            Transport::Mock(c) => Some(c.take()),
+            Transport::Fallback(transports) => transports
+                .iter()
+                .filter_map(Transport::take_messages)
+                .reduce(|mut a, b| {
+                    a.extend(b);
+                    a
+                }),
+            _ => None,
+        }
+    }
 }
 
 impl Engine {
@@ -47,41 +155,180 @@ impl Engine {
         use Transport::*;
         match &self.transport {
             #[cfg(feature = "drop")]
-            Drop(c) => Box::new(LettreSender::new(
-                self.from.clone(),
-                self.templates.clone(),
-                self.default_language.clone(),
-                backends::drop::Transport::from(c.clone()),
-            )),
+            Drop(c) => Box::new(
+                LettreSender::new(
+                    self.from.clone(),
+                    self.templates.clone(),
+                    self.default_language.clone(),
+                    RetryTransport::new(c.clone(), self.retry.clone()),
+                )
+                .with_from_names(self.from_names.clone()),
+            ),
+            #[cfg(feature = "mock")]
+            Mock(c) => Box::new(
+                LettreSender::new(
+                    self.from.clone(),
+                    self.templates.clone(),
+                    self.default_language.clone(),
+                    RetryTransport::new(c.clone(), self.retry.clone()),
+                )
+                .with_from_names(self.from_names.clone()),
+            ),
             #[cfg(feature = "smtp")]
-            SMTP(c) => Box::new(LettreSender::new(
-                self.from.clone(),
-                self.templates.clone(),
-                self.default_language.clone(),
-                backends::smtp::Transport::from(c.clone()),
-            )),
+            SMTP(c) => Box::new(
+                LettreSender::new(
+                    self.from.clone(),
+                    self.templates.clone(),
+                    self.default_language.clone(),
+                    RetryTransport::new(
+                        backends::smtp::Transport::from(c.clone()),
+                        self.retry.clone(),
+                    ),
+                )
+                .with_from_names(self.from_names.clone()),
+            ),
+            Fallback(_) => Box::new(
+                LettreSender::new(
+                    self.from.clone(),
+                    self.templates.clone(),
+                    self.default_language.clone(),
+                    self.transport.chain(FallbackTransport::new(), &self.retry),
+                )
+                .with_from_names(self.from_names.clone()),
+            ),
         }
     }
+
+    /// Verifies that the transport of this engine is reachable.
+    ///
+    /// This is intended for readiness probes. For SMTP transports, a
+    /// connection to the server is established; other transports are always
+    /// available. A fallback chain is available if any of its transports is.
+    pub async fn health_check(&self) -> Result<(), Error> {
+        self.transport.health_check().await
+    }
+
+    /// Retrieves and clears the e-mails sent by senders created by this
+    /// engine.
+    ///
+    /// If this engine does not use the mock transport, `None` is returned.
+    #[cfg(feature = "mock")]
+    pub fn take_messages(&self) -> Option<Vec<lettre::Message>> {
+        self.transport.take_messages()
+    }
 }
 
 impl Configuration {
     /// Constructs a cache engine from this configuration.
     pub async fn engine(&self) -> Result<Engine, configuration::Error> {
-        use crate::configuration::Transport::*;
         let from = self.from.clone();
+        let from_names = self.from_names.clone();
         let default_language = self.templates.default_language.clone();
         let templates = Templates::load(&self.templates.path)?;
-        let transport = match &self.transport {
-            #[cfg(feature = "drop")]
-            Drop(c) => Transport::Drop(c.transport().await?),
-            #[cfg(feature = "smtp")]
-            SMTP(c) => Transport::SMTP(c.transport().await?),
-        };
+        let retry = self.retry.clone().unwrap_or_default();
+        let transport = transport(&self.transport).await?;
         Ok(Engine {
             from,
+            from_names,
             default_language,
             templates,
             transport,
+            retry,
         })
     }
 }
+
+impl Configuration {
+    /// Verifies this configuration, and returns all problems found.
+    ///
+    /// The templates are not loaded and no server is contacted; only the
+    /// presence of the template description file is checked. Apps would
+    /// call this at startup to report all misconfigurations at once.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut problems = Vec::new();
+        if !Path::new(&self.templates.path).is_file() {
+            problems.push(format!(
+                "the template description file {} does not exist",
+                self.templates.path,
+            ));
+        }
+        if self.retry.as_ref().is_some_and(|retry| retry.attempts == 0) {
+            problems.push("the number of attempts must be positive".into());
+        }
+        validate_transport(&self.transport, &mut problems);
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems)
+        }
+    }
+}
+
+/// Verifies a transport configuration.
+///
+/// # Arguments
+/// *  `configuration` - The transport configuration.
+/// *  `problems` - The problems found so far, to which new ones are added.
+fn validate_transport(
+    configuration: &configuration::Transport,
+    problems: &mut Vec<String>,
+) {
+    use crate::configuration::Transport::*;
+    match configuration {
+        #[cfg(feature = "drop")]
+        Drop(_) => {}
+        #[cfg(feature = "mock")]
+        Mock(_) => {}
+        #[cfg(feature = "smtp")]
+        SMTP(c) => {
+            if let Err(e) = c.validate() {
+                problems.extend(e);
+            }
+        }
+        Fallback { transports } if transports.is_empty() => problems.push(
+            "a fallback chain must contain at least one transport".into(),
+        ),
+        Fallback { transports } => transports
+            .iter()
+            .for_each(|c| validate_transport(c, problems)),
+    }
+}
+
+/// Constructs a transport from its configuration.
+///
+/// # Arguments
+/// *  `configuration` - The transport configuration.
+fn transport(
+    configuration: &configuration::Transport,
+) -> Pin<
+    Box<
+        dyn Future<Output = Result<Transport, configuration::Error>>
+            + Send
+            + '_,
+    >,
+> {
+    use crate::configuration::Transport::*;
+    Box::pin(async move {
+        Ok(match configuration {
+            #[cfg(feature = "drop")]
+            Drop(c) => Transport::Drop(c.transport().await?),
+            #[cfg(feature = "mock")]
+            Mock(c) => Transport::Mock(c.transport().await?),
+            #[cfg(feature = "smtp")]
+            SMTP(c) => Transport::SMTP(c.transport().await?),
+            Fallback { transports } if transports.is_empty() => {
+                return Err(configuration::Error::from(
+                    "a fallback chain must contain at least one transport",
+                ));
+            }
+            Fallback { transports } => {
+                let mut result = Vec::with_capacity(transports.len());
+                for c in transports {
+                    result.push(transport(c).await?);
+                }
+                Transport::Fallback(result)
+            }
+        })
+    })
+}
@@ -6,6 +6,10 @@ pub enum Error {
     #[error("unknown template: {0}")]
     UnknownTemplate(TemplateName),
 
+    /// No template was specified.
+    #[error("no template specified")]
+    MissingTemplate,
+
     /// The content to send is invalid.
     #[error("invalid content: {0}")]
     Content(lettre::error::Error),
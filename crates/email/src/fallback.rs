@@ -0,0 +1,327 @@
+//! # Fallback transports
+//!
+//! A [fallback transport](FallbackTransport) tries a chain of transports in
+//! order. When a transport cannot be reached, the next one is tried; other
+//! failures, such as an SMTP _5xx_ response, are returned immediately, since
+//! another transport is not expected to succeed.
+
+use async_trait::async_trait;
+use lettre::address::Envelope;
+use lettre::{AsyncTransport, Message};
+
+/// An error that may be caused by a transport being unreachable.
+pub trait Unreachable {
+    /// Whether the operation failed before reaching the server.
+    fn is_unreachable(&self) -> bool;
+}
+
+#[cfg(feature = "drop")]
+impl Unreachable for lettre::transport::stub::Error {
+    fn is_unreachable(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(feature = "mock")]
+impl Unreachable for crate::engine::backends::mock::Error {
+    fn is_unreachable(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(feature = "smtp")]
+impl Unreachable for lettre::transport::smtp::Error {
+    fn is_unreachable(&self) -> bool {
+        !(self.is_transient()
+            || self.is_permanent()
+            || self.is_response()
+            || self.is_client())
+    }
+}
+
+/// An error occurring when sending using a fallback transport.
+#[derive(Debug, thiserror::Error)]
+#[error("{message}")]
+pub struct FallbackError {
+    /// A description of the error.
+    message: String,
+
+    /// Whether the transport failing was unreachable.
+    unreachable: bool,
+}
+
+impl FallbackError {
+    /// Wraps the error of a transport in the chain.
+    ///
+    /// # Arguments
+    /// *  `error` - The actual error.
+    fn wrap<E>(error: E) -> Self
+    where
+        E: Unreachable + ::std::fmt::Display,
+    {
+        Self {
+            message: error.to_string(),
+            unreachable: error.is_unreachable(),
+        }
+    }
+}
+
+/// A transport in a fallback chain.
+#[async_trait]
+trait Member: Send + Sync {
+    /// Sends a message.
+    ///
+    /// # Arguments
+    /// *  `message` - The message to send.
+    async fn send_message(&self, message: Message)
+        -> Result<(), FallbackError>;
+
+    /// Sends a raw message.
+    ///
+    /// # Arguments
+    /// *  `envelope` - The envelope of the message.
+    /// *  `email` - The formatted message.
+    async fn send_message_raw(
+        &self,
+        envelope: &Envelope,
+        email: &[u8],
+    ) -> Result<(), FallbackError>;
+}
+
+#[async_trait]
+impl<T> Member for T
+where
+    T: AsyncTransport + Send + Sync,
+    T::Error: Unreachable + ::std::fmt::Display + Send,
+{
+    async fn send_message(
+        &self,
+        message: Message,
+    ) -> Result<(), FallbackError> {
+        self.send(message)
+            .await
+            .map(|_| ())
+            .map_err(FallbackError::wrap)
+    }
+
+    async fn send_message_raw(
+        &self,
+        envelope: &Envelope,
+        email: &[u8],
+    ) -> Result<(), FallbackError> {
+        self.send_raw(envelope, email)
+            .await
+            .map(|_| ())
+            .map_err(FallbackError::wrap)
+    }
+}
+
+/// A transport trying a chain of transports in order.
+#[derive(Default)]
+pub struct FallbackTransport {
+    /// The transports, in the order they are tried.
+    transports: Vec<Box<dyn Member>>,
+}
+
+impl ::std::fmt::Debug for FallbackTransport {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        write!(f, "FallbackTransport {{ {} transports }}", self.len())
+    }
+}
+
+impl FallbackTransport {
+    /// Creates an empty chain.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a transport to this chain.
+    ///
+    /// # Arguments
+    /// *  `transport` - The transport to try after the current ones.
+    pub fn with<T>(mut self, transport: T) -> Self
+    where
+        T: AsyncTransport + Send + Sync + 'static,
+        T::Error: Unreachable + ::std::fmt::Display + Send,
+    {
+        self.transports.push(Box::new(transport));
+        self
+    }
+
+    /// The number of transports in this chain.
+    pub fn len(&self) -> usize {
+        self.transports.len()
+    }
+
+    /// Whether this chain is empty.
+    pub fn is_empty(&self) -> bool {
+        self.transports.is_empty()
+    }
+
+    /// The error returned when sending with an empty chain.
+    fn empty() -> FallbackError {
+        FallbackError {
+            message: "no transports available".into(),
+            unreachable: true,
+        }
+    }
+}
+
+#[async_trait]
+impl AsyncTransport for FallbackTransport {
+    type Ok = ();
+    type Error = FallbackError;
+
+    async fn send(&self, message: Message) -> Result<Self::Ok, Self::Error> {
+        let mut result = Err(Self::empty());
+        for transport in &self.transports {
+            result = transport.send_message(message.clone()).await;
+            match &result {
+                Err(e) if e.unreachable => {}
+                _ => break,
+            }
+        }
+        result
+    }
+
+    async fn send_raw(
+        &self,
+        envelope: &Envelope,
+        email: &[u8],
+    ) -> Result<Self::Ok, Self::Error> {
+        let mut result = Err(Self::empty());
+        for transport in &self.transports {
+            result = transport.send_message_raw(envelope, email).await;
+            match &result {
+                Err(e) if e.unreachable => {}
+                _ => break,
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    #[actix_rt::test]
+    async fn falls_back_for_unreachable() {
+        // Arrange
+        let first = FailingTransport::new(Some(true));
+        let second = FailingTransport::new(None);
+        let transport = FallbackTransport::new()
+            .with(first.clone())
+            .with(second.clone());
+
+        // Act
+        let actual = transport.send(message()).await;
+
+        // Assert
+        assert!(actual.is_ok());
+        assert_eq!((1, 1), (first.attempts(), second.attempts()));
+    }
+
+    #[actix_rt::test]
+    async fn fails_for_reachable() {
+        // Arrange
+        let first = FailingTransport::new(Some(false));
+        let second = FailingTransport::new(None);
+        let transport = FallbackTransport::new()
+            .with(first.clone())
+            .with(second.clone());
+
+        // Act
+        let actual = transport.send(message()).await;
+
+        // Assert
+        assert!(actual.is_err());
+        assert_eq!((1, 0), (first.attempts(), second.attempts()));
+    }
+
+    #[actix_rt::test]
+    async fn fails_when_all_unreachable() {
+        // Arrange
+        let first = FailingTransport::new(Some(true));
+        let second = FailingTransport::new(Some(true));
+        let transport = FallbackTransport::new()
+            .with(first.clone())
+            .with(second.clone());
+
+        // Act
+        let actual = transport.send(message()).await;
+
+        // Assert
+        assert!(actual.is_err());
+        assert_eq!((1, 1), (first.attempts(), second.attempts()));
+    }
+
+    #[actix_rt::test]
+    async fn fails_when_empty() {
+        assert!(FallbackTransport::new().send(message()).await.is_err());
+    }
+
+    /// A transport that always fails, or always succeeds.
+    #[derive(Clone)]
+    struct FailingTransport {
+        /// Whether failures are caused by being unreachable, or `None` to
+        /// succeed.
+        unreachable: Option<bool>,
+
+        /// The number of attempts made.
+        attempts: Arc<AtomicU32>,
+    }
+
+    impl FailingTransport {
+        fn new(unreachable: Option<bool>) -> Self {
+            Self {
+                unreachable,
+                attempts: Arc::new(AtomicU32::new(0)),
+            }
+        }
+
+        fn attempts(&self) -> u32 {
+            self.attempts.load(Ordering::SeqCst)
+        }
+    }
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("failing")]
+    struct FailingError(bool);
+
+    impl Unreachable for FailingError {
+        fn is_unreachable(&self) -> bool {
+            self.0
+        }
+    }
+
+    #[async_trait]
+    impl AsyncTransport for FailingTransport {
+        type Ok = ();
+        type Error = FailingError;
+
+        async fn send_raw(
+            &self,
+            _envelope: &Envelope,
+            _email: &[u8],
+        ) -> Result<Self::Ok, Self::Error> {
+            self.attempts.fetch_add(1, Ordering::SeqCst);
+            match self.unreachable {
+                Some(unreachable) => Err(FailingError(unreachable)),
+                None => Ok(()),
+            }
+        }
+    }
+
+    /// A message to send.
+    fn message() -> Message {
+        Message::builder()
+            .from("Sender <sender@domain>".parse().unwrap())
+            .to("Tester <test@test.com>".parse().unwrap())
+            .body(String::from("body"))
+            .unwrap()
+    }
+}
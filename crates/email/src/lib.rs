@@ -1,3 +1,6 @@
+#[cfg(feature = "actix")]
+pub mod actix;
+
 pub mod configuration;
 pub use configuration::Configuration;
 
@@ -10,10 +13,13 @@ pub use error::Error;
 mod traits;
 pub use traits::*;
 
+pub mod fallback;
+pub mod message;
+pub mod retry;
 pub mod sender;
 pub mod template;
 
 pub use lettre;
 
-#[cfg(not(any(feature = "drop")))]
+#[cfg(not(any(feature = "drop", feature = "mock", feature = "smtp")))]
 compile_error!("At least one backend must be enabled!");
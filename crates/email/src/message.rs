@@ -0,0 +1,242 @@
+//! # The message builder
+//!
+//! A [message builder](MessageBuilder) collects the options for an e-mail
+//! one at a time, and then sends it. Builders are created by
+//! [`message`](dyn Sender::message).
+
+use std::collections::HashMap;
+
+use lettre::message::{Mailbox, Mailboxes};
+use lettre::Address;
+
+use crate::template::{
+    Attachment, AttachmentName, Language, LanguagePreference, TemplateName,
+};
+use crate::{Error, Sender};
+
+/// A complete description of an e-mail to send.
+#[derive(Clone, Debug)]
+pub struct Email {
+    /// The mailbox indicated by the _From_ header. If this is `None`, the
+    /// default sender is used.
+    pub from: Option<Mailbox>,
+
+    /// The recipients indicated by the _To_ header.
+    pub to: Mailboxes,
+
+    /// The recipients indicated by the _Cc_ header.
+    pub cc: Mailboxes,
+
+    /// The recipients not indicated by any header.
+    pub bcc: Mailboxes,
+
+    /// The mailbox indicated by the _Reply-To_ header, if any.
+    pub reply_to: Option<Mailbox>,
+
+    /// The SMTP envelope sender, to which bounces are sent. If this is
+    /// `None`, the address of the _From_ header is used.
+    pub envelope_from: Option<Address>,
+
+    /// A sequence of languages to use, in decreasing order of relevance. The
+    /// first language for which the template exists is used.
+    pub languages: Vec<Language>,
+
+    /// The template used to format the message.
+    pub template: TemplateName,
+
+    /// Replacement strings for keys in the template.
+    pub replacements: HashMap<String, String>,
+
+    /// Attachments sent in addition to those of the template.
+    pub attachments: Vec<(AttachmentName, Attachment)>,
+}
+
+impl Email {
+    /// Creates an e-mail without recipients using a template.
+    ///
+    /// # Arguments
+    /// *  `template` - The template used to format the message.
+    pub fn new(template: TemplateName) -> Self {
+        Self {
+            from: None,
+            to: Mailboxes::new(),
+            cc: Mailboxes::new(),
+            bcc: Mailboxes::new(),
+            reply_to: None,
+            envelope_from: None,
+            languages: Vec::new(),
+            template,
+            replacements: HashMap::new(),
+            attachments: Vec::new(),
+        }
+    }
+}
+
+/// A builder of e-mails.
+///
+/// Use [`message`](dyn Sender::message) to create a builder.
+pub struct MessageBuilder<'a> {
+    /// The sender used to send the e-mail.
+    sender: &'a dyn Sender,
+
+    /// The template used to format the message.
+    template: Option<TemplateName>,
+
+    /// The e-mail being built. Its template is replaced when sending.
+    email: Email,
+}
+
+impl<'a> MessageBuilder<'a> {
+    /// Creates a builder for e-mails sent by a specific sender.
+    ///
+    /// # Arguments
+    /// *  `sender` - The sender used to send the e-mail.
+    pub fn new(sender: &'a dyn Sender) -> Self {
+        Self {
+            sender,
+            template: None,
+            email: Email::new(TemplateName::from(String::new())),
+        }
+    }
+
+    /// Sets the mailbox indicated by the _From_ header.
+    ///
+    /// # Arguments
+    /// *  `mailbox` - The sender mailbox.
+    pub fn from(mut self, mailbox: Mailbox) -> Self {
+        self.email.from = Some(mailbox);
+        self
+    }
+
+    /// Adds a recipient indicated by the _To_ header.
+    ///
+    /// # Arguments
+    /// *  `mailbox` - The recipient mailbox.
+    pub fn to(mut self, mailbox: Mailbox) -> Self {
+        self.email.to.push(mailbox);
+        self
+    }
+
+    /// Adds a recipient indicated by the _Cc_ header.
+    ///
+    /// # Arguments
+    /// *  `mailbox` - The recipient mailbox.
+    pub fn cc(mut self, mailbox: Mailbox) -> Self {
+        self.email.cc.push(mailbox);
+        self
+    }
+
+    /// Adds a recipient not indicated by any header.
+    ///
+    /// # Arguments
+    /// *  `mailbox` - The recipient mailbox.
+    pub fn bcc(mut self, mailbox: Mailbox) -> Self {
+        self.email.bcc.push(mailbox);
+        self
+    }
+
+    /// Sets the mailbox indicated by the _Reply-To_ header.
+    ///
+    /// # Arguments
+    /// *  `mailbox` - The mailbox to which replies are sent.
+    pub fn reply_to(mut self, mailbox: Mailbox) -> Self {
+        self.email.reply_to = Some(mailbox);
+        self
+    }
+
+    /// Sets the SMTP envelope sender, independently of the _From_ header.
+    ///
+    /// Bounces are sent to this address rather than to the sender indicated
+    /// by the _From_ header.
+    ///
+    /// # Arguments
+    /// *  `address` - The envelope sender address.
+    pub fn envelope_from(mut self, address: Address) -> Self {
+        self.email.envelope_from = Some(address);
+        self
+    }
+
+    /// Adds languages to use, in decreasing order of relevance.
+    ///
+    /// # Arguments
+    /// *  `languages` - The languages to add.
+    pub fn languages<I>(mut self, languages: I) -> Self
+    where
+        I: IntoIterator<Item = Language>,
+    {
+        self.email.languages.extend(languages);
+        self
+    }
+
+    /// Adds languages to use, ordered by decreasing quality value.
+    ///
+    /// # Arguments
+    /// *  `preferences` - The languages to add, with quality values.
+    pub fn preferences<I>(mut self, preferences: I) -> Self
+    where
+        I: IntoIterator<Item = LanguagePreference>,
+    {
+        self.email.languages.extend(LanguagePreference::order(
+            &preferences.into_iter().collect::<Vec<_>>(),
+        ));
+        self
+    }
+
+    /// Sets the template used to format the message.
+    ///
+    /// # Arguments
+    /// *  `template` - The template name.
+    pub fn template(mut self, template: impl Into<TemplateName>) -> Self {
+        self.template = Some(template.into());
+        self
+    }
+
+    /// Adds a replacement string for a key in the template.
+    ///
+    /// # Arguments
+    /// *  `key` - The key to replace.
+    /// *  `value` - The replacement string.
+    pub fn replace(
+        mut self,
+        key: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        self.email.replacements.insert(key.into(), value.into());
+        self
+    }
+
+    /// Adds an attachment in addition to those of the template.
+    ///
+    /// # Arguments
+    /// *  `name` - The name of the attachment.
+    /// *  `attachment` - The attachment.
+    pub fn attach(
+        mut self,
+        name: impl Into<AttachmentName>,
+        attachment: Attachment,
+    ) -> Self {
+        self.email.attachments.push((name.into(), attachment));
+        self
+    }
+
+    /// Sends the e-mail.
+    ///
+    /// This fails with [`Error::MissingTemplate`] unless a template has been
+    /// set.
+    pub async fn send(self) -> Result<(), Error> {
+        let template = self.template.ok_or(Error::MissingTemplate)?;
+        self.sender
+            .send_email(Email {
+                template,
+                ..self.email
+            })
+            .await
+    }
+}
+
+impl dyn Sender + '_ {
+    /// Starts building an e-mail sent by this sender.
+    pub fn message(&self) -> MessageBuilder<'_> {
+        MessageBuilder::new(self)
+    }
+}
@@ -0,0 +1,248 @@
+//! # Retrying transports
+//!
+//! A [retrying transport](RetryTransport) wraps another transport, and
+//! retries sending e-mails that fail with a transient error, such as an SMTP
+//! _4xx_ response. Permanent failures are returned immediately.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use lettre::address::Envelope;
+use lettre::{AsyncTransport, Message};
+
+use crate::configuration::Retry;
+
+/// An error that may be transient.
+pub trait Transient {
+    /// Whether the operation failing with this error may succeed if retried.
+    fn is_transient(&self) -> bool;
+}
+
+#[cfg(feature = "drop")]
+impl Transient for lettre::transport::stub::Error {
+    fn is_transient(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(feature = "mock")]
+impl Transient for crate::engine::backends::mock::Error {
+    fn is_transient(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(feature = "smtp")]
+impl Transient for lettre::transport::smtp::Error {
+    fn is_transient(&self) -> bool {
+        lettre::transport::smtp::Error::is_transient(self)
+    }
+}
+
+/// A transport retrying failed attempts with exponential backoff.
+#[derive(Clone, Debug)]
+pub struct RetryTransport<T> {
+    /// The actual transport.
+    inner: T,
+
+    /// The retry configuration.
+    retry: Retry,
+}
+
+impl<T> RetryTransport<T>
+where
+    T: AsyncTransport + Sync,
+    T::Error: Transient,
+{
+    /// Wraps a transport.
+    ///
+    /// # Arguments
+    /// *  `inner` - The actual transport.
+    /// *  `retry` - The retry configuration.
+    pub fn new(inner: T, retry: Retry) -> Self {
+        Self { inner, retry }
+    }
+
+    /// Whether to retry after a failed attempt.
+    ///
+    /// Only attempts failing with a transient error are retried, and only
+    /// while attempts remain.
+    ///
+    /// # Arguments
+    /// *  `attempt` - The number of the failed attempt, starting at 0.
+    /// *  `error` - The error of the failed attempt.
+    fn retries(&self, attempt: u32, error: &T::Error) -> bool {
+        attempt + 1 < self.retry.attempts && error.is_transient()
+    }
+
+    /// Waits before retrying a failed attempt.
+    ///
+    /// # Arguments
+    /// *  `attempt` - The number of the failed attempt, starting at 0.
+    async fn backoff(&self, attempt: u32) {
+        tokio::time::sleep(
+            Duration::from_millis(self.retry.backoff)
+                .saturating_mul(2u32.saturating_pow(attempt)),
+        )
+        .await
+    }
+}
+
+#[async_trait]
+impl<T> AsyncTransport for RetryTransport<T>
+where
+    T: AsyncTransport + Send + Sync,
+    T::Error: Transient + Send,
+{
+    type Ok = T::Ok;
+    type Error = T::Error;
+
+    async fn send(&self, message: Message) -> Result<Self::Ok, Self::Error> {
+        let mut attempt = 0;
+        loop {
+            match self.inner.send(message.clone()).await {
+                Err(e) if self.retries(attempt, &e) => {}
+                result => break result,
+            }
+            self.backoff(attempt).await;
+            attempt += 1;
+        }
+    }
+
+    async fn send_raw(
+        &self,
+        envelope: &Envelope,
+        email: &[u8],
+    ) -> Result<Self::Ok, Self::Error> {
+        let mut attempt = 0;
+        loop {
+            match self.inner.send_raw(envelope, email).await {
+                Err(e) if self.retries(attempt, &e) => {}
+                result => break result,
+            }
+            self.backoff(attempt).await;
+            attempt += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    #[actix_rt::test]
+    async fn succeeds_after_transient() {
+        // Arrange
+        let inner = FlakyTransport::new(2, true);
+        let transport = RetryTransport::new(inner.clone(), retry(3));
+
+        // Act
+        let actual = transport.send(message()).await;
+
+        // Assert
+        assert!(actual.is_ok());
+        assert_eq!(3, inner.attempts());
+    }
+
+    #[actix_rt::test]
+    async fn fails_after_attempts() {
+        // Arrange
+        let inner = FlakyTransport::new(5, true);
+        let transport = RetryTransport::new(inner.clone(), retry(3));
+
+        // Act
+        let actual = transport.send(message()).await;
+
+        // Assert
+        assert!(actual.is_err());
+        assert_eq!(3, inner.attempts());
+    }
+
+    #[actix_rt::test]
+    async fn fails_for_permanent() {
+        // Arrange
+        let inner = FlakyTransport::new(5, false);
+        let transport = RetryTransport::new(inner.clone(), retry(3));
+
+        // Act
+        let actual = transport.send(message()).await;
+
+        // Assert
+        assert!(actual.is_err());
+        assert_eq!(1, inner.attempts());
+    }
+
+    /// A transport failing a number of times before succeeding.
+    #[derive(Clone)]
+    struct FlakyTransport {
+        /// The number of failures before succeeding.
+        failures: u32,
+
+        /// Whether failures are transient.
+        transient: bool,
+
+        /// The number of attempts made.
+        attempts: Arc<AtomicU32>,
+    }
+
+    impl FlakyTransport {
+        fn new(failures: u32, transient: bool) -> Self {
+            Self {
+                failures,
+                transient,
+                attempts: Arc::new(AtomicU32::new(0)),
+            }
+        }
+
+        fn attempts(&self) -> u32 {
+            self.attempts.load(Ordering::SeqCst)
+        }
+    }
+
+    #[derive(Debug)]
+    struct FlakyError(bool);
+
+    impl Transient for FlakyError {
+        fn is_transient(&self) -> bool {
+            self.0
+        }
+    }
+
+    #[async_trait]
+    impl AsyncTransport for FlakyTransport {
+        type Ok = ();
+        type Error = FlakyError;
+
+        async fn send_raw(
+            &self,
+            _envelope: &Envelope,
+            _email: &[u8],
+        ) -> Result<Self::Ok, Self::Error> {
+            if self.attempts.fetch_add(1, Ordering::SeqCst) < self.failures {
+                Err(FlakyError(self.transient))
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    /// A retry configuration without delays.
+    fn retry(attempts: u32) -> Retry {
+        Retry {
+            attempts,
+            backoff: 0,
+        }
+    }
+
+    /// A message to send.
+    fn message() -> Message {
+        Message::builder()
+            .from("Sender <sender@domain>".parse().unwrap())
+            .to("Tester <test@test.com>".parse().unwrap())
+            .body(String::from("body"))
+            .unwrap()
+    }
+}
@@ -4,15 +4,51 @@ use std::iter;
 use async_trait::async_trait;
 use lettre;
 
+use lettre::address::Envelope;
 use lettre::message::{header, Attachment, Message, MultiPart, SinglePart};
 use lettre::AsyncTransport;
 
 use crate::error::Error;
-use crate::template::{Language, TemplateName, Templates};
+use crate::message::Email;
+use crate::template::{Disposition, Language, Templates};
 use crate::Sender;
 
 pub use lettre::message::{Mailbox, Mailboxes};
 
+/// Parses a list of addresses into mailboxes.
+///
+/// If any address fails to parse, all failures are returned as pairs of the
+/// input and the error message.
+///
+/// # Arguments
+/// *  `addrs` - The addresses to parse.
+pub fn parse_mailboxes<I, S>(
+    addrs: I,
+) -> Result<Mailboxes, Vec<(String, String)>>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let (mailboxes, errors) = addrs.into_iter().fold(
+        (Mailboxes::new(), Vec::new()),
+        |(mailboxes, mut errors), addr| {
+            let addr = addr.as_ref();
+            match addr.parse::<Mailbox>() {
+                Ok(mailbox) => (mailboxes.with(mailbox), errors),
+                Err(e) => {
+                    errors.push((addr.to_string(), e.to_string()));
+                    (mailboxes, errors)
+                }
+            }
+        },
+    );
+    if errors.is_empty() {
+        Ok(mailboxes)
+    } else {
+        Err(errors)
+    }
+}
+
 /// A sender of emails.
 ///
 /// This struct maintains a selection of templates
@@ -26,6 +62,9 @@ where
     /// The mailbox indicated by the _From_ header.
     from: Mailbox,
 
+    /// The display names of the sender for specific languages.
+    from_names: HashMap<Language, String>,
+
     /// The default language to use when none of the requested languages is
     /// available.
     default_language: Language,
@@ -53,10 +92,40 @@ where
         Self {
             templates,
             from,
+            from_names: HashMap::new(),
             default_language,
             transport,
         }
     }
+
+    /// Sets the display names of the sender for specific languages.
+    ///
+    /// The name used in the _From_ header is the one for the language of the
+    /// template sent, or the name of the default mailbox if none is set for
+    /// that language. The address is never changed.
+    ///
+    /// # Arguments
+    /// *  `from_names` - The display names, by language.
+    pub fn with_from_names(
+        mut self,
+        from_names: HashMap<Language, String>,
+    ) -> Self {
+        self.from_names = from_names;
+        self
+    }
+
+    /// The mailbox indicated by the _From_ header for a language.
+    ///
+    /// # Arguments
+    /// *  `language` - The language of the template sent.
+    fn sender_for(&self, language: &Language) -> Mailbox {
+        match self.from_names.get(language) {
+            Some(name) => {
+                Mailbox::new(Some(name.clone()), self.from.email.clone())
+            }
+            None => self.from.clone(),
+        }
+    }
 }
 
 #[async_trait]
@@ -65,42 +134,110 @@ where
     T: AsyncTransport + Send + Sync,
     <T as AsyncTransport>::Error: std::fmt::Display,
 {
-    async fn send(
-        &self,
-        recipients: Mailboxes,
-        languages: &[Language],
-        template: &TemplateName,
-        replacements: &HashMap<String, String>,
-    ) -> Result<(), Error> {
-        let template = languages
+    async fn send_email(&self, email: Email) -> Result<(), Error> {
+        let Email {
+            from,
+            to,
+            cc,
+            bcc,
+            reply_to,
+            envelope_from,
+            languages,
+            template,
+            replacements,
+            attachments,
+        } = email;
+        let (language, template) = languages
             .iter()
             .chain(iter::once(&self.default_language))
-            .find_map(|language| self.templates.get(&language, template))
+            .find_map(|language| {
+                self.templates
+                    .get(language, &template)
+                    .map(|template| (language, template))
+            })
             .ok_or_else(|| Error::UnknownTemplate(template.clone()))?;
-        let message = Message::builder()
-            .from(self.from.clone())
-            .subject(template.subject())
-            .mailbox(header::To::from(recipients))
-            .multipart(
-                template.attachments().iter().fold(
-                    MultiPart::related()
-                        .singlepart(SinglePart::html(template.html(|key| {
-                            replacements.get(key).map(String::as_str)
-                        })))
-                        .singlepart(SinglePart::plain(template.text(|key| {
-                            replacements.get(key).map(String::as_str)
-                        }))),
-                    |multipart, (name, attachment)| {
-                        multipart.singlepart(
-                            Attachment::new_inline(name.as_ref().clone()).body(
-                                attachment.data().to_vec(),
-                                attachment.content_type().clone(),
-                            ),
-                        )
-                    },
-                ),
+        let rendered =
+            template.render(|key| replacements.get(key).map(String::as_str));
+        // Attachment data is read only now, to keep memory use bounded by
+        // the messages actually being sent
+        let mut parts = Vec::new();
+        for (name, attachment) in template.attachments().iter().chain(
+            attachments
+                .iter()
+                .map(|(name, attachment)| (name, attachment)),
+        ) {
+            let data = attachment
+                .source()
+                .read()
+                .await
+                .map_err(|e| Error::Content(e.into()))?;
+            parts.push((name, attachment, data));
+        }
+
+        // Inline attachments are related to the body, whereas other
+        // attachments are separate parts
+        let (inline, separate) =
+            parts
+                .into_iter()
+                .partition::<Vec<_>, _>(|(_, attachment, _)| {
+                    attachment.disposition() == Disposition::Inline
+                });
+        let related = inline.into_iter().fold(
+            MultiPart::related()
+                .singlepart(SinglePart::html(rendered.html))
+                .singlepart(SinglePart::plain(rendered.text)),
+            |multipart, (name, attachment, data)| {
+                multipart.singlepart(
+                    Attachment::new_inline(name.as_ref().clone())
+                        .body(data, attachment.content_type().clone()),
+                )
+            },
+        );
+        let body = if separate.is_empty() {
+            related
+        } else {
+            separate.into_iter().fold(
+                MultiPart::mixed().multipart(related),
+                |multipart, (name, attachment, data)| {
+                    multipart.singlepart(
+                        Attachment::new(name.as_ref().clone())
+                            .body(data, attachment.content_type().clone()),
+                    )
+                },
             )
+        };
+        // The envelope must be calculated before the recipients are moved
+        // into the headers
+        let envelope = envelope_from
+            .map(|address| {
+                Envelope::new(
+                    Some(address),
+                    to.iter()
+                        .chain(cc.iter())
+                        .chain(bcc.iter())
+                        .map(|mailbox| mailbox.email.clone())
+                        .collect(),
+                )
+            })
+            .transpose()
             .map_err(Error::Content)?;
+        let mut message = Message::builder()
+            .from(from.unwrap_or_else(|| self.sender_for(language)))
+            .subject(rendered.subject)
+            .mailbox(header::To::from(to));
+        if cc.iter().next().is_some() {
+            message = message.mailbox(header::Cc::from(cc));
+        }
+        if bcc.iter().next().is_some() {
+            message = message.mailbox(header::Bcc::from(bcc));
+        }
+        if let Some(reply_to) = reply_to {
+            message = message.reply_to(reply_to);
+        }
+        if let Some(envelope) = envelope {
+            message = message.envelope(envelope);
+        }
+        let message = message.multipart(body).map_err(Error::Content)?;
 
         Ok(self
             .transport
@@ -115,11 +252,16 @@ where
 mod tests {
     use super::*;
 
+    use std::collections::HashMap;
     use std::path::PathBuf;
+    use std::sync::Arc;
 
     use lettre::transport::stub::AsyncStubTransport;
 
-    use crate::template::Templates;
+    use crate::template::{
+        self, AttachmentSource, LanguagePreference, Template, Templates,
+    };
+    use lettre::message::header::ContentType;
 
     #[actix_rt::test]
     async fn fails_for_unknown() {
@@ -191,6 +333,325 @@ mod tests {
         );
     }
 
+    #[actix_rt::test]
+    async fn send_one_succeeds_for_no_error() {
+        let sender = LettreSender::new(
+            "Sender <sender@domain>".parse().unwrap(),
+            templates(),
+            "default".into(),
+            AsyncStubTransport::new_ok(),
+        );
+
+        assert_eq!(
+            Ok(()),
+            sender
+                .send_one(
+                    "Tester <test@test.com>".parse().unwrap(),
+                    &["l1".into()],
+                    &"t1".into(),
+                    &replacements()
+                )
+                .await
+                .map_err(|e| e.to_string()),
+        );
+    }
+
+    #[actix_rt::test]
+    async fn send_email_cc_bcc_language() {
+        let template = |subject: &str| {
+            Template::new(
+                subject.into(),
+                "<message/>",
+                "body".into(),
+                HashMap::new(),
+                HashMap::new(),
+            )
+        };
+        let templates = Templates::from_map(
+            [
+                (
+                    "l1".into(),
+                    [("t1".into(), template("Subject l1"))]
+                        .into_iter()
+                        .collect(),
+                ),
+                (
+                    "l2".into(),
+                    [("t1".into(), template("Subject l2"))]
+                        .into_iter()
+                        .collect(),
+                ),
+            ]
+            .into_iter()
+            .collect(),
+        );
+        let transport = AsyncStubTransport::new_ok();
+        let sender = LettreSender::new(
+            "Sender <sender@domain>".parse().unwrap(),
+            templates,
+            "l1".into(),
+            transport.clone(),
+        );
+        let mut email = Email::new("t1".into());
+        email.to = recipients();
+        email.cc = "cc@test.com".parse().unwrap();
+        email.bcc = "bcc@test.com".parse().unwrap();
+        email.languages = vec!["l3".into(), "l2".into()];
+
+        sender.send_email(email).await.unwrap();
+
+        let messages = transport.messages().await;
+        let (envelope, message) = &messages[0];
+        assert_eq!(1, messages.len());
+        assert_eq!(
+            vec!["test@test.com", "cc@test.com", "bcc@test.com"],
+            envelope
+                .to()
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+        );
+        assert!(message.contains("Subject: Subject l2\r\n"));
+        assert!(message.contains("Cc: cc@test.com\r\n"));
+        assert!(!message.contains("bcc@test.com"));
+    }
+
+    #[actix_rt::test]
+    async fn send_email_envelope_from() {
+        let transport = AsyncStubTransport::new_ok();
+        let sender = LettreSender::new(
+            "Sender <sender@domain>".parse().unwrap(),
+            templates(),
+            "l1".into(),
+            transport.clone(),
+        );
+        let mut email = Email::new("t1".into());
+        email.to = recipients();
+        email.bcc = "bcc@test.com".parse().unwrap();
+        email.envelope_from = Some("bounces+test@domain".parse().unwrap());
+
+        sender.send_email(email).await.unwrap();
+
+        let messages = transport.messages().await;
+        let (envelope, message) = &messages[0];
+        assert_eq!(
+            Some("bounces+test@domain".to_string()),
+            envelope.from().map(ToString::to_string),
+        );
+        assert_eq!(
+            vec!["test@test.com", "bcc@test.com"],
+            envelope
+                .to()
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+        );
+        assert!(message.contains("From: Sender <sender@domain>\r\n"));
+    }
+
+    #[actix_rt::test]
+    async fn send_email_envelope_from_default() {
+        let transport = AsyncStubTransport::new_ok();
+        let sender = LettreSender::new(
+            "Sender <sender@domain>".parse().unwrap(),
+            templates(),
+            "l1".into(),
+            transport.clone(),
+        );
+        let mut email = Email::new("t1".into());
+        email.to = recipients();
+
+        sender.send_email(email).await.unwrap();
+
+        let messages = transport.messages().await;
+        assert_eq!(
+            Some("sender@domain".to_string()),
+            messages[0].0.from().map(ToString::to_string),
+        );
+    }
+
+    #[actix_rt::test]
+    async fn send_preferred_highest_quality() {
+        let template = |subject: &str| {
+            Template::new(
+                subject.into(),
+                "<message/>",
+                "body".into(),
+                HashMap::new(),
+                HashMap::new(),
+            )
+        };
+        let templates = Templates::from_map(
+            ["en", "fr", "de"]
+                .into_iter()
+                .map(|language| {
+                    (
+                        language.into(),
+                        [("t1".into(), template(language))]
+                            .into_iter()
+                            .collect(),
+                    )
+                })
+                .collect(),
+        );
+        let transport = AsyncStubTransport::new_ok();
+        let sender = LettreSender::new(
+            "Sender <sender@domain>".parse().unwrap(),
+            templates,
+            "de".into(),
+            transport.clone(),
+        );
+
+        sender
+            .send_preferred(
+                recipients(),
+                &LanguagePreference::parse_header("sv, en;q=0.8, fr;q=0.9"),
+                &"t1".into(),
+                &replacements(),
+            )
+            .await
+            .unwrap();
+
+        let messages = transport.messages().await;
+        assert!(messages[0].1.contains("Subject: fr\r\n"));
+    }
+
+    #[actix_rt::test]
+    async fn send_email_reads_attachments() {
+        let transport = AsyncStubTransport::new_ok();
+        let sender = LettreSender::new(
+            "Sender <sender@domain>".parse().unwrap(),
+            templates(),
+            "l1".into(),
+            transport.clone(),
+        );
+        let mut email = Email::new("t1".into());
+        email.to = recipients();
+        email.attachments = vec![(
+            "streamed".into(),
+            template::Attachment::with_source(
+                ContentType::TEXT_PLAIN,
+                Disposition::Attachment,
+                AttachmentSource::Reader(Arc::new(|| {
+                    Box::pin(async {
+                        Ok(Box::new(&b"streamed data"[..])
+                            as template::AttachmentReader)
+                    })
+                })),
+            ),
+        )];
+
+        sender.send_email(email).await.unwrap();
+
+        let messages = transport.messages().await;
+        assert!(messages[0].1.contains(
+            "Content-Disposition: attachment; filename=\"streamed\""
+        ));
+        assert!(messages[0].1.contains("streamed data"));
+    }
+
+    #[actix_rt::test]
+    async fn send_email_from_name_language() {
+        let transport = AsyncStubTransport::new_ok();
+        let sender = LettreSender::new(
+            "Sender <sender@domain>".parse().unwrap(),
+            templates(),
+            "l1".into(),
+            transport.clone(),
+        )
+        .with_from_names(
+            [("l1".into(), "Localised".into())].into_iter().collect(),
+        );
+        let mut email = Email::new("t1".into());
+        email.to = recipients();
+        email.languages = vec!["l2".into()];
+
+        sender.send_email(email).await.unwrap();
+
+        let messages = transport.messages().await;
+        let (_, message) = &messages[0];
+        assert!(message.contains("From: Localised <sender@domain>\r\n"));
+    }
+
+    #[actix_rt::test]
+    async fn send_email_from_name_default() {
+        let transport = AsyncStubTransport::new_ok();
+        let sender = LettreSender::new(
+            "Sender <sender@domain>".parse().unwrap(),
+            templates(),
+            "l1".into(),
+            transport.clone(),
+        )
+        .with_from_names(
+            [("l2".into(), "Localised".into())].into_iter().collect(),
+        );
+        let mut email = Email::new("t1".into());
+        email.to = recipients();
+
+        sender.send_email(email).await.unwrap();
+
+        let messages = transport.messages().await;
+        let (_, message) = &messages[0];
+        assert!(message.contains("From: Sender <sender@domain>\r\n"));
+    }
+
+    #[actix_rt::test]
+    async fn send_email_fails_for_missing_attachment() {
+        let sender = LettreSender::new(
+            "Sender <sender@domain>".parse().unwrap(),
+            templates(),
+            "l1".into(),
+            AsyncStubTransport::new_ok(),
+        );
+        let mut email = Email::new("t1".into());
+        email.to = recipients();
+        email.attachments = vec![(
+            "missing".into(),
+            template::Attachment::with_source(
+                ContentType::TEXT_PLAIN,
+                Disposition::Attachment,
+                AttachmentSource::Path("does/not/exist".into()),
+            ),
+        )];
+
+        assert!(matches!(
+            sender.send_email(email).await,
+            Err(Error::Content(lettre::error::Error::Io(_))),
+        ));
+    }
+
+    #[test]
+    fn parse_mailboxes_valid() {
+        let actual =
+            parse_mailboxes(["Tester <test@test.com>", "other@test.com"]);
+
+        assert_eq!(
+            Ok(vec![
+                "Tester <test@test.com>".parse::<Mailbox>().unwrap(),
+                "other@test.com".parse::<Mailbox>().unwrap(),
+            ]),
+            actual.map(|mailboxes| mailboxes.into_iter().collect::<Vec<_>>()),
+        );
+    }
+
+    #[test]
+    fn parse_mailboxes_invalid() {
+        let actual = parse_mailboxes(vec![
+            "Tester <test@test.com>".to_string(),
+            "invalid".to_string(),
+            "also invalid@".to_string(),
+        ]);
+
+        assert_eq!(
+            vec!["invalid".to_string(), "also invalid@".to_string()],
+            actual
+                .unwrap_err()
+                .into_iter()
+                .map(|(input, _)| input)
+                .collect::<Vec<_>>(),
+        );
+    }
+
     /// Loads the valid templates from the test resource directory.
     fn templates() -> Templates {
         Templates::load(
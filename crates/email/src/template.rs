@@ -1,14 +1,18 @@
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::fmt;
 use std::fs;
+use std::future::Future;
 use std::io;
 use std::ops::Range;
 use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
 
 use lettre::message::header::ContentType;
 use pulldown_cmark::html;
 use pulldown_cmark::{Event, Parser, Tag};
 use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncReadExt};
 
 /// An error occurring when loading templates.
 #[derive(Debug, thiserror::Error)]
@@ -53,6 +57,81 @@ impl fmt::Display for Language {
     }
 }
 
+/// A language with a quality value, as used in an _Accept-Language_ header.
+///
+/// When represented by a string, this is a language code optionally followed
+/// by a quality value, such as `"fr;q=0.9"`. If the quality value is omitted,
+/// it is `1`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LanguagePreference {
+    /// The language.
+    pub language: Language,
+
+    /// The quality value, between `0` and `1`. Higher values are preferred.
+    pub quality: f32,
+}
+
+impl LanguagePreference {
+    /// Parses an _Accept-Language_ header into a list of preferences.
+    ///
+    /// Invalid entries, the wildcard `*` and entries with a quality value of
+    /// `0` are ignored.
+    ///
+    /// # Arguments
+    /// *  `header` - The header value.
+    pub fn parse_header(header: &str) -> Vec<Self> {
+        header
+            .split(',')
+            .filter_map(|entry| entry.parse::<Self>().ok())
+            .filter(|p| p.language.as_ref() != "*" && p.quality > 0.0)
+            .collect()
+    }
+
+    /// Orders languages by decreasing quality value.
+    ///
+    /// Languages with the same quality value retain their relative order.
+    ///
+    /// # Arguments
+    /// *  `preferences` - The language preferences.
+    pub fn order(preferences: &[Self]) -> Vec<Language> {
+        let mut preferences = preferences.iter().collect::<Vec<_>>();
+        preferences.sort_by(|a, b| b.quality.total_cmp(&a.quality));
+        preferences
+            .into_iter()
+            .map(|preference| preference.language.clone())
+            .collect()
+    }
+}
+
+impl ::std::str::FromStr for LanguagePreference {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split(';').map(str::trim);
+        let language = parts
+            .next()
+            .filter(|language| !language.is_empty())
+            .ok_or_else(|| format!("missing language in {:?}", s))?;
+        let quality = parts
+            .find_map(|part| {
+                part.strip_prefix("q=").or_else(|| part.strip_prefix("Q="))
+            })
+            .map(|quality| {
+                quality
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|quality| (0.0..=1.0).contains(quality))
+                    .ok_or_else(|| format!("invalid quality in {:?}", s))
+            })
+            .transpose()?
+            .unwrap_or(1.0);
+        Ok(Self {
+            language: language.into(),
+            quality,
+        })
+    }
+}
+
 /// A template name.
 #[derive(Clone, Debug, Eq, Hash, PartialEq, Deserialize, Serialize)]
 #[serde(transparent)]
@@ -105,28 +184,175 @@ impl fmt::Display for AttachmentName {
     }
 }
 
+/// How an attachment is presented by mail clients.
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Disposition {
+    /// The attachment is embedded in the message, and referenced by its
+    /// content ID.
+    Inline,
+
+    /// The attachment is a separate file.
+    #[default]
+    Attachment,
+}
+
+/// A reader of attachment data.
+pub type AttachmentReader = Box<dyn AsyncRead + Send + Unpin>;
+
+/// A function opening a new reader of attachment data.
+///
+/// This is called once for every message sent.
+pub type AttachmentOpener = Arc<
+    dyn Fn()
+            -> Pin<Box<dyn Future<Output = io::Result<AttachmentReader>> + Send>>
+        + Send
+        + Sync,
+>;
+
+/// The source of the data of an attachment.
+///
+/// Only inline data is kept in memory; the other sources are read when a
+/// message is sent.
+#[derive(Clone)]
+pub enum AttachmentSource {
+    /// The data is kept in memory.
+    Inline(Vec<u8>),
+
+    /// The data is read from a file.
+    Path(PathBuf),
+
+    /// The data is read from a reader opened for every message.
+    Reader(AttachmentOpener),
+}
+
+impl AttachmentSource {
+    /// Reads the data of this source.
+    pub async fn read(&self) -> io::Result<Vec<u8>> {
+        match self {
+            Self::Inline(data) => Ok(data.clone()),
+            Self::Path(path) => tokio::fs::read(path).await,
+            Self::Reader(open) => {
+                let mut data = Vec::new();
+                open().await?.read_to_end(&mut data).await?;
+                Ok(data)
+            }
+        }
+    }
+}
+
+impl fmt::Debug for AttachmentSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Inline(data) => f.debug_tuple("Inline").field(data).finish(),
+            Self::Path(path) => f.debug_tuple("Path").field(path).finish(),
+            Self::Reader(_) => f.debug_tuple("Reader").finish(),
+        }
+    }
+}
+
+impl PartialEq for AttachmentSource {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Inline(a), Self::Inline(b)) => a == b,
+            (Self::Path(a), Self::Path(b)) => a == b,
+            (Self::Reader(a), Self::Reader(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for AttachmentSource {}
+
 /// An attachment.
 #[derive(Clone, Debug, PartialEq)]
 pub struct Attachment {
     /// The content type of this attachment.
     content_type: ContentType,
 
-    /// The actual file data.
-    data: Vec<u8>,
+    /// How this attachment is presented.
+    disposition: Disposition,
+
+    /// The source of the file data.
+    source: AttachmentSource,
 }
 
 impl Eq for Attachment {}
 
 impl Attachment {
+    /// Creates an attachment with data kept in memory.
+    ///
+    /// # Arguments
+    /// *  `content_type` - The content type of the attachment.
+    /// *  `disposition` - How the attachment is presented.
+    /// *  `data` - The actual file data.
+    pub fn new(
+        content_type: ContentType,
+        disposition: Disposition,
+        data: Vec<u8>,
+    ) -> Self {
+        Self::with_source(
+            content_type,
+            disposition,
+            AttachmentSource::Inline(data),
+        )
+    }
+
+    /// Creates an attachment with data read when a message is sent.
+    ///
+    /// # Arguments
+    /// *  `content_type` - The content type of the attachment.
+    /// *  `disposition` - How the attachment is presented.
+    /// *  `source` - The source of the file data.
+    pub fn with_source(
+        content_type: ContentType,
+        disposition: Disposition,
+        source: AttachmentSource,
+    ) -> Self {
+        Self {
+            content_type,
+            disposition,
+            source,
+        }
+    }
+
     /// The content type of this attachment.
     pub fn content_type(&self) -> &ContentType {
         &self.content_type
     }
 
-    /// The data of this attachment.
-    pub fn data(&self) -> &[u8] {
-        &self.data
+    /// How this attachment is presented.
+    pub fn disposition(&self) -> Disposition {
+        self.disposition
+    }
+
+    /// The source of the data of this attachment.
+    pub fn source(&self) -> &AttachmentSource {
+        &self.source
     }
+
+    /// The data of this attachment, if it is kept in memory.
+    pub fn data(&self) -> Option<&[u8]> {
+        match &self.source {
+            AttachmentSource::Inline(data) => Some(data),
+            _ => None,
+        }
+    }
+}
+
+/// A message rendered from a [template](Template).
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RenderedMessage {
+    /// The message subject.
+    pub subject: String,
+
+    /// The HTML version of the message.
+    pub html: String,
+
+    /// The text version of the message.
+    pub text: String,
 }
 
 /// An email template.
@@ -135,7 +361,9 @@ impl Attachment {
 /// markdown document, and then converting it to a different format.
 ///
 /// Characters in a token cannot be escaped; a token is consumed in its
-/// entirety until a closing bracket is encountered.
+/// entirety until a closing bracket is encountered. A token can however be
+/// escaped in its entirety by doubling the dollar sign: `"$${token}"` is
+/// rendered as `"${token}"`.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Template {
     /// The text wrapping the HTML.
@@ -149,6 +377,12 @@ pub struct Template {
 
     /// Additional named attachments.
     attachments: HashMap<AttachmentName, Attachment>,
+
+    /// Replacements used for keys not provided when rendering.
+    defaults: HashMap<String, String>,
+
+    /// Whether the body is HTML to use verbatim rather than markdown.
+    raw_html: bool,
 }
 
 impl Template {
@@ -166,11 +400,14 @@ impl Template {
     /// *  `wrapping` - The HTML wrapping the message.
     /// *  `body` - The markdown document.
     /// *  `attachments` - Additional attachments.
+    /// *  `defaults` - Replacements used for keys not provided when
+    ///    rendering.
     pub fn new(
         subject: String,
         wrapping: &str,
         body: String,
         attachments: HashMap<AttachmentName, Attachment>,
+        defaults: HashMap<String, String>,
     ) -> Self {
         let wrapping = {
             let mut parts = wrapping.splitn(2, Self::MESSAGE_ELEMENT);
@@ -184,9 +421,23 @@ impl Template {
             subject,
             body,
             attachments,
+            defaults,
+            raw_html: false,
         }
     }
 
+    /// Sets whether the body of this template is HTML.
+    ///
+    /// An HTML body is not parsed as markdown, but inserted into the wrapper
+    /// verbatim after interpolation. The text version of the message is the
+    /// interpolated HTML.
+    ///
+    /// # Arguments
+    /// *  `raw_html` - Whether the body is HTML.
+    pub fn with_raw_html(self, raw_html: bool) -> Self {
+        Self { raw_html, ..self }
+    }
+
     /// The subject of this message.
     pub fn subject(&self) -> &str {
         &self.subject
@@ -196,14 +447,22 @@ impl Template {
     ///
     /// # Arguments
     /// *  `replacements` - A function converting keys to replacement strings.
-    ///    If this function returns `None`, the replacement string is kept.
+    ///    If this function returns `None`, the default replacement is used,
+    ///    and if none exists, the replacement string is kept.
     pub fn html<'a, F>(&self, replacements: F) -> String
     where
         F: Fn(&str) -> Option<&'a str> + 'a,
     {
+        let replacements = |key: &str| self.replacement(&replacements, key);
         let mut result =
             self.interpolate(&self.wrapping.0, |key| replacements(key));
-        html::push_html(&mut result, self.events(|key| replacements(key)));
+        if self.raw_html {
+            result.push_str(
+                &self.interpolate(&self.body, |key| replacements(key)),
+            );
+        } else {
+            html::push_html(&mut result, self.events(|key| replacements(key)));
+        }
         result.push_str(
             &self.interpolate(&self.wrapping.1, |key| replacements(key)),
         );
@@ -214,12 +473,31 @@ impl Template {
     ///
     /// # Arguments
     /// *  `replacements` - A function converting keys to replacement strings.
-    ///    If this function returns `None`, the replacement string is kept.
+    ///    If this function returns `None`, the default replacement is used,
+    ///    and if none exists, the replacement string is kept.
     pub fn text<'a, F>(&self, replacements: F) -> String
     where
         F: Fn(&str) -> Option<&'a str> + 'a,
     {
-        self.interpolate(&self.body, replacements)
+        self.interpolate(&self.body, |key| self.replacement(&replacements, key))
+    }
+
+    /// Renders the subject, HTML and text of this template at once, given
+    /// replacements from `replacements`.
+    ///
+    /// # Arguments
+    /// *  `replacements` - A function converting keys to replacement strings.
+    ///    If this function returns `None`, the default replacement is used,
+    ///    and if none exists, the replacement string is kept.
+    pub fn render<'a, F>(&self, replacements: F) -> RenderedMessage
+    where
+        F: Fn(&str) -> Option<&'a str> + 'a,
+    {
+        RenderedMessage {
+            subject: self.subject.clone(),
+            html: self.html(|key| replacements(key)),
+            text: self.text(|key| replacements(key)),
+        }
     }
 
     /// The attachments for this message.
@@ -227,6 +505,59 @@ impl Template {
         &self.attachments
     }
 
+    /// The default replacements of this template.
+    pub fn defaults(&self) -> &HashMap<String, String> {
+        &self.defaults
+    }
+
+    /// The names of all replacement tokens in the subject, body and wrapper
+    /// of this template.
+    ///
+    /// Escaped tokens are not included. Tokens with a
+    /// [default replacement](Template::defaults) are included.
+    pub fn required_keys(&self) -> BTreeSet<String> {
+        [
+            self.subject.as_str(),
+            self.body.as_str(),
+            self.wrapping.0.as_str(),
+            self.wrapping.1.as_str(),
+        ]
+        .into_iter()
+        .fold(BTreeSet::new(), |mut result, string| {
+            let mut index = 0;
+            while let Some((replacement_range, key_range)) =
+                Self::next_replacement(index, string)
+            {
+                if let Some(key_range) = key_range {
+                    index = key_range.end;
+                    result.insert(string[key_range].into());
+                } else {
+                    // Skip the escaping character and the token start
+                    index = replacement_range.end + "${".len();
+                }
+            }
+            result
+        })
+    }
+
+    /// Looks up a replacement string, falling back on the defaults of this
+    /// template.
+    ///
+    /// # Arguments
+    /// *  `replacements` - A function converting keys to replacement strings.
+    /// *  `key` - The key to look up.
+    fn replacement<'s, 'a, F>(
+        &'s self,
+        replacements: &F,
+        key: &str,
+    ) -> Option<&'s str>
+    where
+        'a: 's,
+        F: Fn(&str) -> Option<&'a str>,
+    {
+        replacements(key).or_else(|| self.defaults.get(key).map(String::as_str))
+    }
+
     /// Provides a sequence of markdown events, interpolating texts with
     /// `replacements`.
     ///
@@ -291,7 +622,8 @@ impl Template {
     /// Interpolates all replacements in `string` given replacements in
     /// `replacements`.
     ///
-    /// Tokens for which `replacements` returns `None` are kept.
+    /// Tokens for which `replacements` returns `None` are kept, and tokens
+    /// escaped as `"$${token}"` are rendered as `"${token}"`.
     ///
     /// # Arguments
     /// *  `string` - The string to interpolate.
@@ -305,13 +637,19 @@ impl Template {
         while let Some((replacement_range, key_range)) =
             Self::next_replacement(index, &text)
         {
-            let key = &text[key_range.clone()];
-            if let Some(replacement) = replacements(key).map(str::to_string) {
-                index += replacement_range.start + replacement.len();
-                text = text.clone();
-                text.replace_range(replacement_range, &replacement);
+            if let Some(key_range) = key_range {
+                let key = &text[key_range.clone()];
+                if let Some(replacement) = replacements(key).map(str::to_string)
+                {
+                    index = replacement_range.start + replacement.len();
+                    text.replace_range(replacement_range, &replacement);
+                } else {
+                    index = key_range.end;
+                }
             } else {
-                index += replacement_range.start + key.len();
+                // Drop the escaping character and skip the token start
+                index = replacement_range.start + "${".len();
+                text.replace_range(replacement_range, "");
             }
         }
         text
@@ -323,26 +661,34 @@ impl Template {
     /// Since a replacement token is marked with `"${token}"`, the replacement
     /// token will always be a subset of the text to be replcaed.
     ///
+    /// An escaped token, marked with `"$${token}"`, yields the range of the
+    /// escaping `'$'` and no token range.
+    ///
     /// # Arguments
     /// *  `offset` - The start offset. Characters before this will be ignored.
     /// *  `string` - The string in which to search.
     fn next_replacement(
         offset: usize,
         string: &str,
-    ) -> Option<(Range<usize>, Range<usize>)> {
+    ) -> Option<(Range<usize>, Option<Range<usize>>)> {
         enum State {
             BeforeStart,
             Start(usize),
+            Escape(usize),
             Key(usize, usize),
         }
         let mut state = State::BeforeStart;
 
         use State::*;
-        for (i, c) in string.chars().enumerate().skip(offset) {
+        for (i, c) in string[offset..].char_indices() {
+            let i = offset + i;
             state = match (state, c) {
                 (BeforeStart, '$') => Start(i),
+                (Start(p), '$') => Escape(p),
                 (Start(p), '{') => Key(p, i + 1),
-                (Key(p, k), '}') => return Some((p..i + 1, k..i)),
+                (Escape(p), '$') => Escape(p + 1),
+                (Escape(p), '{') => return Some((p..p + 1, None)),
+                (Key(p, k), '}') => return Some((p..i + 1, Some(k..i))),
                 (Key(p, k), _) => Key(p, k),
                 _ => BeforeStart,
             };
@@ -357,6 +703,17 @@ impl Template {
 pub struct Templates(HashMap<Language, HashMap<TemplateName, Template>>);
 
 impl Templates {
+    /// Creates a collection of templates from templates constructed in
+    /// memory.
+    ///
+    /// # Arguments
+    /// *  `templates` - The templates, grouped into language and then name.
+    pub fn from_map(
+        templates: HashMap<Language, HashMap<TemplateName, Template>>,
+    ) -> Self {
+        Self(templates)
+    }
+
     /// Attempts to load a collection of templates from a description file.
     ///
     /// # Arguments
@@ -369,8 +726,26 @@ impl Templates {
             .as_ref()
             .parent()
             .map_or_else(PathBuf::new, PathBuf::from);
+        Self::from_str(
+            &fs::read_to_string(path).map_err(|e| Error::Parse(Box::new(e)))?,
+            parent,
+        )
+    }
+
+    /// Attempts to load a collection of templates from a description string.
+    ///
+    /// # Arguments
+    /// *  `description` - The description, in the same format as a
+    ///    description file.
+    /// *  `parent` - The directory relative to which the files referenced by
+    ///    the description are read.
+    pub fn from_str<P>(description: &str, parent: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let parent = parent.as_ref();
         Ok(Self(
-            TemplateDescriptions::load(path)
+            TemplateDescriptions::parse(description)
                 .map_err(|e| Error::Parse(Box::new(e)))?
                 .0
                 .into_iter()
@@ -385,15 +760,17 @@ impl Templates {
                                     Template::new(
                                         description.subject.clone(),
                                         &description.wrapping_data(
-                                            &language, &name, &parent,
+                                            &language, &name, parent,
                                         )?,
                                         description.body_data(
-                                            &language, &name, &parent,
+                                            &language, &name, parent,
                                         )?,
                                         description.attachments_data(
-                                            &language, &name, &parent,
+                                            &language, &name, parent,
                                         )?,
-                                    ),
+                                        description.defaults.clone(),
+                                    )
+                                    .with_raw_html(description.raw_html),
                                 ))
                             })
                             .collect::<Result<HashMap<_, _>, Error>>()?,
@@ -415,6 +792,58 @@ impl Templates {
     ) -> Option<&Template> {
         self.0.get(language).and_then(|l| l.get(name))
     }
+
+    /// The languages for which templates are loaded, in no particular order.
+    pub fn languages(&self) -> Vec<&Language> {
+        self.0.keys().collect()
+    }
+
+    /// The names of the templates loaded for a language, in no particular
+    /// order.
+    ///
+    /// If no templates are loaded for the language, the list is empty.
+    ///
+    /// # Arguments
+    /// *  `language` - The language.
+    pub fn template_names(&self, language: &Language) -> Vec<&TemplateName> {
+        self.0
+            .get(language)
+            .map(|templates| templates.keys().collect())
+            .unwrap_or_default()
+    }
+
+    /// Iterates over all templates with their languages and names, in no
+    /// particular order.
+    pub fn iter(
+        &self,
+    ) -> impl Iterator<Item = (&Language, &TemplateName, &Template)> {
+        self.0.iter().flat_map(|(language, templates)| {
+            templates
+                .iter()
+                .map(move |(name, template)| (language, name, template))
+        })
+    }
+
+    /// The [required keys](Template::required_keys) of all templates,
+    /// grouped into language and then name.
+    pub fn all_required_keys(
+        &self,
+    ) -> HashMap<Language, HashMap<TemplateName, BTreeSet<String>>> {
+        self.0
+            .iter()
+            .map(|(language, templates)| {
+                (
+                    language.clone(),
+                    templates
+                        .iter()
+                        .map(|(name, template)| {
+                            (name.clone(), template.required_keys())
+                        })
+                        .collect(),
+                )
+            })
+            .collect()
+    }
 }
 
 /// A description of a single attachment.
@@ -423,6 +852,10 @@ struct AttachmentDescription {
     /// The content type of this file.
     content_type: String,
 
+    /// How this file is presented.
+    #[serde(default)]
+    disposition: Disposition,
+
     /// The path, relative to the template file, of the data.
     path: String,
 }
@@ -439,8 +872,23 @@ struct TemplateDescription {
     /// The file containing the body.
     body: String,
 
+    /// Whether the body is HTML rather than markdown.
+    #[serde(default)]
+    raw_html: bool,
+
+    /// The character encoding of the body file.
+    ///
+    /// This is a label such as `"iso-8859-1"`. If this is not set, the body
+    /// must be _UTF-8_ encoded.
+    #[serde(default)]
+    charset: Option<String>,
+
     /// The files containing the attachments.
     attachments: HashMap<AttachmentName, AttachmentDescription>,
+
+    /// Replacements used for keys not provided when rendering.
+    #[serde(default)]
+    defaults: HashMap<String, String>,
 }
 
 impl TemplateDescription {
@@ -487,10 +935,35 @@ impl TemplateDescription {
     where
         P: AsRef<Path>,
     {
-        Self::load_string(language, name, parent.as_ref().join(&self.body))
+        let path = parent.as_ref().join(&self.body);
+        match &self.charset {
+            Some(charset) => {
+                let encoding =
+                    encoding_rs::Encoding::for_label(charset.as_bytes())
+                        .ok_or_else(|| {
+                            Error::Parse(
+                                format!("unknown charset: {}", charset).into(),
+                            )
+                        })?;
+                Self::load(language, name, path, |d| {
+                    encoding
+                        .decode_without_bom_handling_and_without_replacement(&d)
+                        .map(String::from)
+                        .ok_or_else(|| {
+                            Error::Parse(
+                                format!("invalid {} data", encoding.name())
+                                    .into(),
+                            )
+                        })
+                })
+            }
+            None => Self::load_string(language, name, path),
+        }
     }
 
-    /// Attempts to load the files specified as attachments.
+    /// Resolves the files specified as attachments.
+    ///
+    /// The files are not read until a message is sent, but they must exist.
     ///
     /// # Arguments
     /// *  `language` - The language for this template. This is used to
@@ -518,12 +991,12 @@ impl TemplateDescription {
                             &description.content_type,
                         )
                         .map_err(|e| Error::Parse(Box::new(e)))?,
-                        data: Self::load(
+                        disposition: description.disposition,
+                        source: AttachmentSource::Path(Self::locate(
                             language,
                             template_name,
                             parent.as_ref().join(&description.path),
-                            Ok,
-                        )?,
+                        )?),
                     },
                 ))
             })
@@ -563,6 +1036,36 @@ impl TemplateDescription {
             .and_then(mapper)
     }
 
+    /// Ensures that a file exists without reading it.
+    ///
+    /// # Arguments
+    /// *  `language` - The language for this template. This is used to
+    ///    generate an error message.
+    /// *  `name` - The name of this template. This is used to generate and
+    ///    error message.
+    /// *  `path` - The full path to the file.
+    fn locate(
+        language: &Language,
+        name: &TemplateName,
+        path: PathBuf,
+    ) -> Result<PathBuf, Error> {
+        match fs::metadata(&path) {
+            Ok(metadata) if metadata.is_file() => Ok(path),
+            Ok(_) => Err(Error::Reference(
+                path,
+                language.clone(),
+                name.clone(),
+                "not a file".into(),
+            )),
+            Err(e) => Err(Error::Reference(
+                path,
+                language.clone(),
+                name.clone(),
+                Box::new(e),
+            )),
+        }
+    }
+
     /// Attempts to load a text file.
     ///
     /// This functtion will fail if the file does not contain _UTF-8_ encoded
@@ -593,16 +1096,12 @@ struct TemplateDescriptions(
 );
 
 impl TemplateDescriptions {
-    /// Loads a description of a collection of templates.
+    /// Parses a description of a collection of templates.
     ///
     /// # Arguments
-    /// *  `path` - The source path.
-    pub fn load<P>(path: P) -> Result<Self, io::Error>
-    where
-        P: AsRef<Path>,
-    {
-        toml::from_str(&fs::read_to_string(path)?)
-            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+    /// *  `description` - The description.
+    pub fn parse(description: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(description)
     }
 }
 
@@ -614,17 +1113,26 @@ mod tests {
 
     #[test]
     fn templates_valid() {
-        let attachments = vec![(
-            "file1".into(),
-            Attachment {
-                content_type: ContentType::parse("text/plain").unwrap(),
-                data: fs::read(
-                    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-                        .join("resources/test/email/template/attachment.txt"),
-                )
-                .unwrap(),
-            },
-        )]
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("resources/test/email/template/attachment.txt");
+        let attachments = vec![
+            (
+                "file1".into(),
+                Attachment {
+                    content_type: ContentType::parse("text/plain").unwrap(),
+                    disposition: Disposition::Attachment,
+                    source: AttachmentSource::Path(path.clone()),
+                },
+            ),
+            (
+                "file2".into(),
+                Attachment {
+                    content_type: ContentType::parse("text/plain").unwrap(),
+                    disposition: Disposition::Inline,
+                    source: AttachmentSource::Path(path),
+                },
+            ),
+        ]
         .into_iter()
         .collect();
         assert_eq!(
@@ -640,6 +1148,9 @@ mod tests {
                             ${replace}\n"
                                 .into(),
                             attachments,
+                            [("replace".into(), "default".into())]
+                                .into_iter()
+                                .collect(),
                         ),
                     ),]
                     .iter()
@@ -658,6 +1169,158 @@ mod tests {
         );
     }
 
+    #[test]
+    fn templates_from_str() {
+        let parent = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("resources/test/email/template");
+        assert_eq!(
+            Templates::load(parent.join("valid.toml")).unwrap(),
+            Templates::from_str(
+                &fs::read_to_string(parent.join("valid.toml")).unwrap(),
+                &parent,
+            )
+            .unwrap(),
+        );
+    }
+
+    #[test]
+    fn templates_from_str_invalid() {
+        assert!(matches!(
+            Templates::from_str("invalid", "."),
+            Err(Error::Parse(_)),
+        ));
+    }
+
+    #[test]
+    fn templates_from_map() {
+        let template = Template::new(
+            "subject".into(),
+            "<message/>",
+            "body".into(),
+            HashMap::new(),
+            HashMap::new(),
+        );
+        let templates = Templates::from_map(
+            [(
+                "l1".into(),
+                [("t1".into(), template.clone())].into_iter().collect(),
+            )]
+            .into_iter()
+            .collect(),
+        );
+        assert_eq!(Some(&template), templates.get(&"l1".into(), &"t1".into()));
+        assert_eq!(None, templates.get(&"l2".into(), &"t1".into()));
+    }
+
+    #[test]
+    fn templates_enumerate() {
+        let template = Template::new(
+            "subject".into(),
+            "<message/>",
+            "body".into(),
+            HashMap::new(),
+            HashMap::new(),
+        );
+        let templates = Templates::from_map(
+            [(
+                "l1".into(),
+                [("t1".into(), template.clone())].into_iter().collect(),
+            )]
+            .into_iter()
+            .collect(),
+        );
+        assert_eq!(vec![&Language::from("l1")], templates.languages());
+        assert_eq!(
+            vec![&TemplateName::from("t1")],
+            templates.template_names(&"l1".into()),
+        );
+        assert!(templates.template_names(&"l2".into()).is_empty());
+        assert_eq!(
+            vec![(&Language::from("l1"), &TemplateName::from("t1"), &template)],
+            templates.iter().collect::<Vec<_>>(),
+        );
+    }
+
+    #[test]
+    fn language_preference_from_str() {
+        assert_eq!(
+            Ok(LanguagePreference {
+                language: "fr".into(),
+                quality: 0.9,
+            }),
+            "fr;q=0.9".parse(),
+        );
+        assert_eq!(
+            Ok(LanguagePreference {
+                language: "en-GB".into(),
+                quality: 1.0,
+            }),
+            " en-GB ".parse(),
+        );
+        assert!("".parse::<LanguagePreference>().is_err());
+        assert!("fr;q=2".parse::<LanguagePreference>().is_err());
+        assert!("fr;q=x".parse::<LanguagePreference>().is_err());
+    }
+
+    #[test]
+    fn language_preference_parse_header() {
+        assert_eq!(
+            vec![Language::from("de"), "fr".into(), "en".into(), "sv".into()],
+            LanguagePreference::order(&LanguagePreference::parse_header(
+                "en;q=0.8, fr;q=0.9, *;q=0.5, de, nl;q=0, sv;q=0.8, ;q=1",
+            )),
+        );
+    }
+
+    #[test]
+    fn required_keys() {
+        assert_eq!(
+            ["body", "post", "pre", "subject"]
+                .into_iter()
+                .map(String::from)
+                .collect::<BTreeSet<_>>(),
+            Template::new(
+                "${subject}".into(),
+                "${pre}<message/>${post}",
+                "${body}, $${escaped}, ${body}".into(),
+                Default::default(),
+                Default::default(),
+            )
+            .required_keys(),
+        );
+    }
+
+    #[test]
+    fn all_required_keys() {
+        let templates = Templates::from_map(
+            [(
+                Language::from("en"),
+                [(
+                    TemplateName::from("greeting"),
+                    Template::new(
+                        "Hello".into(),
+                        "",
+                        "Hello, ${name}".into(),
+                        Default::default(),
+                        Default::default(),
+                    ),
+                )]
+                .into_iter()
+                .collect(),
+            )]
+            .into_iter()
+            .collect(),
+        );
+
+        assert_eq!(
+            Some(&["name".to_string()].into_iter().collect()),
+            templates
+                .all_required_keys()
+                .get(&"en".into())
+                .and_then(|t| t.get(&"greeting".into())),
+        );
+    }
+
     #[test]
     fn text_simple_replacements() {
         assert_eq!(
@@ -667,10 +1330,162 @@ mod tests {
                 "",
                 "${r1}, ${r2}".into(),
                 Default::default(),
+                Default::default(),
             )
             .text(|r| match r {
-                "r1" => Some(&"replacement 1"),
-                "r2" => Some(&"replacement 2"),
+                "r1" => Some("replacement 1"),
+                "r2" => Some("replacement 2"),
+                _ => None,
+            }),
+        );
+    }
+
+    #[test]
+    fn render_all_parts() {
+        let template = Template::new(
+            "subject".into(),
+            "<html>${r1}<message/></html>",
+            "${r1}, ${r2}".into(),
+            Default::default(),
+            [("r2".to_string(), "default 2".to_string())]
+                .into_iter()
+                .collect(),
+        );
+        let replacements = |r: &str| match r {
+            "r1" => Some("replacement 1"),
+            _ => None,
+        };
+
+        assert_eq!(
+            RenderedMessage {
+                subject: "subject".into(),
+                html: template.html(replacements),
+                text: "replacement 1, default 2".into(),
+            },
+            template.render(replacements),
+        );
+    }
+
+    #[test]
+    fn text_escaped_token() {
+        assert_eq!(
+            "${not_replaced}, replacement 1, $${r1}",
+            Template::new(
+                "subject".into(),
+                "",
+                "$${not_replaced}, ${r1}, $$${r1}".into(),
+                Default::default(),
+                Default::default(),
+            )
+            .text(|r| match r {
+                "not_replaced" => Some("replacement"),
+                "r1" => Some("replacement 1"),
+                _ => None,
+            }),
+        );
+    }
+
+    #[test]
+    fn text_non_ascii_replacements() {
+        assert_eq!(
+            "åäö replacement 1 ${r2} ü",
+            Template::new(
+                "subject".into(),
+                "",
+                "åäö ${r1} ${r2} ü".into(),
+                Default::default(),
+                Default::default(),
+            )
+            .text(|r| match r {
+                "r1" => Some("replacement 1"),
+                _ => None,
+            }),
+        );
+    }
+
+    #[test]
+    fn text_default_replacements() {
+        let template = Template::new(
+            "subject".into(),
+            "",
+            "${r1}, ${r2}, ${r3}".into(),
+            Default::default(),
+            [
+                ("r1".into(), "default 1".into()),
+                ("r2".into(), "default 2".into()),
+            ]
+            .into_iter()
+            .collect(),
+        );
+
+        assert_eq!(
+            "replacement 1, default 2, ${r3}",
+            template.text(|r| match r {
+                "r1" => Some("replacement 1"),
+                _ => None,
+            }),
+        );
+        assert_eq!(
+            "<p>replacement 1, default 2, ${r3}</p>\n",
+            template.html(|r| match r {
+                "r1" => Some("replacement 1"),
+                _ => None,
+            }),
+        );
+    }
+
+    #[test]
+    fn templates_raw_html() {
+        let templates = Templates::load(
+            PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("resources/test/email/template/raw.toml"),
+        )
+        .unwrap();
+        let template = templates.get(&"l1".into(), &"t2".into()).unwrap();
+
+        assert_eq!(
+            "<html><body><p>*Hej* Anna, åäö</p>\n</body></html>\n",
+            template.html(|r| match r {
+                "name" => Some("Anna"),
+                _ => None,
+            }),
+        );
+    }
+
+    #[test]
+    fn templates_invalid_charset() {
+        assert!(matches!(
+            Templates::from_str(
+                r#"
+                [l1.t2]
+                wrapping = "t1.l1.html"
+                subject = "subject"
+                body = "t2.l1.html"
+                raw_html = true
+                charset = "invalid"
+                attachments = {}
+                "#,
+                PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                    .join("resources/test/email/template"),
+            ),
+            Err(Error::Parse(_)),
+        ));
+    }
+
+    #[test]
+    fn html_raw() {
+        assert_eq!(
+            "<div><p>*replacement 1*</p></div>",
+            Template::new(
+                "subject".into(),
+                "<div><message/></div>",
+                "<p>*${r1}*</p>".into(),
+                Default::default(),
+                Default::default(),
+            )
+            .with_raw_html(true)
+            .html(|r| match r {
+                "r1" => Some("replacement 1"),
                 _ => None,
             }),
         );
@@ -691,6 +1506,7 @@ This is the *first* paragraph.
 And _this_ is the second."
                     .into(),
                 Default::default(),
+                Default::default(),
             )
             .html(|_| None),
         );
@@ -705,10 +1521,11 @@ And _this_ is the second."
                 "",
                 "${r1}, ${r2}".into(),
                 Default::default(),
+                Default::default(),
             )
             .html(|r| match r {
-                "r1" => Some(&"replacement 1"),
-                "r2" => Some(&"replacement 2"),
+                "r1" => Some("replacement 1"),
+                "r2" => Some("replacement 2"),
                 _ => None,
             }),
         );
@@ -723,10 +1540,11 @@ And _this_ is the second."
                 "<html><body><message/></body></html>",
                 "${r1}, ${r2}".into(),
                 Default::default(),
+                Default::default(),
             )
             .html(|r| match r {
-                "r1" => Some(&"replacement 1"),
-                "r2" => Some(&"replacement 2"),
+                "r1" => Some("replacement 1"),
+                "r2" => Some("replacement 2"),
                 _ => None,
             }),
         );
@@ -738,12 +1556,13 @@ And _this_ is the second."
             "<header>r</header><p>r</p>\n<footer>r</footer>",
             Template::new(
                 "subject".into(),
-                "<header>${r}</header><message/><footer>${r}</footer>".into(),
+                "<header>${r}</header><message/><footer>${r}</footer>",
                 "${r}".into(),
                 Default::default(),
+                Default::default(),
             )
             .html(|r| match r {
-                "r" => Some(&"r"),
+                "r" => Some("r"),
                 _ => None,
             }),
         );
@@ -758,10 +1577,11 @@ And _this_ is the second."
                 "",
                 "`${r1}, ${r2}`".into(),
                 Default::default(),
+                Default::default(),
             )
             .html(|r| match r {
-                "r1" => Some(&"replacement 1"),
-                "r2" => Some(&"replacement 2"),
+                "r1" => Some("replacement 1"),
+                "r2" => Some("replacement 2"),
                 _ => None,
             }),
         );
@@ -776,9 +1596,10 @@ And _this_ is the second."
                 "",
                 "[link](${r})".into(),
                 Default::default(),
+                Default::default(),
             )
             .html(|r| match r {
-                "r" => Some(&"http://example.com"),
+                "r" => Some("http://example.com"),
                 _ => None,
             }),
         );
@@ -793,9 +1614,10 @@ And _this_ is the second."
                 "",
                 "${r".into(),
                 Default::default(),
+                Default::default(),
             )
             .html(|r| match r {
-                "r" => Some(&"this & *that*"),
+                "r" => Some("this & *that*"),
                 _ => None,
             }),
         );
@@ -810,9 +1632,10 @@ And _this_ is the second."
                 "",
                 "${r1}, ${r2}".into(),
                 Default::default(),
+                Default::default(),
             )
             .html(|r| match r {
-                "r1" => Some(&"replacement 1"),
+                "r1" => Some("replacement 1"),
                 _ => None,
             }),
         );
@@ -827,9 +1650,28 @@ And _this_ is the second."
                 "",
                 "${r}".into(),
                 Default::default(),
+                Default::default(),
+            )
+            .html(|r| match r {
+                "r" => Some("this & *that*"),
+                _ => None,
+            }),
+        );
+    }
+
+    #[test]
+    fn html_escaped_token() {
+        assert_eq!(
+            "<p>${not_replaced}</p>\n",
+            Template::new(
+                "subject".into(),
+                "",
+                "$${not_replaced}".into(),
+                Default::default(),
+                Default::default(),
             )
             .html(|r| match r {
-                "r" => Some(&"this & *that*"),
+                "not_replaced" => Some("replacement"),
                 _ => None,
             }),
         );
@@ -845,10 +1687,11 @@ And _this_ is the second."
                 "This example ${uses a very, VERY long replacement token}"
                     .into(),
                 Default::default(),
+                Default::default(),
             )
             .html(|r| match r {
                 "uses a very, VERY long replacement token" =>
-                    Some(&"is correct"),
+                    Some("is correct"),
                 _ => None,
             }),
         );
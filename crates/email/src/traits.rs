@@ -1,9 +1,10 @@
 use std::collections::HashMap;
 
 use async_trait::async_trait;
-use lettre::message::Mailboxes;
+use lettre::message::{Mailbox, Mailboxes};
 
-use crate::template::{Language, TemplateName};
+use crate::message::Email;
+use crate::template::{Language, LanguagePreference, TemplateName};
 use crate::Error;
 
 /// An e-mail sender.
@@ -11,6 +12,9 @@ use crate::Error;
 pub trait Sender: Send + Sync {
     /// Sends an e-mail to a recipient.
     ///
+    /// This is a convenience wrapper around [`send_email`](Sender::send_email);
+    /// use [`message`](dyn Sender::message) for more options.
+    ///
     /// # Arguments
     /// *  `recipients`- The e-mail recipients.
     /// *  `languages` - A sequence of langauges to use, in decreasing order of
@@ -24,5 +28,94 @@ pub trait Sender: Send + Sync {
         languages: &[Language],
         template: &TemplateName,
         replacements: &HashMap<String, String>,
-    ) -> Result<(), Error>;
+    ) -> Result<(), Error> {
+        self.send_from(None, recipients, languages, template, replacements)
+            .await
+    }
+
+    /// Sends an e-mail to a recipient, selecting the language by quality
+    /// value.
+    ///
+    /// This is a convenience wrapper around [`send`](Sender::send).
+    ///
+    /// # Arguments
+    /// *  `recipients`- The e-mail recipients.
+    /// *  `preferences` - The languages to use, with quality values. The
+    ///    language with the highest quality value for which the template
+    ///    exists is used.
+    /// *  `template` - The template used to format the message.
+    /// *  `replacements` - A function converting keys to replacement strings.
+    ///    If this function returns `None`, the replacement string is kept.
+    async fn send_preferred(
+        &self,
+        recipients: Mailboxes,
+        preferences: &[LanguagePreference],
+        template: &TemplateName,
+        replacements: &HashMap<String, String>,
+    ) -> Result<(), Error> {
+        self.send(
+            recipients,
+            &LanguagePreference::order(preferences),
+            template,
+            replacements,
+        )
+        .await
+    }
+
+    /// Sends an e-mail to a recipient from a specific sender.
+    ///
+    /// # Arguments
+    /// *  `from` - The mailbox indicated by the _From_ header. If this is
+    ///    `None`, the default sender is used.
+    /// *  `recipients`- The e-mail recipients.
+    /// *  `languages` - A sequence of languages to use, in decreasing order of
+    ///    relevance. The first language for which the template exists is used.
+    /// *  `template` - The template used to format the message.
+    /// *  `replacements` - A function converting keys to replacement strings.
+    ///    If this function returns `None`, the replacement string is kept.
+    async fn send_from(
+        &self,
+        from: Option<Mailbox>,
+        recipients: Mailboxes,
+        languages: &[Language],
+        template: &TemplateName,
+        replacements: &HashMap<String, String>,
+    ) -> Result<(), Error> {
+        self.send_email(Email {
+            from,
+            to: recipients,
+            languages: languages.to_vec(),
+            replacements: replacements.clone(),
+            ..Email::new(template.clone())
+        })
+        .await
+    }
+
+    /// Sends an e-mail.
+    ///
+    /// # Arguments
+    /// *  `email` - A description of the e-mail to send.
+    async fn send_email(&self, email: Email) -> Result<(), Error>;
+
+    /// Sends an e-mail to a single recipient.
+    ///
+    /// This is a convenience wrapper around [`send`](Sender::send).
+    ///
+    /// # Arguments
+    /// *  `recipient` - The e-mail recipient.
+    /// *  `languages` - A sequence of languages to use, in decreasing order of
+    ///    relevance. The first language for which the template exists is used.
+    /// *  `template` - The template used to format the message.
+    /// *  `replacements` - A function converting keys to replacement strings.
+    ///    If this function returns `None`, the replacement string is kept.
+    async fn send_one(
+        &self,
+        recipient: Mailbox,
+        languages: &[Language],
+        template: &TemplateName,
+        replacements: &HashMap<String, String>,
+    ) -> Result<(), Error> {
+        self.send(recipient.into(), languages, template, replacements)
+            .await
+    }
 }
@@ -1,83 +1,732 @@
 use proc_macro::*;
-use quote::quote;
-use syn::{parse_macro_input, Ident, ItemStruct};
+use quote::{quote, ToTokens};
+use syn::parse::Parser;
+use syn::punctuated::Punctuated;
+use syn::{
+    parse_macro_input, Attribute, Field, Ident, ItemStruct, Lit, Meta,
+    NestedMeta, Token, Type,
+};
+
+/// The way a field value is bound to statements and read from rows.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Encoding {
+    /// The value is converted to its own type when bound.
+    Default,
+
+    /// The value is wrapped in `Json`; marked by `#[json]`.
+    Json,
+
+    /// The value is bound and read as is; marked by `#[raw]`.
+    Raw,
+}
+
+impl Encoding {
+    /// The attribute marking a field as stored as JSON.
+    const JSON: &'static str = "json";
+
+    /// The attribute marking a field as bound and read as is.
+    const RAW: &'static str = "raw";
+
+    /// Determines the encoding of a field from its attributes.
+    ///
+    /// # Arguments
+    /// *  `field` - The field to inspect.
+    fn of(field: &Field) -> Self {
+        field
+            .attrs
+            .iter()
+            .find_map(|a| {
+                if a.path.is_ident(Self::JSON) {
+                    Some(Self::Json)
+                } else if a.path.is_ident(Self::RAW) {
+                    Some(Self::Raw)
+                } else {
+                    None
+                }
+            })
+            .unwrap_or(Self::Default)
+    }
+
+    /// Whether an attribute is one of the field attributes handled by this
+    /// macro.
+    ///
+    /// # Arguments
+    /// *  `attr` - The attribute to check.
+    fn is_attribute(attr: &Attribute) -> bool {
+        attr.path.is_ident(Self::JSON)
+            || attr.path.is_ident(Self::RAW)
+            || attr.path.is_ident(KEY)
+            || attr.path.is_ident(INDEX)
+    }
+}
+
+/// The attribute marking a field as the key of an entity.
+const KEY: &str = "key";
+
+/// The attribute marking a field as indexed.
+const INDEX: &str = "index";
+
+/// Determines whether a field is indexed from its attributes.
+///
+/// This returns `None` if the field is not indexed, and otherwise whether
+/// the index is unique.
+///
+/// # Arguments
+/// *  `field` - The field to inspect.
+fn index_of(field: &Field) -> Option<bool> {
+    field
+        .attrs
+        .iter()
+        .find(|a| a.path.is_ident(INDEX))
+        .map(|a| {
+            is_unique(
+                &a.parse_meta()
+                    .expect("Expected #[index] or #[index(unique)]"),
+            )
+        })
+}
+
+/// Determines whether an index is unique.
+///
+/// # Arguments
+/// *  `meta` - Either `index` or `index(unique)`.
+fn is_unique(meta: &Meta) -> bool {
+    match meta {
+        Meta::Path(_) => false,
+        Meta::List(list)
+            if list.nested.len() == 1
+                && matches!(
+                    list.nested.first(),
+                    Some(NestedMeta::Meta(Meta::Path(p)))
+                        if p.is_ident("unique")
+                ) =>
+        {
+            true
+        }
+        _ => panic!("Expected #[index] or #[index(unique)]"),
+    }
+}
+
+/// The macro argument enabling `EntityHooks`.
+const HOOKS: &str = "hooks";
+
+/// The macro argument suppressing the description of an entity.
+const NO_DESCRIPTION: &str = "no_description";
+
+/// The helper attribute of the derive macro.
+const ENTITY: &str = "entity";
+
+/// The derive macro argument naming the table.
+const TABLE: &str = "table";
+
+/// The derive macro field argument naming the column.
+const COLUMN: &str = "column";
+
+/// Collects the arguments of all `#[entity(...)]` attributes.
+///
+/// # Arguments
+/// *  `attrs` - The attributes of a struct or field.
+fn arguments_of(attrs: &[Attribute]) -> Vec<NestedMeta> {
+    attrs
+        .iter()
+        .filter(|a| a.path.is_ident(ENTITY))
+        .flat_map(|a| match a.parse_meta() {
+            Ok(Meta::List(list)) => list.nested,
+            _ => panic!("Expected #[entity(...)]"),
+        })
+        .collect()
+}
+
+/// A column of an entity.
+struct Column {
+    /// The name of the field.
+    ident: Ident,
+
+    /// The type of the field.
+    ty: Type,
+
+    /// The name of the column.
+    name: String,
+
+    /// The way the value is bound and read.
+    encoding: Encoding,
+
+    /// Whether the column is indexed, and if so, whether the index is
+    /// unique.
+    index: Option<bool>,
+}
+
+impl Column {
+    /// Creates a column named after a field, without any annotations.
+    ///
+    /// # Arguments
+    /// *  `field` - The field.
+    fn of(field: &Field) -> Self {
+        let ident = field
+            .ident
+            .clone()
+            .expect("The fields of an entity must be named");
+        Self {
+            name: ident.to_string(),
+            ident,
+            ty: field.ty.clone(),
+            encoding: Encoding::Default,
+            index: None,
+        }
+    }
+}
+
+/// A parsed entity.
+struct Definition {
+    /// The name of the struct.
+    name: Ident,
+
+    /// The name of the table.
+    table_name: String,
+
+    /// Whether the entity calls `EntityHooks`.
+    hooks: bool,
+
+    /// Whether a description is generated for the entity.
+    description: bool,
+
+    /// All columns, in declaration order.
+    columns: Vec<Column>,
+
+    /// The index of the key in `columns`.
+    key: usize,
+}
+
+impl Definition {
+    /// Creates a definition.
+    ///
+    /// # Arguments
+    /// *  `name` - The name of the struct.
+    /// *  `table_name` - The name of the table.
+    /// *  `hooks` - Whether the entity calls `EntityHooks`.
+    /// *  `description` - Whether a description is generated.
+    /// *  `columns` - All columns, in declaration order.
+    /// *  `key` - The index of the key in `columns`.
+    fn new(
+        name: Ident,
+        table_name: String,
+        hooks: bool,
+        description: bool,
+        columns: Vec<Column>,
+        key: usize,
+    ) -> Self {
+        if columns.is_empty() {
+            panic!("An entity must have at least one field");
+        }
+        Self {
+            name,
+            table_name,
+            hooks,
+            description,
+            columns,
+            key,
+        }
+    }
+}
 
 pub fn entity(attr: TokenStream, item: TokenStream) -> TokenStream {
-    let table_name = parse_macro_input!(attr as Ident).to_string();
+    let mut args = Punctuated::<Ident, Token![,]>::parse_terminated
+        .parse(attr)
+        .expect("Expected a table name, optionally followed by arguments")
+        .into_iter();
+    let table_name = args
+        .next()
+        .expect("An entity must have a table name")
+        .to_string();
+    let mut hooks = false;
+    let mut description = true;
+    for arg in args {
+        if arg == HOOKS {
+            hooks = true;
+        } else if arg == NO_DESCRIPTION {
+            description = false;
+        } else {
+            panic!(
+                "Unknown entity argument {}; expected {} or {}",
+                arg, HOOKS, NO_DESCRIPTION,
+            );
+        }
+    }
+    let mut struct_definition = parse_macro_input!(item as ItemStruct);
+
+    // The key is the field marked with #[key], or the first field
+    let key = {
+        let mut keys = struct_definition
+            .fields
+            .iter()
+            .enumerate()
+            .filter(|(_, f)| f.attrs.iter().any(|a| a.path.is_ident(KEY)))
+            .map(|(i, _)| i);
+        let key = keys.next().unwrap_or(0);
+        if keys.next().is_some() {
+            panic!("An entity must have at most one key field");
+        }
+        key
+    };
+    let columns = struct_definition
+        .fields
+        .iter()
+        .map(|f| {
+            let mut column = Column::of(f);
+            column.encoding = Encoding::of(f);
+            column.index = index_of(f);
+            column
+        })
+        .collect();
+    let definition = Definition::new(
+        struct_definition.ident.clone(),
+        table_name,
+        hooks,
+        description,
+        columns,
+        key,
+    );
+
+    // Remove our attributes, since they are unknown to the compiler
+    struct_definition
+        .fields
+        .iter_mut()
+        .for_each(|f| f.attrs.retain(|a| !Encoding::is_attribute(a)));
+
+    let mut result: TokenStream = quote! { #struct_definition }.into();
+    result.extend(generate(&definition));
+    result
+}
+
+pub fn derive_entity(item: TokenStream) -> TokenStream {
     let struct_definition = parse_macro_input!(item as ItemStruct);
-    let name = struct_definition.ident.clone();
+
+    let mut table_name = None;
+    let mut hooks = false;
+    let mut description = true;
+    for arg in arguments_of(&struct_definition.attrs) {
+        match arg {
+            NestedMeta::Meta(Meta::NameValue(value))
+                if value.path.is_ident(TABLE) =>
+            {
+                match value.lit {
+                    Lit::Str(lit) => table_name = Some(lit.value()),
+                    _ => panic!("Expected #[entity(table = \"...\")]"),
+                }
+            }
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident(HOOKS) => {
+                hooks = true;
+            }
+            NestedMeta::Meta(Meta::Path(path))
+                if path.is_ident(NO_DESCRIPTION) =>
+            {
+                description = false;
+            }
+            _ => panic!(
+                "Unknown entity argument; expected table, hooks or \
+                 no_description"
+            ),
+        }
+    }
+    let table_name =
+        table_name.expect("An entity must have #[entity(table = \"...\")]");
+
+    let mut key = None;
+    let mut columns = Vec::new();
+    for (i, field) in struct_definition.fields.iter().enumerate() {
+        let mut column = Column::of(field);
+        for arg in arguments_of(&field.attrs) {
+            match arg {
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident(KEY) => {
+                    if key.replace(i).is_some() {
+                        panic!("An entity must have at most one key field");
+                    }
+                }
+                NestedMeta::Meta(Meta::NameValue(value))
+                    if value.path.is_ident(COLUMN) =>
+                {
+                    match value.lit {
+                        Lit::Str(lit) => column.name = lit.value(),
+                        _ => panic!("Expected #[entity(column = \"...\")]"),
+                    }
+                }
+                NestedMeta::Meta(Meta::Path(path))
+                    if path.is_ident(Encoding::JSON) =>
+                {
+                    column.encoding = Encoding::Json;
+                }
+                NestedMeta::Meta(Meta::Path(path))
+                    if path.is_ident(Encoding::RAW) =>
+                {
+                    column.encoding = Encoding::Raw;
+                }
+                NestedMeta::Meta(meta) if meta.path().is_ident(INDEX) => {
+                    column.index = Some(is_unique(&meta));
+                }
+                _ => panic!(
+                    "Unknown entity field argument; expected key, column, \
+                     json, raw or index"
+                ),
+            }
+        }
+        columns.push(column);
+    }
+    let definition = Definition::new(
+        struct_definition.ident,
+        table_name,
+        hooks,
+        description,
+        columns,
+        key.unwrap_or(0),
+    );
+
+    generate(&definition)
+}
+
+/// Generates the implementation of an entity.
+///
+/// # Arguments
+/// *  `definition` - The definition of the entity.
+fn generate(definition: &Definition) -> TokenStream {
+    let Definition {
+        name,
+        table_name,
+        hooks,
+        description,
+        columns,
+        key,
+    } = definition;
     let description_name =
         Ident::new(&format!("{}Description", name), Span::call_site().into());
 
-    let self_ty = &struct_definition.ident;
+    let self_ty = name;
 
-    let (key_name, key_ty) = struct_definition
-        .fields
-        .iter()
-        .next()
-        .map(|f| (f.ident.clone().unwrap(), f.ty.clone()))
-        .expect("An entity must have at least one field");
+    let (key_name, key_ty, key_column) =
+        (&columns[*key].ident, &columns[*key].ty, &columns[*key].name);
     let key_i = 1;
 
-    let (field_name, field_ty) = struct_definition
-        .fields
+    let fields = columns
         .iter()
-        .skip(1)
-        .map(|f| (f.ident.clone().unwrap(), f.ty.clone()))
-        .unzip::<_, _, Vec<_>, Vec<_>>();
-    let field_i = (2..=struct_definition.fields.len()).collect::<Vec<_>>();
+        .enumerate()
+        .filter(|&(i, _)| i != *key)
+        .map(|(_, c)| c)
+        .collect::<Vec<_>>();
+    let field_name = fields.iter().map(|c| &c.ident).collect::<Vec<_>>();
+    let field_ty = fields.iter().map(|c| &c.ty).collect::<Vec<_>>();
+    let field_column = fields.iter().map(|c| &c.name).collect::<Vec<_>>();
+    let field_i = (2..=columns.len()).collect::<Vec<_>>();
 
-    quote! {
-        #struct_definition
-
-        /// A description of an entity.
-        ///
-        /// This struct contains all fields of the entity except the key.
-        #[derive(
-            Clone,
-            Debug,
-            Default,
-            PartialEq,
-            ::serde::Deserialize,
-            ::serde::Serialize,
-        )]
-        pub struct #description_name {
-            #(
-                pub #field_name: Option<#field_ty>,
-            )*
-        }
+    // Fields may be annotated to change how they are bound and read
+    let field_encoding = fields.iter().map(|c| c.encoding).collect::<Vec<_>>();
+    let field_bind_of = |item: &dyn ToTokens| {
+        field_name
+            .iter()
+            .zip(&field_ty)
+            .zip(&field_encoding)
+            .map(|((name, ty), encoding)| match encoding {
+                Encoding::Default => {
+                    quote! { <#ty>::from(#item.#name.clone()) }
+                }
+                Encoding::Json => quote! {
+                    ::weru::database::sqlx::types::Json(#item.#name.clone())
+                },
+                Encoding::Raw => quote! { #item.#name.clone() },
+            })
+            .collect::<Vec<_>>()
+    };
+    let field_bind = field_bind_of(&quote! { self });
+    let item_field_bind = field_bind_of(&quote! { item });
+    let value_bind = field_ty
+        .iter()
+        .zip(&field_encoding)
+        .map(|(ty, encoding)| match encoding {
+            Encoding::Default => quote! { <#ty>::from(value.clone()) },
+            Encoding::Json => quote! {
+                ::weru::database::sqlx::types::Json(value.clone())
+            },
+            Encoding::Raw => quote! { value.clone() },
+        })
+        .collect::<Vec<_>>();
+    let field_get = field_column
+        .iter()
+        .zip(&field_ty)
+        .zip(&field_encoding)
+        .map(|((column, ty), encoding)| match encoding {
+            Encoding::Default => quote! { row.try_get(#column)? },
+            Encoding::Json => quote! {
+                row.try_get::<
+                    ::weru::database::sqlx::types::Json<#ty>,
+                    _,
+                >(#column)?.0
+            },
+            Encoding::Raw => quote! {
+                row.try_get::<#ty, _>(#column)?
+            },
+        })
+        .collect::<Vec<_>>();
 
-        #[allow(unused)]
-        impl #description_name {
-            /// Merges this description with another.
-            ///
-            /// All items set in `other` will be copied to a new item.
-            ///
-            /// # Arguments
-            /// *  `other` - Another description.
-            pub fn merge(self, other: Self) -> Self {
-                Self {
+    // The number of parameters bound for every row inserted
+    let column_count = fields.len() + 1;
+
+    // The SQL statements, for a table name given as a string literal token
+    let create = |table: &dyn ToTokens| {
+        quote! {
+            concat!(
+                "INSERT INTO ", #table, " (",
+                    #key_column,
+                    #(", ", #field_column),*,
+                ") ",
+                "VALUES (",
+                    ::weru::database::parameter!(#key_i),
+                    #(", ", ::weru::database::parameter!(#field_i)),*,
+                ")",
+            )
+        }
+    };
+    let read = |table: &dyn ToTokens| {
+        quote! {
+            concat!(
+                "SELECT ",
+                    #key_column,
+                    #(", ", #field_column),*,
+                " ",
+                "FROM ", #table, " ",
+                "WHERE ", #key_column, " = ",
+                    ::weru::database::parameter!(#key_i),
+            )
+        }
+    };
+    let read_all = |table: &dyn ToTokens| {
+        quote! {
+            concat!(
+                "SELECT ",
+                    #key_column,
+                    #(", ", #field_column),*,
+                " ",
+                "FROM ", #table,
+            )
+        }
+    };
+    let update = |table: &dyn ToTokens| {
+        quote! {
+            concat!(
+                "UPDATE ", #table, " ",
+                "SET ",
+                    #key_column,
+                    " = ",
+                    ::weru::database::parameter!(#key_i),
                     #(
-                        #field_name: other.#field_name.or(self.#field_name),
-                    )*
+                        ", ",
+                        #field_column,
+                        " = ",
+                        ::weru::database::parameter!(#field_i)
+                    ),*,
+                " ",
+                "WHERE ", #key_column, " = ",
+                    ::weru::database::parameter!(#key_i),
+            )
+        }
+    };
+    let delete = |table: &dyn ToTokens| {
+        quote! {
+            concat!(
+                "DELETE FROM ", #table, " ",
+                "WHERE ", #key_column, " = ",
+                    ::weru::database::parameter!(1),
+            )
+        }
+    };
+
+    // The indexes, named after the table and column; the names are quoted
+    // like the table name
+    let indexes = columns
+        .iter()
+        .filter_map(|c| {
+            let name = &c.name;
+            let index_name = format!("{}_{}_idx", table_name, name);
+            c.index.map(|unique| {
+                let create_index = if unique {
+                    quote! { ::weru::database::create_index!(unique) }
+                } else {
+                    quote! { ::weru::database::create_index!() }
+                };
+                quote! {
+                    concat!(
+                        #create_index,
+                        stringify!(#index_name),
+                        " ON ", stringify!(#table_name),
+                        " (", #name, ")",
+                    )
                 }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    // The constants use the table name of the macro, and the functions a
+    // format placeholder
+    let table_const = quote! { stringify!(#table_name) };
+    let table_fmt = quote! { "{}" };
+    let (create_const, create_fmt) = (create(&table_const), create(&table_fmt));
+    let (read_const, read_fmt) = (read(&table_const), read(&table_fmt));
+    let (read_all_const, read_all_fmt) =
+        (read_all(&table_const), read_all(&table_fmt));
+    let (update_const, update_fmt) = (update(&table_const), update(&table_fmt));
+    let (delete_const, delete_fmt) = (delete(&table_const), delete(&table_fmt));
+
+    // The values bound when inserting and updating
+    let create_bind = quote! {
+        .bind(<#key_ty>::from(self.#key_name.clone()))
+        #(
+            .bind(#field_bind)
+        )*
+    };
+    let update_bind = quote! {
+        .bind(self.#key_name.clone())
+        #(
+            .bind(#field_bind)
+        )*
+        .bind(self.#key_name.clone())
+    };
+
+    // The hooks delegate to EntityHooks if enabled, and otherwise use the
+    // empty default implementations
+    let hook_methods = if *hooks {
+        quote! {
+            async fn on_created(&self) {
+                ::weru::database::EntityHooks::on_created(self).await
             }
 
-            /// Attempts to convert this description to an entity.
-            ///
-            /// Unless all fields are set, this method will return `None`.
-            ///
-            /// # Arguments
-            /// *  `key` - The key value to use.
-            pub fn entity(self, key: #key_ty) -> Option<#self_ty> {
-                Some(#self_ty {
-                    #key_name: key,
-                    #(
-                        #field_name: self.#field_name?,
-                    )*
-                })
+            async fn on_updated(&self) {
+                ::weru::database::EntityHooks::on_updated(self).await
+            }
+
+            async fn on_deleted(&self) {
+                ::weru::database::EntityHooks::on_deleted(self).await
             }
         }
+    } else {
+        quote! {}
+    };
+
+    // Read-only entities may opt out of the description, in which case the
+    // unit type is used in its place, and partial updates do nothing
+    let (description_items, description_ty, update_partial_body, merge_method) =
+        if *description {
+            (
+                quote! {
+                    /// A description of an entity.
+                    ///
+                    /// This struct contains all fields of the entity except the
+                    /// key.
+                    #[derive(
+                        Clone,
+                        Debug,
+                        Default,
+                        PartialEq,
+                        ::serde::Deserialize,
+                        ::serde::Serialize,
+                    )]
+                    pub struct #description_name {
+                        #(
+                            pub #field_name: Option<#field_ty>,
+                        )*
+                    }
+
+                    #[allow(unused)]
+                    impl #description_name {
+                        /// Merges this description with another.
+                        ///
+                        /// All items set in `other` will be copied to a new
+                        /// item.
+                        ///
+                        /// # Arguments
+                        /// *  `other` - Another description.
+                        pub fn merge(self, other: Self) -> Self {
+                            Self {
+                                #(
+                                    #field_name: other
+                                        .#field_name
+                                        .or(self.#field_name),
+                                )*
+                            }
+                        }
+
+                        /// Attempts to convert this description to an entity.
+                        ///
+                        /// Unless all fields are set, this method will return
+                        /// `None`.
+                        ///
+                        /// # Arguments
+                        /// *  `key` - The key value to use.
+                        pub fn entity(self, key: #key_ty) -> Option<#self_ty> {
+                            Some(#self_ty {
+                                #key_name: key,
+                                #(
+                                    #field_name: self.#field_name?,
+                                )*
+                            })
+                        }
+                    }
+                },
+                quote! { #description_name },
+                quote! {
+                    let mut builder = ::weru::database::sqlx::QueryBuilder::<
+                        ::weru::database::Database
+                    >::new(concat!(
+                        "UPDATE ", stringify!(#table_name), " SET ",
+                    ));
+                    let mut columns = builder.separated(", ");
+                    let mut empty = true;
+                    #(
+                        if let Some(value) = &description.#field_name {
+                            columns.push(concat!(#field_column, " = "));
+                            columns.push_bind_unseparated(#value_bind);
+                            empty = false;
+                        }
+                    )*
+                    if empty {
+                        return Ok(());
+                    }
+
+                    builder.push(concat!(" WHERE ", #key_column, " = "));
+                    builder.push_bind(key);
+                    let count = builder
+                        .build()
+                        .execute(e)
+                        .await?
+                        .rows_affected();
+                    if count != 1 {
+                        Err(::weru::database::Error::RowNotFound)
+                    } else {
+                        Ok(())
+                    }
+                },
+                quote! {
+                    fn merge(mut self, description: Self::Description) -> Self {
+                        #(
+                            if let Some(#field_name) = description.#field_name {
+                                self.#field_name = #field_name;
+                            }
+                        )*
+                        self
+                    }
+                },
+            )
+        } else {
+            (
+                quote! {},
+                quote! { () },
+                quote! { Ok(()) },
+                quote! {
+                    fn merge(self, _description: ()) -> Self {
+                        self
+                    }
+                },
+            )
+        };
+
+    quote! {
+        #description_items
 
         #[allow(unused)]
         impl #name {
@@ -140,9 +789,9 @@ pub fn entity(attr: TokenStream, item: TokenStream) -> TokenStream {
             {
                 use ::weru::database::sqlx::Row;
                 Ok(Self {
-                    #key_name: row.try_get(&stringify!(#key_name))?,
+                    #key_name: row.try_get(&#key_column)?,
                     #(
-                        #field_name: row.try_get(&stringify!(#field_name))?,
+                        #field_name: #field_get,
                     )*
                 })
             }
@@ -152,48 +801,50 @@ pub fn entity(attr: TokenStream, item: TokenStream) -> TokenStream {
         #[::weru::async_trait::async_trait]
         impl ::weru::database::Entity for #self_ty {
             type Key = #key_ty;
-            type Description = #description_name;
+            type Description = #description_ty;
 
-            const CREATE: &'static str = concat!(
-                "INSERT INTO ", stringify!(#table_name), " (",
-                    stringify!(#key_name),
-                    #(", ", stringify!(#field_name)),*,
-                ") ",
-                "VALUES (",
-                    ::weru::database::parameter!(#key_i),
-                    #(", ", ::weru::database::parameter!(#field_i)),*,
-                ")",
-            );
-            const READ: &'static str = concat!(
-                "SELECT ",
-                    stringify!(#key_name),
-                    #(", ", stringify!(#field_name)),*,
-                " ",
-                "FROM ", stringify!(#table_name), " ",
-                "WHERE ", stringify!(#key_name), " = ",
-                    ::weru::database::parameter!(#key_i),
-            );
-            const UPDATE: &'static str = concat!(
-                "UPDATE ", stringify!(#table_name), " ",
-                "SET ",
-                    stringify!(#key_name),
-                    " = ",
-                    ::weru::database::parameter!(#key_i),
-                    #(
-                        ", ",
-                        stringify!(#field_name),
-                        " = ",
-                        ::weru::database::parameter!(#field_i)
-                    ),*,
-                " ",
-                "WHERE ", stringify!(#key_name), " = ",
-                    ::weru::database::parameter!(#key_i),
-            );
-            const DELETE: &'static str = concat!(
-                "DELETE FROM ", stringify!(#table_name), " ",
-                "WHERE ", stringify!(#key_name), " = ",
-                    ::weru::database::parameter!(1),
-            );
+            const CREATE: &'static str = #create_const;
+            const READ: &'static str = #read_const;
+            const READ_ALL: &'static str = #read_all_const;
+            const UPDATE: &'static str = #update_const;
+            const DELETE: &'static str = #delete_const;
+
+            const INDEXES: &'static [&'static str] = &[#(#indexes),*];
+
+            fn create_sql(table: &str) -> String {
+                format!(
+                    #create_fmt,
+                    ::weru::database::quote_identifier(table),
+                )
+            }
+
+            fn read_sql(table: &str) -> String {
+                format!(
+                    #read_fmt,
+                    ::weru::database::quote_identifier(table),
+                )
+            }
+
+            fn read_all_sql(table: &str) -> String {
+                format!(
+                    #read_all_fmt,
+                    ::weru::database::quote_identifier(table),
+                )
+            }
+
+            fn update_sql(table: &str) -> String {
+                format!(
+                    #update_fmt,
+                    ::weru::database::quote_identifier(table),
+                )
+            }
+
+            fn delete_sql(table: &str) -> String {
+                format!(
+                    #delete_fmt,
+                    ::weru::database::quote_identifier(table),
+                )
+            }
 
             /// Inserts this item to the database.
             ///
@@ -210,20 +861,100 @@ pub fn entity(attr: TokenStream, item: TokenStream) -> TokenStream {
                 ,
             {
                 let count = ::weru::database::sqlx::query(Self::CREATE)
-                    .bind(<#key_ty>::from(self.#key_name.clone()))
-                    #(
-                        .bind(<#field_ty>::from(self.#field_name.clone()))
-                    )*
+                    #create_bind
                     .execute(e)
                     .await?
                     .rows_affected();
                 if count != 1 {
                     Err(::weru::database::Error::RowNotFound)
                 } else {
+                    ::weru::database::Entity::on_created(self).await;
                     Ok(())
                 }
             }
 
+            /// Inserts this item to a specific table.
+            ///
+            /// # Arguments
+            /// *  `e` - The database executor.
+            /// *  `table` - The name of the table.
+            async fn create_in<'a, E>(
+                &self,
+                e: E,
+                table: &str,
+            ) -> Result<(), ::weru::database::Error>
+            where
+                E: ::weru::database::sqlx::Executor<
+                    'a,
+                    Database = ::weru::database::Database>
+                ,
+            {
+                let sql = Self::create_sql(table);
+                let count = ::weru::database::sqlx::query(&sql)
+                    #create_bind
+                    .execute(e)
+                    .await?
+                    .rows_affected();
+                if count != 1 {
+                    Err(::weru::database::Error::RowNotFound)
+                } else {
+                    ::weru::database::Entity::on_created(self).await;
+                    Ok(())
+                }
+            }
+
+            /// Inserts several items to the database.
+            ///
+            /// # Arguments
+            /// *  `e` - The connection, pool or transaction to use.
+            /// *  `items` - The items to insert.
+            async fn create_many<'a, E>(
+                e: E,
+                items: &[Self],
+            ) -> Result<u64, ::weru::database::Error>
+            where
+                Self: Sync,
+                E: ::weru::database::sqlx::Acquire<
+                    'a,
+                    Database = ::weru::database::Database
+                > + Send,
+            {
+                if items.is_empty() {
+                    return Ok(0);
+                }
+
+                let rows = ::weru::database::engine::MAX_PARAMETERS
+                    / #column_count;
+                let mut tx = e.begin().await?;
+                let mut count = 0;
+                for chunk in items.chunks(rows) {
+                    let mut builder = ::weru::database::sqlx::QueryBuilder::<
+                        ::weru::database::Database
+                    >::new(concat!(
+                        "INSERT INTO ", stringify!(#table_name), " (",
+                            #key_column,
+                            #(", ", #field_column),*,
+                        ") ",
+                    ));
+                    builder.push_values(chunk, |mut row, item| {
+                        row.push_bind(<#key_ty>::from(item.#key_name.clone()))
+                            #(
+                                .push_bind(#item_field_bind)
+                            )*;
+                    });
+                    count += builder
+                        .build()
+                        .execute(&mut *tx)
+                        .await?
+                        .rows_affected();
+                }
+                tx.commit().await?;
+                for item in items {
+                    ::weru::database::Entity::on_created(item).await;
+                }
+                Ok(count)
+            }
+
             /// Loads an item of this kind from the database.
             ///
             /// If no item corresponding to the keys exists, `Ok(None)` is
@@ -247,6 +978,115 @@ pub fn entity(attr: TokenStream, item: TokenStream) -> TokenStream {
                     .await
             }
 
+            /// Loads an item of this kind and locks it until the end of a
+            /// transaction.
+            ///
+            /// # Arguments
+            /// *  `tx` - The transaction holding the lock.
+            /// *  `key` - The key of the item.
+            async fn read_for_update<'a>(
+                tx: &mut ::weru::database::Transaction<'a>,
+                key: &#key_ty,
+            ) -> Result<Option<Self>, ::weru::database::Error> {
+                ::weru::database::sqlx::query_as(concat!(
+                    #read_const,
+                    ::weru::database::for_update!(),
+                ))
+                .bind(key)
+                .fetch_optional(&mut **tx)
+                .await
+            }
+
+            /// Loads several items of this kind from the database.
+            ///
+            /// # Arguments
+            /// *  `e` - The connection, pool or transaction to use.
+            /// *  `keys` - The keys of the items to load.
+            async fn read_many<'a, E>(
+                e: E,
+                keys: &[#key_ty],
+            ) -> Result<Vec<Self>, ::weru::database::Error>
+            where
+                Self: Send,
+                E: ::weru::database::sqlx::Acquire<
+                    'a,
+                    Database = ::weru::database::Database
+                > + Send,
+            {
+                if keys.is_empty() {
+                    return Ok(Vec::new());
+                }
+
+                let mut connection = e.acquire().await?;
+                let mut result = Vec::with_capacity(keys.len());
+                for chunk in keys.chunks(
+                    ::weru::database::engine::MAX_PARAMETERS,
+                ) {
+                    let mut builder = ::weru::database::sqlx::QueryBuilder::<
+                        ::weru::database::Database
+                    >::new(concat!(
+                        #read_all_const, " ",
+                        "WHERE ", #key_column, " IN (",
+                    ));
+                    let mut separated = builder.separated(", ");
+                    for key in chunk {
+                        separated.push_bind(key);
+                    }
+                    separated.push_unseparated(")");
+                    result.extend(
+                        builder
+                            .build_query_as::<Self>()
+                            .fetch_all(&mut *connection)
+                            .await?,
+                    );
+                }
+                Ok(result)
+            }
+
+            /// Lists a page of items of this kind, ordered by key.
+            ///
+            /// # Arguments
+            /// *  `e` - The database executor.
+            /// *  `after` - The key after which to start listing.
+            /// *  `limit` - The maximum number of items to list.
+            async fn list_after<'a, E>(
+                e: E,
+                after: Option<#key_ty>,
+                limit: i64,
+            ) -> Result<Vec<Self>, ::weru::database::Error>
+            where
+                E: ::weru::database::sqlx::Executor<
+                    'a,
+                    Database = ::weru::database::Database
+                >,
+            {
+                match after {
+                    Some(after) => {
+                        ::weru::database::sqlx::query_as(concat!(
+                            #read_all_const, " ",
+                            "WHERE ", #key_column, " > ",
+                                ::weru::database::parameter!(1), " ",
+                            "ORDER BY ", #key_column, " ",
+                            "LIMIT ", ::weru::database::parameter!(2),
+                        ))
+                            .bind(after)
+                            .bind(limit)
+                            .fetch_all(e)
+                            .await
+                    }
+                    None => {
+                        ::weru::database::sqlx::query_as(concat!(
+                            #read_all_const, " ",
+                            "ORDER BY ", #key_column, " ",
+                            "LIMIT ", ::weru::database::parameter!(1),
+                        ))
+                            .bind(limit)
+                            .fetch_all(e)
+                            .await
+                    }
+                }
+            }
+
             /// Updates this item in the database.
             ///
             /// # Arguments
@@ -262,17 +1102,65 @@ pub fn entity(attr: TokenStream, item: TokenStream) -> TokenStream {
                 >,
             {
                 let count = ::weru::database::sqlx::query(Self::UPDATE)
-                    .bind(self.#key_name.clone())
-                    #(
-                        .bind(self.#field_name.clone())
-                    )*
-                    .bind(self.#key_name.clone())
+                    #update_bind
+                    .execute(e)
+                    .await?
+                    .rows_affected();
+                if count != 1 {
+                    Err(::weru::database::Error::RowNotFound)
+                } else {
+                    ::weru::database::Entity::on_updated(self).await;
+                    Ok(())
+                }
+            }
+
+            /// Updates only the columns of an item set in a description.
+            ///
+            /// # Arguments
+            /// *  `e` - The database executor.
+            /// *  `key` - The key of the item to update.
+            /// *  `description` - The values to set.
+            async fn update_partial<'a, E>(
+                e: E,
+                key: &#key_ty,
+                description: &#description_ty,
+            ) -> Result<(), ::weru::database::Error>
+            where
+                #description_ty: Sync,
+                E: ::weru::database::sqlx::Executor<
+                    'a,
+                    Database = ::weru::database::Database
+                >,
+            {
+                #update_partial_body
+            }
+
+            /// Updates this item in a specific table.
+            ///
+            /// # Arguments
+            /// *  `e` - The database executor.
+            /// *  `table` - The name of the table.
+            async fn update_in<'a, E>(
+                &self,
+                e: E,
+                table: &str,
+            ) -> Result<(), ::weru::database::Error>
+            where
+                E: ::weru::database::sqlx::Executor<
+                    'a,
+                    Database = ::weru::database::Database
+                >,
+            {
+                let sql = Self::update_sql(table);
+                let count = ::weru::database::sqlx::query(&sql)
+                    #update_bind
                     .execute(e)
                     .await?
                     .rows_affected();
                 if count != 1 {
                     Err(::weru::database::Error::RowNotFound)
                 } else {
+                    ::weru::database::Entity::on_updated(self).await;
                     Ok(())
                 }
             }
@@ -282,14 +1170,9 @@ pub fn entity(attr: TokenStream, item: TokenStream) -> TokenStream {
                 &self.#key_name
             }
 
-            fn merge(mut self, description: Self::Description) -> Self {
-                #(
-                    if let Some(#field_name) = description.#field_name {
-                        self.#field_name = #field_name;
-                    }
-                )*
-                self
-            }
+            #hook_methods
+
+            #merge_method
         }
     }
     .into()
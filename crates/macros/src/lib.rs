@@ -6,3 +6,8 @@ mod database;
 pub fn database_entity(attr: TokenStream, item: TokenStream) -> TokenStream {
     self::database::entity(attr, item)
 }
+
+#[proc_macro_derive(Entity, attributes(entity))]
+pub fn database_entity_derive(item: TokenStream) -> TokenStream {
+    self::database::derive_entity(item)
+}
@@ -19,6 +19,14 @@ pub enum Configuration {
     #[cfg(feature = "cookie")]
     Cookie(crate::store::cookie::Configuration),
 
+    /// A session backed by a database table.
+    #[cfg(feature = "database")]
+    Database(crate::store::database::Configuration),
+
+    /// A session kept in process memory.
+    #[cfg(feature = "memory")]
+    Memory(crate::store::memory::Configuration),
+
     /// A session backed by Redis.
     #[cfg(feature = "redis")]
     Redis(crate::store::redis::Configuration),
@@ -29,3 +37,10 @@ impl From<anyhow::Error> for Error {
         Self(source.to_string())
     }
 }
+
+#[cfg(feature = "database")]
+impl From<weru_database::configuration::Error> for Error {
+    fn from(source: weru_database::configuration::Error) -> Self {
+        Self(source.to_string())
+    }
+}
@@ -0,0 +1,429 @@
+//! # The session middleware
+//!
+//! The [session middleware](SessionMiddleware) wraps the middleware provided
+//! by _actix-session_ and adds support for rotating secrets and partitioned
+//! cookies.
+//!
+//! ## Compatibility
+//!
+//! [`SessionMiddleware`] used to be a type alias for
+//! `actix_session::SessionMiddleware<Store>`. It is now a distinct type, so
+//! code that created it through `SessionMiddleware::builder` or passed it
+//! where the _actix-session_ type is expected no longer compiles; create it
+//! with [`Store::middleware`] instead.
+
+use std::future::{ready, Future, Ready};
+use std::pin::Pin;
+use std::rc::Rc;
+
+use actix_session::SessionMiddleware as ActixSessionMiddleware;
+use actix_web::body::MessageBody;
+use actix_web::cookie::{Cookie, CookieJar, Key};
+use actix_web::dev::{
+    forward_ready, Service, ServiceRequest, ServiceResponse, Transform,
+};
+use actix_web::http::header::{HeaderMap, HeaderValue, COOKIE, SET_COOKIE};
+
+use crate::Store;
+
+/// The session middleware exposed by this crate.
+///
+/// Session cookies protected by a previous secret are transparently
+/// protected by the current secret before being passed on to the actual
+/// session middleware.
+///
+/// If enabled, the _Partitioned_ attribute is added to session cookies set
+/// by the actual session middleware.
+///
+/// This is not the _actix-session_ middleware type; use
+/// [`Store::middleware`] to create it.
+pub struct SessionMiddleware {
+    /// The wrapped middleware.
+    inner: ActixSessionMiddleware<Store>,
+
+    /// The secrets used to protect cookies.
+    rotation: Rc<Rotation>,
+
+    /// Whether to set the _Partitioned_ attribute on session cookies.
+    partitioned: bool,
+}
+
+impl SessionMiddleware {
+    /// Wraps a session middleware.
+    ///
+    /// # Arguments
+    /// *  `inner` - The wrapped middleware.
+    /// *  `name` - The name of the session cookie.
+    /// *  `key` - The key currently used to protect cookies.
+    /// *  `previous` - Keys previously used to protect cookies.
+    /// *  `partitioned` - Whether to set the _Partitioned_ attribute on
+    ///    session cookies.
+    pub fn new(
+        inner: ActixSessionMiddleware<Store>,
+        name: String,
+        key: Key,
+        previous: Vec<Key>,
+        partitioned: bool,
+    ) -> Self {
+        Self {
+            inner,
+            rotation: Rc::new(Rotation {
+                name,
+                key,
+                previous,
+            }),
+            partitioned,
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for SessionMiddleware
+where
+    S: Service<
+            ServiceRequest,
+            Response = ServiceResponse<B>,
+            Error = actix_web::Error,
+        > + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Transform = InnerSessionMiddleware<
+        <ActixSessionMiddleware<Store> as Transform<S, ServiceRequest>>::Transform,
+    >;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(
+            self.inner
+                .new_transform(service)
+                .into_inner()
+                .map(|service| InnerSessionMiddleware {
+                    service,
+                    rotation: Rc::clone(&self.rotation),
+                    partitioned: self.partitioned,
+                }),
+        )
+    }
+}
+
+#[doc(hidden)]
+pub struct InnerSessionMiddleware<S> {
+    /// The wrapped service.
+    service: S,
+
+    /// The secrets used to protect cookies.
+    rotation: Rc<Rotation>,
+
+    /// Whether to set the _Partitioned_ attribute on session cookies.
+    partitioned: bool,
+}
+
+impl<S, B> Service<ServiceRequest> for InnerSessionMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>>,
+    S::Future: 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future =
+        Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        self.rotation.apply(&mut req);
+        let response = self.service.call(req);
+        if self.partitioned {
+            let rotation = Rc::clone(&self.rotation);
+            Box::pin(async move {
+                let mut res = response.await?;
+                partition(res.headers_mut(), &rotation.name);
+                Ok(res)
+            })
+        } else {
+            Box::pin(response)
+        }
+    }
+}
+
+/// Adds the _Partitioned_ attribute to session cookies in a response.
+///
+/// # Arguments
+/// *  `headers` - The response headers.
+/// *  `name` - The name of the session cookie.
+fn partition(headers: &mut HeaderMap, name: &str) {
+    let prefix = format!("{}=", name);
+    let values = headers
+        .get_all(SET_COOKIE)
+        .map(|value| match value.to_str() {
+            Ok(s) if s.starts_with(&prefix) => {
+                HeaderValue::from_str(&format!("{}; Partitioned", s))
+                    .unwrap_or_else(|_| value.clone())
+            }
+            _ => value.clone(),
+        })
+        .collect::<Vec<_>>();
+    headers.remove(SET_COOKIE);
+    values
+        .into_iter()
+        .for_each(|value| headers.append(SET_COOKIE, value));
+}
+
+/// The secrets used to protect session cookies.
+struct Rotation {
+    /// The name of the session cookie.
+    name: String,
+
+    /// The key currently used to protect cookies.
+    key: Key,
+
+    /// Keys previously used to protect cookies.
+    previous: Vec<Key>,
+}
+
+impl Rotation {
+    /// Replaces a session cookie protected by a previous key with one
+    /// protected by the current key.
+    ///
+    /// If no previous keys are configured, this is a no-op.
+    ///
+    /// # Arguments
+    /// *  `req` - The request to modify.
+    fn apply(&self, req: &mut ServiceRequest) {
+        if self.previous.is_empty() {
+            return;
+        }
+
+        let headers = req.headers_mut();
+        let mut rotated = false;
+        let cookies = headers
+            .get_all(COOKIE)
+            .map(|value| value.to_str().ok())
+            .collect::<Option<Vec<_>>>()
+            .map(|values| {
+                values
+                    .into_iter()
+                    .flat_map(|value| value.split(';'))
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .map(|s| {
+                        if let Some(cookie) = self.rotate(s) {
+                            rotated = true;
+                            cookie
+                        } else {
+                            s.to_string()
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            });
+
+        if rotated {
+            if let Some(value) =
+                cookies.and_then(|c| HeaderValue::from_str(&c.join("; ")).ok())
+            {
+                headers.insert(COOKIE, value);
+            }
+        }
+    }
+
+    /// Re-protects a single cookie.
+    ///
+    /// If the cookie is not the session cookie, if it is already protected
+    /// by the current key or if it is not protected by any previous key,
+    /// `None` is returned.
+    ///
+    /// # Arguments
+    /// *  `s` - The cookie string.
+    fn rotate(&self, s: &str) -> Option<String> {
+        let cookie = Cookie::parse_encoded(s).ok()?.into_owned();
+        if cookie.name() != self.name {
+            return None;
+        }
+
+        let jar = CookieJar::new();
+        if jar.private(&self.key).decrypt(cookie.clone()).is_some() {
+            return None;
+        }
+        let plain = self
+            .previous
+            .iter()
+            .find_map(|key| jar.private(key).decrypt(cookie.clone()))?;
+
+        let mut jar = CookieJar::new();
+        jar.private_mut(&self.key).add(plain);
+        jar.get(&self.name)
+            .map(|cookie| cookie.encoded().stripped().to_string())
+    }
+}
+
+#[cfg(all(test, feature = "cookie"))]
+mod tests {
+    use super::*;
+
+    use actix_session::SessionExt;
+    use actix_web::{test, web, App, HttpRequest, HttpResponse};
+
+    use crate::store::{cookie, SameSite, Secret};
+    use crate::Configuration;
+
+    const OLD: &str = "\
+        e464c40145589aad0a25331fdc835dbd1a442e53b1604c1bf1665671a478324d\
+        f42ca55f76ef4f5c5e25e6ca18438566ca6fff5cefcc83a0042157df9dee4521";
+    const NEW: &str = "\
+        AC8D4F2C95517D26DE4805113A44077B3321E7A9F212020E2074190BAE1506AB\
+        1D149588144B5C33F3EE5A1630C5E1A2A027F7DFEB90FF877A7A2D00EF7D3DEF";
+
+    #[actix_rt::test]
+    async fn previous_secret_accepted() {
+        // Arrange
+        let cookie = login(configuration(OLD, &[])).await;
+
+        // Act
+        let actual = read(configuration(NEW, &[OLD]), cookie).await;
+
+        // Assert
+        assert_eq!("user", actual);
+    }
+
+    #[actix_rt::test]
+    async fn unknown_secret_rejected() {
+        // Arrange
+        let cookie = login(configuration(OLD, &[])).await;
+
+        // Act
+        let actual = read(configuration(NEW, &[]), cookie).await;
+
+        // Assert
+        assert_eq!("", actual);
+    }
+
+    #[actix_rt::test]
+    async fn partitioned_cookie_secure() {
+        // Arrange
+        let configuration = Configuration::Cookie(cookie::Configuration {
+            secure: Some(false),
+            same_site: Some(SameSite::None),
+            partitioned: true,
+            ..cookie_configuration(OLD, &[])
+        });
+
+        // Act
+        let actual = login_header(configuration).await;
+
+        // Assert
+        assert!(actual.starts_with("session="));
+        assert!(actual.contains("; Secure"));
+        assert!(actual.contains("; SameSite=None"));
+        assert!(actual.ends_with("; Partitioned"));
+    }
+
+    #[actix_rt::test]
+    async fn unpartitioned_cookie() {
+        // Act
+        let actual = login_header(configuration(OLD, &[])).await;
+
+        // Assert
+        assert!(!actual.contains("Partitioned"));
+    }
+
+    /// Creates a cookie session configuration.
+    ///
+    /// # Arguments
+    /// *  `secret` - The current secret.
+    /// *  `previous` - Secrets previously used.
+    fn configuration(secret: &str, previous: &[&str]) -> Configuration {
+        Configuration::Cookie(cookie_configuration(secret, previous))
+    }
+
+    /// Creates the configuration for a cookie store.
+    ///
+    /// # Arguments
+    /// *  `secret` - The current secret.
+    /// *  `previous` - Secrets previously used.
+    fn cookie_configuration(
+        secret: &str,
+        previous: &[&str],
+    ) -> cookie::Configuration {
+        cookie::Configuration {
+            secret: secret.parse().unwrap(),
+            previous_secrets: previous
+                .iter()
+                .map(|s| s.parse::<Secret<64>>().unwrap())
+                .collect(),
+            encoding: Default::default(),
+            name: "session".into(),
+            secure: None,
+            same_site: None,
+            partitioned: false,
+            ttl: 60,
+            expiration: Default::default(),
+        }
+    }
+
+    /// Stores a value in a new session, and returns the session cookie.
+    ///
+    /// # Arguments
+    /// *  `configuration` - The session configuration.
+    async fn login(configuration: Configuration) -> Cookie<'static> {
+        Cookie::parse_encoded(login_header(configuration).await)
+            .unwrap()
+            .into_owned()
+    }
+
+    /// Stores a value in a new session, and returns the _Set-Cookie_ header
+    /// of the response.
+    ///
+    /// # Arguments
+    /// *  `configuration` - The session configuration.
+    async fn login_header(configuration: Configuration) -> String {
+        let store = configuration.store().await.unwrap();
+        let app = test::init_service(
+            App::new().wrap(store.middleware(&configuration)).route(
+                "/",
+                web::get().to(|req: HttpRequest| async move {
+                    req.get_session().insert("user", "user").unwrap();
+                    HttpResponse::Ok().finish()
+                }),
+            ),
+        )
+        .await;
+        let res =
+            test::call_service(&app, test::TestRequest::get().to_request())
+                .await;
+        res.headers()
+            .get(SET_COOKIE)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
+    /// Reads the value stored in a session.
+    ///
+    /// # Arguments
+    /// *  `configuration` - The session configuration.
+    /// *  `cookie` - The session cookie.
+    async fn read(configuration: Configuration, cookie: Cookie<'_>) -> String {
+        let store = configuration.store().await.unwrap();
+        let app = test::init_service(
+            App::new().wrap(store.middleware(&configuration)).route(
+                "/",
+                web::get().to(|req: HttpRequest| async move {
+                    req.get_session()
+                        .get::<String>("user")
+                        .unwrap()
+                        .unwrap_or_default()
+                }),
+            ),
+        )
+        .await;
+        let req = test::TestRequest::get()
+            .cookie(cookie.into_owned())
+            .to_request();
+        String::from_utf8(test::call_and_read_body(&app, req).await.to_vec())
+            .unwrap()
+    }
+}
@@ -1,32 +1,239 @@
-use serde::{Deserialize, Serialize};
-
-pub use actix_session::storage::CookieSessionStore as Store;
+use actix_session::storage::{
+    CookieSessionStore, LoadError, SaveError, SessionKey, SessionStore,
+    UpdateError,
+};
+use actix_web::cookie::time::Duration;
+use serde::{Deserialize, Serialize, Serializer};
 
+use super::SessionState;
 use crate::configuration;
 
 /// The size, in bytes, of a key used to protect sessions.
 const SESSION_KEY_SIZE: usize = 32 + 32;
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize)]
 pub struct Configuration {
     /// The secret used to protect cookies.
     pub secret: super::Secret<SESSION_KEY_SIZE>,
 
+    /// Secrets previously used to protect cookies.
+    ///
+    /// Cookies protected by any of these are still accepted, but new cookies
+    /// are always protected by `secret`.
+    #[serde(default)]
+    pub previous_secrets: Vec<super::Secret<SESSION_KEY_SIZE>>,
+
+    /// The encoding used when serialising `secret` and `previous_secrets`.
+    ///
+    /// Secrets in either encoding are accepted when deserialising.
+    #[serde(default)]
+    pub encoding: super::SecretEncoding,
+
     /// The name of the cookie
     pub name: String,
+
+    /// Whether to set the _Secure_ attribute on the cookie.
+    ///
+    /// If not specified, the attribute is set.
+    #[serde(default)]
+    pub secure: Option<bool>,
+
+    /// The value of the _SameSite_ attribute of the cookie.
+    ///
+    /// If not specified, the default of the session middleware is used.
+    #[serde(default)]
+    pub same_site: Option<super::SameSite>,
+
+    /// Whether to set the _Partitioned_ attribute on the cookie.
+    ///
+    /// This allows the cookie to be used in embedded third-party contexts.
+    /// If _SameSite_ is `none`, this also forces the _Secure_ attribute.
+    #[serde(default)]
+    pub partitioned: bool,
+
+    /// The time-to-live for sessions, in seconds.
+    #[serde(default = "super::default_ttl")]
+    pub ttl: u32,
+
+    /// How activity affects the expiration of sessions.
+    #[serde(default)]
+    pub expiration: super::Expiration,
+}
+
+impl Serialize for Configuration {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        /// This configuration, with secrets written with its encoding.
+        #[derive(Serialize)]
+        struct Serialized<'a> {
+            secret: super::Encoded<'a, super::Secret<SESSION_KEY_SIZE>>,
+            previous_secrets:
+                super::Encoded<'a, Vec<super::Secret<SESSION_KEY_SIZE>>>,
+            encoding: super::SecretEncoding,
+            name: &'a str,
+            secure: Option<bool>,
+            same_site: Option<super::SameSite>,
+            partitioned: bool,
+            ttl: u32,
+            expiration: super::Expiration,
+        }
+
+        Serialized {
+            secret: super::Encoded(&self.secret, self.encoding),
+            previous_secrets: super::Encoded(
+                &self.previous_secrets,
+                self.encoding,
+            ),
+            encoding: self.encoding,
+            name: &self.name,
+            secure: self.secure,
+            same_site: self.same_site,
+            partitioned: self.partitioned,
+            ttl: self.ttl,
+            expiration: self.expiration,
+        }
+        .serialize(serializer)
+    }
 }
 
 impl Configuration {
     /// Constructs a session middleware with this configuraed storage.
     pub async fn store(&self) -> Result<Store, configuration::Error> {
-        Ok(Store::default())
+        Ok(Store {
+            configuration: self.clone(),
+        })
+    }
+}
+
+/// A session store keeping session states in the session cookie.
+///
+/// The states are stored by [`CookieSessionStore`], which is stateless; this
+/// store carries the configuration it was created from.
+#[derive(Clone, Debug)]
+pub struct Store {
+    /// The configuration of this store.
+    configuration: Configuration,
+}
+
+impl Store {
+    /// The configuration of this store.
+    pub fn configuration(&self) -> &Configuration {
+        &self.configuration
+    }
+}
+
+impl SessionStore for Store {
+    async fn load(
+        &self,
+        session_key: &SessionKey,
+    ) -> Result<Option<SessionState>, LoadError> {
+        CookieSessionStore::default().load(session_key).await
+    }
+
+    async fn save(
+        &self,
+        session_state: SessionState,
+        ttl: &Duration,
+    ) -> Result<SessionKey, SaveError> {
+        CookieSessionStore::default().save(session_state, ttl).await
+    }
+
+    async fn update(
+        &self,
+        session_key: SessionKey,
+        session_state: SessionState,
+        ttl: &Duration,
+    ) -> Result<SessionKey, UpdateError> {
+        CookieSessionStore::default()
+            .update(session_key, session_state, ttl)
+            .await
+    }
+
+    async fn update_ttl(
+        &self,
+        session_key: &SessionKey,
+        ttl: &Duration,
+    ) -> Result<(), anyhow::Error> {
+        CookieSessionStore::default()
+            .update_ttl(session_key, ttl)
+            .await
+    }
+
+    async fn delete(
+        &self,
+        session_key: &SessionKey,
+    ) -> Result<(), anyhow::Error> {
+        CookieSessionStore::default().delete(session_key).await
     }
 }
 
 /// Creates a clone of a store.
 ///
+/// The clone carries the same configuration as the original.
+///
 /// # Arguments
-/// *  `_store` - The store to clone.
-pub fn clone(_store: &Store) -> Store {
-    Store::default()
+/// *  `store` - The store to clone.
+pub fn clone(store: &Store) -> Store {
+    store.clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine as _;
+
+    use crate::store::SecretEncoding;
+
+    #[actix_rt::test]
+    async fn clone_preserves_configuration() {
+        // Arrange
+        let store = Configuration {
+            secret: super::super::Secret::generate(),
+            previous_secrets: Vec::new(),
+            encoding: Default::default(),
+            name: "session".into(),
+            secure: None,
+            same_site: None,
+            partitioned: false,
+            ttl: super::super::default_ttl(),
+            expiration: Default::default(),
+        }
+        .store()
+        .await
+        .unwrap();
+
+        // Act
+        let cloned = clone(&store);
+
+        // Assert
+        assert_eq!("session", cloned.configuration().name);
+        assert_eq!(store.configuration().secret, cloned.configuration().secret,);
+    }
+
+    #[test]
+    fn configuration_round_trip_base64() {
+        // Arrange
+        let secret = STANDARD.encode([0x5f; SESSION_KEY_SIZE]);
+        let source = serde_json::json!({
+            "secret": secret,
+            "previous_secrets": [secret],
+            "encoding": "base64",
+            "name": "session",
+        });
+
+        // Act
+        let configuration =
+            serde_json::from_value::<Configuration>(source).unwrap();
+        let actual = serde_json::to_value(&configuration).unwrap();
+
+        // Assert
+        assert_eq!(SecretEncoding::Base64, configuration.encoding);
+        assert_eq!(secret, actual["secret"]);
+        assert_eq!(serde_json::json!([secret]), actual["previous_secrets"]);
+        assert_eq!("base64", actual["encoding"]);
+    }
 }
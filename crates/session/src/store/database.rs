@@ -0,0 +1,422 @@
+//! # The database session store
+//!
+//! Sessions are stored in a table with the following columns:
+//!
+//! *  `session_key` - A text column holding the session key. This should be
+//!    the primary key.
+//! *  `state` - A text column holding the JSON encoded session state.
+//! *  `expires_at` - An integer column holding the instant of expiry, in
+//!    seconds since the UNIX epoch.
+//!
+//! The table is not created by the store. With _SQLite_, a suitable table
+//! can be created with the following statement:
+//!
+//! ```sql
+//! CREATE TABLE Sessions (
+//!     session_key TEXT NOT NULL PRIMARY KEY,
+//!     state TEXT NOT NULL,
+//!     expires_at BIGINT NOT NULL
+//! );
+//! ```
+
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use actix_session::storage::{
+    generate_session_key, LoadError, SaveError, SessionKey, SessionStore,
+    UpdateError,
+};
+use actix_web::cookie::time::Duration;
+use serde::{Deserialize, Serialize, Serializer};
+use weru_database::sqlx::{self, Row};
+use weru_database::{parameter, Engine};
+
+use super::SessionState;
+use crate::configuration;
+
+/// The size, in bytes, of a key used to protect sessions.
+const SESSION_KEY_SIZE: usize = 32 + 32;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Configuration {
+    /// The secret used to protect cookies.
+    pub secret: super::Secret<SESSION_KEY_SIZE>,
+
+    /// Secrets previously used to protect cookies.
+    ///
+    /// Cookies protected by any of these are still accepted, but new cookies
+    /// are always protected by `secret`.
+    #[serde(default)]
+    pub previous_secrets: Vec<super::Secret<SESSION_KEY_SIZE>>,
+
+    /// The encoding used when serialising `secret` and `previous_secrets`.
+    ///
+    /// Secrets in either encoding are accepted when deserialising.
+    #[serde(default)]
+    pub encoding: super::SecretEncoding,
+
+    /// The name of the cookie
+    pub name: String,
+
+    /// Whether to set the _Secure_ attribute on the cookie.
+    ///
+    /// If not specified, the attribute is set.
+    #[serde(default)]
+    pub secure: Option<bool>,
+
+    /// The value of the _SameSite_ attribute of the cookie.
+    ///
+    /// If not specified, the default of the session middleware is used.
+    #[serde(default)]
+    pub same_site: Option<super::SameSite>,
+
+    /// Whether to set the _Partitioned_ attribute on the cookie.
+    ///
+    /// This allows the cookie to be used in embedded third-party contexts.
+    /// If _SameSite_ is `none`, this also forces the _Secure_ attribute.
+    #[serde(default)]
+    pub partitioned: bool,
+
+    /// The database connection string.
+    pub connection_string: String,
+
+    /// The name of the table holding sessions.
+    pub table: String,
+
+    /// The time-to-live for sessions, in seconds.
+    #[serde(default = "super::default_ttl")]
+    pub ttl: u32,
+
+    /// How activity affects the expiration of sessions.
+    #[serde(default)]
+    pub expiration: super::Expiration,
+}
+
+impl Serialize for Configuration {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        /// This configuration, with secrets written with its encoding.
+        #[derive(Serialize)]
+        struct Serialized<'a> {
+            secret: super::Encoded<'a, super::Secret<SESSION_KEY_SIZE>>,
+            previous_secrets:
+                super::Encoded<'a, Vec<super::Secret<SESSION_KEY_SIZE>>>,
+            encoding: super::SecretEncoding,
+            name: &'a str,
+            secure: Option<bool>,
+            same_site: Option<super::SameSite>,
+            partitioned: bool,
+            connection_string: &'a str,
+            table: &'a str,
+            ttl: u32,
+            expiration: super::Expiration,
+        }
+
+        Serialized {
+            secret: super::Encoded(&self.secret, self.encoding),
+            previous_secrets: super::Encoded(
+                &self.previous_secrets,
+                self.encoding,
+            ),
+            encoding: self.encoding,
+            name: &self.name,
+            secure: self.secure,
+            same_site: self.same_site,
+            partitioned: self.partitioned,
+            connection_string: &self.connection_string,
+            table: &self.table,
+            ttl: self.ttl,
+            expiration: self.expiration,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl Configuration {
+    /// Constructs a session middleware with this configuraed storage.
+    pub async fn store(&self) -> Result<Store, configuration::Error> {
+        let engine = weru_database::Configuration {
+            connection_string: self.connection_string.clone(),
+            parts: None,
+            slow_threshold: None,
+            acquire_threshold: None,
+        }
+        .engine()
+        .await?;
+        Ok(Store::new(engine, &self.table))
+    }
+
+    /// Verifies this configuration, and returns all problems found.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut problems = Vec::new();
+        if self.connection_string.is_empty() {
+            problems.push("the connection string must not be empty".into());
+        }
+        if self.table.is_empty() {
+            problems.push("the table name must not be empty".into());
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems)
+        }
+    }
+}
+
+/// A session store keeping session states in a database table.
+#[derive(Clone, Debug)]
+pub struct Store {
+    /// The database engine.
+    engine: Arc<Engine>,
+
+    /// The statement used to load a session state.
+    load: Arc<str>,
+
+    /// The statement used to save a new session state.
+    save: Arc<str>,
+
+    /// The statement used to update a session state.
+    update: Arc<str>,
+
+    /// The statement used to update the expiry of a session state.
+    update_ttl: Arc<str>,
+
+    /// The statement used to delete a session state.
+    delete: Arc<str>,
+}
+
+impl Store {
+    /// Creates a new store using a database engine.
+    ///
+    /// # Arguments
+    /// *  `engine` - The database engine.
+    /// *  `table` - The name of the table holding sessions.
+    pub fn new(engine: Engine, table: &str) -> Self {
+        Self {
+            engine: Arc::new(engine),
+            load: format!(
+                "SELECT state FROM {} WHERE session_key = {} \
+                AND expires_at > {}",
+                table,
+                parameter!(1),
+                parameter!(2),
+            )
+            .into(),
+            save: format!(
+                "INSERT INTO {} (session_key, state, expires_at) \
+                VALUES ({}, {}, {})",
+                table,
+                parameter!(1),
+                parameter!(2),
+                parameter!(3),
+            )
+            .into(),
+            update: format!(
+                "UPDATE {} SET state = {}, expires_at = {} \
+                WHERE session_key = {} AND expires_at > {}",
+                table,
+                parameter!(1),
+                parameter!(2),
+                parameter!(3),
+                parameter!(4),
+            )
+            .into(),
+            update_ttl: format!(
+                "UPDATE {} SET expires_at = {} \
+                WHERE session_key = {} AND expires_at > {}",
+                table,
+                parameter!(1),
+                parameter!(2),
+                parameter!(3),
+            )
+            .into(),
+            delete: format!(
+                "DELETE FROM {} WHERE session_key = {}",
+                table,
+                parameter!(1),
+            )
+            .into(),
+        }
+    }
+
+    /// The current time, in seconds since the UNIX epoch.
+    fn now() -> i64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0)
+    }
+
+    /// Calculates the instant of expiry for a time-to-live, in seconds since
+    /// the UNIX epoch.
+    ///
+    /// # Arguments
+    /// *  `ttl` - The time-to-live.
+    fn expiry(ttl: &Duration) -> i64 {
+        Self::now().saturating_add(ttl.whole_seconds())
+    }
+}
+
+impl SessionStore for Store {
+    async fn load(
+        &self,
+        session_key: &SessionKey,
+    ) -> Result<Option<SessionState>, LoadError> {
+        let mut conn = self
+            .engine
+            .connection()
+            .await
+            .map_err(|e| LoadError::Other(e.into()))?;
+        let row = sqlx::query(&self.load)
+            .bind(session_key.as_ref())
+            .bind(Self::now())
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(|e| LoadError::Other(e.into()))?;
+
+        row.map(|row| {
+            let state: String =
+                row.try_get(0).map_err(|e| LoadError::Other(e.into()))?;
+            serde_json::from_str(&state)
+                .map_err(|e| LoadError::Deserialization(e.into()))
+        })
+        .transpose()
+    }
+
+    async fn save(
+        &self,
+        session_state: SessionState,
+        ttl: &Duration,
+    ) -> Result<SessionKey, SaveError> {
+        let state = serde_json::to_string(&session_state)
+            .map_err(|e| SaveError::Serialization(e.into()))?;
+        let mut conn = self
+            .engine
+            .connection()
+            .await
+            .map_err(|e| SaveError::Other(e.into()))?;
+
+        let session_key = generate_session_key();
+        sqlx::query(&self.save)
+            .bind(session_key.as_ref())
+            .bind(state)
+            .bind(Self::expiry(ttl))
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| SaveError::Other(e.into()))?;
+        Ok(session_key)
+    }
+
+    async fn update(
+        &self,
+        session_key: SessionKey,
+        session_state: SessionState,
+        ttl: &Duration,
+    ) -> Result<SessionKey, UpdateError> {
+        let state = serde_json::to_string(&session_state)
+            .map_err(|e| UpdateError::Serialization(e.into()))?;
+        let count = {
+            let mut conn = self
+                .engine
+                .connection()
+                .await
+                .map_err(|e| UpdateError::Other(e.into()))?;
+            sqlx::query(&self.update)
+                .bind(state)
+                .bind(Self::expiry(ttl))
+                .bind(session_key.as_ref())
+                .bind(Self::now())
+                .execute(&mut *conn)
+                .await
+                .map_err(|e| UpdateError::Other(e.into()))?
+                .rows_affected()
+        };
+
+        if count > 0 {
+            Ok(session_key)
+        } else {
+            self.save(session_state, ttl).await.map_err(|e| match e {
+                SaveError::Serialization(e) => UpdateError::Serialization(e),
+                SaveError::Other(e) => UpdateError::Other(e),
+            })
+        }
+    }
+
+    async fn update_ttl(
+        &self,
+        session_key: &SessionKey,
+        ttl: &Duration,
+    ) -> Result<(), anyhow::Error> {
+        let mut conn = self.engine.connection().await?;
+
+        sqlx::query(&self.update_ttl)
+            .bind(Self::expiry(ttl))
+            .bind(session_key.as_ref())
+            .bind(Self::now())
+            .execute(&mut *conn)
+            .await?;
+        Ok(())
+    }
+
+    async fn delete(
+        &self,
+        session_key: &SessionKey,
+    ) -> Result<(), anyhow::Error> {
+        let mut conn = self.engine.connection().await?;
+
+        sqlx::query(&self.delete)
+            .bind(session_key.as_ref())
+            .execute(&mut *conn)
+            .await?;
+        Ok(())
+    }
+}
+
+/// Creates a clone of a store.
+///
+/// The clone shares the connection pool with the original.
+///
+/// # Arguments
+/// *  `store` - The store to clone.
+pub fn clone(store: &Store) -> Store {
+    store.clone()
+}
+
+/// Creates a store backed by an in-memory database.
+#[cfg(test)]
+async fn store() -> Store {
+    use weru_database::sqlx::Executor;
+
+    let engine = weru_database::Configuration {
+        connection_string: "sqlite::memory:".into(),
+        parts: None,
+        slow_threshold: None,
+        acquire_threshold: None,
+    }
+    .engine()
+    .await
+    .unwrap();
+    engine
+        .connection()
+        .await
+        .unwrap()
+        .execute(
+            r#"
+            CREATE TABLE Sessions (
+                session_key TEXT NOT NULL PRIMARY KEY,
+                state TEXT NOT NULL,
+                expires_at BIGINT NOT NULL
+            );
+            "#,
+        )
+        .await
+        .unwrap();
+    Store::new(engine, "Sessions")
+}
+
+#[cfg(test)]
+#[path = "tests.rs"]
+mod tests;
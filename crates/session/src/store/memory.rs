@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Instant;
+
+use actix_session::storage::{
+    generate_session_key, LoadError, SaveError, SessionKey, SessionStore,
+    UpdateError,
+};
+use actix_web::cookie::time::Duration;
+use serde::{Deserialize, Serialize, Serializer};
+
+use super::SessionState;
+use crate::configuration;
+
+/// The size, in bytes, of a key used to protect sessions.
+const SESSION_KEY_SIZE: usize = 32 + 32;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Configuration {
+    /// The secret used to protect cookies.
+    pub secret: super::Secret<SESSION_KEY_SIZE>,
+
+    /// Secrets previously used to protect cookies.
+    ///
+    /// Cookies protected by any of these are still accepted, but new cookies
+    /// are always protected by `secret`.
+    #[serde(default)]
+    pub previous_secrets: Vec<super::Secret<SESSION_KEY_SIZE>>,
+
+    /// The encoding used when serialising `secret` and `previous_secrets`.
+    ///
+    /// Secrets in either encoding are accepted when deserialising.
+    #[serde(default)]
+    pub encoding: super::SecretEncoding,
+
+    /// The name of the cookie
+    pub name: String,
+
+    /// Whether to set the _Secure_ attribute on the cookie.
+    ///
+    /// If not specified, the attribute is set.
+    #[serde(default)]
+    pub secure: Option<bool>,
+
+    /// The value of the _SameSite_ attribute of the cookie.
+    ///
+    /// If not specified, the default of the session middleware is used.
+    #[serde(default)]
+    pub same_site: Option<super::SameSite>,
+
+    /// Whether to set the _Partitioned_ attribute on the cookie.
+    ///
+    /// This allows the cookie to be used in embedded third-party contexts.
+    /// If _SameSite_ is `none`, this also forces the _Secure_ attribute.
+    #[serde(default)]
+    pub partitioned: bool,
+
+    /// The time-to-live for sessions, in seconds.
+    #[serde(default = "super::default_ttl")]
+    pub ttl: u32,
+
+    /// How activity affects the expiration of sessions.
+    #[serde(default)]
+    pub expiration: super::Expiration,
+}
+
+impl Serialize for Configuration {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        /// This configuration, with secrets written with its encoding.
+        #[derive(Serialize)]
+        struct Serialized<'a> {
+            secret: super::Encoded<'a, super::Secret<SESSION_KEY_SIZE>>,
+            previous_secrets:
+                super::Encoded<'a, Vec<super::Secret<SESSION_KEY_SIZE>>>,
+            encoding: super::SecretEncoding,
+            name: &'a str,
+            secure: Option<bool>,
+            same_site: Option<super::SameSite>,
+            partitioned: bool,
+            ttl: u32,
+            expiration: super::Expiration,
+        }
+
+        Serialized {
+            secret: super::Encoded(&self.secret, self.encoding),
+            previous_secrets: super::Encoded(
+                &self.previous_secrets,
+                self.encoding,
+            ),
+            encoding: self.encoding,
+            name: &self.name,
+            secure: self.secure,
+            same_site: self.same_site,
+            partitioned: self.partitioned,
+            ttl: self.ttl,
+            expiration: self.expiration,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl Configuration {
+    /// Constructs a session middleware with this configuraed storage.
+    pub async fn store(&self) -> Result<Store, configuration::Error> {
+        Ok(Store::default())
+    }
+}
+
+/// A session store keeping session states in process memory.
+///
+/// Sessions are shared between all clones of a store, but not between
+/// processes. Expired sessions are not automatically evicted, only removed
+/// upon access or when a new session is saved.
+#[derive(Clone, Debug, Default)]
+pub struct Store {
+    /// The session states, keyed by session key.
+    data: Arc<Mutex<HashMap<String, Data>>>,
+}
+
+impl Store {
+    /// Locks the session states.
+    fn lock(
+        &self,
+    ) -> Result<MutexGuard<'_, HashMap<String, Data>>, anyhow::Error> {
+        self.data.lock().map_err(|e| anyhow::anyhow!(e.to_string()))
+    }
+
+    /// Calculates the instant of expiry for a time-to-live.
+    ///
+    /// A negative time-to-live is treated as zero.
+    ///
+    /// # Arguments
+    /// *  `ttl` - The time-to-live.
+    fn expiry(ttl: &Duration) -> Instant {
+        Instant::now()
+            + ::std::time::Duration::try_from(*ttl).unwrap_or_default()
+    }
+}
+
+impl SessionStore for Store {
+    async fn load(
+        &self,
+        session_key: &SessionKey,
+    ) -> Result<Option<SessionState>, LoadError> {
+        let mut data = self.lock().map_err(LoadError::Other)?;
+        let now = Instant::now();
+
+        match data.get(session_key.as_ref()) {
+            Some(d) if d.live(now) => Ok(Some(d.state.clone())),
+            Some(_) => {
+                data.remove(session_key.as_ref());
+                Ok(None)
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn save(
+        &self,
+        session_state: SessionState,
+        ttl: &Duration,
+    ) -> Result<SessionKey, SaveError> {
+        let mut data = self.lock().map_err(SaveError::Other)?;
+        let now = Instant::now();
+        data.retain(|_, d| d.live(now));
+
+        let session_key = generate_session_key();
+        data.insert(
+            session_key.as_ref().into(),
+            Data::new(session_state, Self::expiry(ttl)),
+        );
+        Ok(session_key)
+    }
+
+    async fn update(
+        &self,
+        session_key: SessionKey,
+        session_state: SessionState,
+        ttl: &Duration,
+    ) -> Result<SessionKey, UpdateError> {
+        let live = {
+            let mut data = self.lock().map_err(UpdateError::Other)?;
+            let now = Instant::now();
+            match data.get_mut(session_key.as_ref()) {
+                Some(d) if d.live(now) => {
+                    *d = Data::new(session_state.clone(), Self::expiry(ttl));
+                    true
+                }
+                _ => false,
+            }
+        };
+
+        if live {
+            Ok(session_key)
+        } else {
+            self.save(session_state, ttl).await.map_err(|e| match e {
+                SaveError::Serialization(e) => UpdateError::Serialization(e),
+                SaveError::Other(e) => UpdateError::Other(e),
+            })
+        }
+    }
+
+    async fn update_ttl(
+        &self,
+        session_key: &SessionKey,
+        ttl: &Duration,
+    ) -> Result<(), anyhow::Error> {
+        let mut data = self.lock()?;
+        let now = Instant::now();
+
+        if let Some(d) = data.get_mut(session_key.as_ref()) {
+            if d.live(now) {
+                d.expiry = Self::expiry(ttl);
+            }
+        }
+        Ok(())
+    }
+
+    async fn delete(
+        &self,
+        session_key: &SessionKey,
+    ) -> Result<(), anyhow::Error> {
+        let mut data = self.lock()?;
+
+        data.remove(session_key.as_ref());
+        Ok(())
+    }
+}
+
+/// Creates a clone of a store.
+///
+/// The clone shares session states with the original.
+///
+/// # Arguments
+/// *  `store` - The store to clone.
+pub fn clone(store: &Store) -> Store {
+    store.clone()
+}
+
+/// A session state with a time-to-live.
+#[derive(Clone, Debug)]
+struct Data {
+    /// The session state.
+    state: SessionState,
+
+    /// The instant of expiry.
+    expiry: Instant,
+}
+
+impl Data {
+    /// Creates a new wrapper.
+    ///
+    /// # Arguments
+    /// *  `state` - The session state to wrap.
+    /// *  `expiry` - The instant of expiry for the state.
+    pub fn new(state: SessionState, expiry: Instant) -> Self {
+        Self { state, expiry }
+    }
+
+    /// Checks the expiry of this wrapper, and returns whether it is still
+    /// live.
+    ///
+    /// # Arguments
+    /// *  `now` - The time to check.
+    pub fn live(&self, now: Instant) -> bool {
+        now < self.expiry
+    }
+}
+
+/// Creates an empty store.
+#[cfg(test)]
+async fn store() -> Store {
+    Store::default()
+}
+
+#[cfg(test)]
+#[allow(clippy::duplicate_mod)]
+#[path = "tests.rs"]
+mod tests;
@@ -4,26 +4,43 @@
 use std::collections::HashMap;
 
 use actix_session::{
-    config::PersistentSession,
+    config::{PersistentSession, TtlExtensionPolicy},
     storage::{LoadError, SaveError, SessionKey, SessionStore, UpdateError},
+    SessionMiddleware as ActixSessionMiddleware,
 };
-use actix_web::cookie::{time::Duration, Key};
-use serde::{Deserialize, Serialize};
+use actix_web::cookie::{time::Duration, Key, SameSite as CookieSameSite};
+use base64::engine::general_purpose::{STANDARD, STANDARD_NO_PAD};
+use base64::Engine as _;
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize, Serializer};
 
 use crate::{configuration, Configuration};
 
 pub mod cookie;
+#[cfg(feature = "database")]
+pub mod database;
+#[cfg(feature = "memory")]
+pub mod memory;
 pub mod redis;
 
-///
+/// The state of a session.
 type SessionState = HashMap<String, String>;
 
-/// An engine that produces channels.
+/// A backing store for sessions.
+#[allow(clippy::large_enum_variant)]
 pub enum Store {
     /// A storage backed by a cookie value.
     #[cfg(feature = "cookie")]
     Cookie(cookie::Store),
 
+    /// A storage backed by a database table.
+    #[cfg(feature = "database")]
+    Database(database::Store),
+
+    /// A storage kept in process memory.
+    #[cfg(feature = "memory")]
+    Memory(memory::Store),
+
     /// A storage backed by Redis.
     #[cfg(feature = "redis")]
     Redis(redis::Store),
@@ -38,11 +55,29 @@ impl Store {
         self,
         configuration: &Configuration,
     ) -> crate::SessionMiddleware {
-        crate::SessionMiddleware::builder(self, configuration.key())
-            .cookie_name(configuration.name())
-            .cookie_secure(true)
-            .session_lifecycle(PersistentSession::default())
-            .build()
+        let builder =
+            ActixSessionMiddleware::builder(self, configuration.key())
+                .cookie_name(configuration.name())
+                .cookie_secure(configuration.secure())
+                .session_lifecycle(
+                    PersistentSession::default()
+                        .session_ttl(configuration.ttl())
+                        .session_ttl_extension_policy(
+                            configuration.expiration().into(),
+                        ),
+                );
+        let inner = if let Some(same_site) = configuration.same_site() {
+            builder.cookie_same_site(same_site.into()).build()
+        } else {
+            builder.build()
+        };
+        crate::SessionMiddleware::new(
+            inner,
+            configuration.name(),
+            configuration.key(),
+            configuration.previous_keys(),
+            configuration.partitioned(),
+        )
     }
 }
 
@@ -52,6 +87,10 @@ impl Clone for Store {
         match self {
             #[cfg(feature = "cookie")]
             Cookie(store) => Cookie(cookie::clone(store)),
+            #[cfg(feature = "database")]
+            Database(store) => Database(database::clone(store)),
+            #[cfg(feature = "memory")]
+            Memory(store) => Memory(memory::clone(store)),
             #[cfg(feature = "redis")]
             Redis(store) => Redis(redis::clone(store)),
         }
@@ -65,32 +104,174 @@ impl Configuration {
         match self {
             #[cfg(feature = "cookie")]
             Cookie(c) => c.store().await.map(Store::Cookie),
+            #[cfg(feature = "database")]
+            Database(c) => c.store().await.map(Store::Database),
+            #[cfg(feature = "memory")]
+            Memory(c) => c.store().await.map(Store::Memory),
             #[cfg(feature = "redis")]
             Redis(c) => c.store().await.map(Store::Redis),
         }
     }
 
+    /// Verifies this configuration, and returns all problems found.
+    ///
+    /// No backing store is contacted. This lets apps report a broken
+    /// session configuration at startup rather than on the first request.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        use Configuration::*;
+        let mut problems = match self {
+            #[cfg(feature = "cookie")]
+            Cookie(_) => Vec::new(),
+            #[cfg(feature = "database")]
+            Database(c) => c.validate().err().unwrap_or_default(),
+            #[cfg(feature = "memory")]
+            Memory(_) => Vec::new(),
+            #[cfg(feature = "redis")]
+            Redis(c) => c.validate().err().unwrap_or_default(),
+        };
+        if self.name().is_empty() {
+            problems.push("the cookie name must not be empty".into());
+        }
+        if self.ttl().is_zero() {
+            problems.push("the session time-to-live must be positive".into());
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems)
+        }
+    }
+
     /// The secret key to use.
     fn key(&self) -> Key {
         use Configuration::*;
         match self {
             #[cfg(feature = "cookie")]
             Cookie(c) => Key::from(&c.secret.key),
+            #[cfg(feature = "database")]
+            Database(c) => Key::from(&c.secret.key),
+            #[cfg(feature = "memory")]
+            Memory(c) => Key::from(&c.secret.key),
             #[cfg(feature = "redis")]
             Redis(c) => Key::from(&c.secret.key),
         }
     }
 
+    /// The secret keys previously used.
+    fn previous_keys(&self) -> Vec<Key> {
+        use Configuration::*;
+        match self {
+            #[cfg(feature = "cookie")]
+            Cookie(c) => &c.previous_secrets,
+            #[cfg(feature = "database")]
+            Database(c) => &c.previous_secrets,
+            #[cfg(feature = "memory")]
+            Memory(c) => &c.previous_secrets,
+            #[cfg(feature = "redis")]
+            Redis(c) => &c.previous_secrets,
+        }
+        .iter()
+        .map(|secret| Key::from(&secret.key))
+        .collect()
+    }
+
     /// The name of the cookie.
     fn name(&self) -> String {
         use Configuration::*;
         match self {
             #[cfg(feature = "cookie")]
             Cookie(c) => c.name.clone(),
+            #[cfg(feature = "database")]
+            Database(c) => c.name.clone(),
+            #[cfg(feature = "memory")]
+            Memory(c) => c.name.clone(),
             #[cfg(feature = "redis")]
             Redis(c) => c.name.clone(),
         }
     }
+
+    /// Whether to set the _Secure_ attribute on the cookie.
+    ///
+    /// Partitioned cookies with _SameSite_ set to `none` are always secure.
+    fn secure(&self) -> bool {
+        use Configuration::*;
+        match self {
+            #[cfg(feature = "cookie")]
+            Cookie(c) => c.secure,
+            #[cfg(feature = "database")]
+            Database(c) => c.secure,
+            #[cfg(feature = "memory")]
+            Memory(c) => c.secure,
+            #[cfg(feature = "redis")]
+            Redis(c) => c.secure,
+        }
+        .unwrap_or(true)
+            || (self.partitioned() && self.same_site() == Some(SameSite::None))
+    }
+
+    /// The time-to-live for sessions.
+    fn ttl(&self) -> Duration {
+        use Configuration::*;
+        Duration::seconds(
+            match self {
+                #[cfg(feature = "cookie")]
+                Cookie(c) => c.ttl,
+                #[cfg(feature = "database")]
+                Database(c) => c.ttl,
+                #[cfg(feature = "memory")]
+                Memory(c) => c.ttl,
+                #[cfg(feature = "redis")]
+                Redis(c) => c.ttl,
+            }
+            .into(),
+        )
+    }
+
+    /// How activity affects the expiration of sessions.
+    fn expiration(&self) -> Expiration {
+        use Configuration::*;
+        match self {
+            #[cfg(feature = "cookie")]
+            Cookie(c) => c.expiration,
+            #[cfg(feature = "database")]
+            Database(c) => c.expiration,
+            #[cfg(feature = "memory")]
+            Memory(c) => c.expiration,
+            #[cfg(feature = "redis")]
+            Redis(c) => c.expiration,
+        }
+    }
+
+    /// The value of the _SameSite_ attribute of the cookie, if configured.
+    fn same_site(&self) -> Option<SameSite> {
+        use Configuration::*;
+        match self {
+            #[cfg(feature = "cookie")]
+            Cookie(c) => c.same_site,
+            #[cfg(feature = "database")]
+            Database(c) => c.same_site,
+            #[cfg(feature = "memory")]
+            Memory(c) => c.same_site,
+            #[cfg(feature = "redis")]
+            Redis(c) => c.same_site,
+        }
+    }
+
+    /// Whether to set the _Partitioned_ attribute on the cookie.
+    fn partitioned(&self) -> bool {
+        use Configuration::*;
+        match self {
+            #[cfg(feature = "cookie")]
+            Cookie(c) => c.partitioned,
+            #[cfg(feature = "database")]
+            Database(c) => c.partitioned,
+            #[cfg(feature = "memory")]
+            Memory(c) => c.partitioned,
+            #[cfg(feature = "redis")]
+            Redis(c) => c.partitioned,
+        }
+    }
 }
 
 impl SessionStore for Store {
@@ -102,6 +283,10 @@ impl SessionStore for Store {
         match self {
             #[cfg(feature = "cookie")]
             Cookie(s) => s.load(session_key).await,
+            #[cfg(feature = "database")]
+            Database(s) => s.load(session_key).await,
+            #[cfg(feature = "memory")]
+            Memory(s) => s.load(session_key).await,
             #[cfg(feature = "redis")]
             Redis(s) => s.load(session_key).await,
         }
@@ -116,6 +301,10 @@ impl SessionStore for Store {
         match self {
             #[cfg(feature = "cookie")]
             Cookie(s) => s.save(session_state, ttl).await,
+            #[cfg(feature = "database")]
+            Database(s) => s.save(session_state, ttl).await,
+            #[cfg(feature = "memory")]
+            Memory(s) => s.save(session_state, ttl).await,
             #[cfg(feature = "redis")]
             Redis(s) => s.save(session_state, ttl).await,
         }
@@ -131,6 +320,10 @@ impl SessionStore for Store {
         match self {
             #[cfg(feature = "cookie")]
             Cookie(s) => s.update(session_key, session_state, ttl).await,
+            #[cfg(feature = "database")]
+            Database(s) => s.update(session_key, session_state, ttl).await,
+            #[cfg(feature = "memory")]
+            Memory(s) => s.update(session_key, session_state, ttl).await,
             #[cfg(feature = "redis")]
             Redis(s) => s.update(session_key, session_state, ttl).await,
         }
@@ -145,6 +338,10 @@ impl SessionStore for Store {
         match self {
             #[cfg(feature = "cookie")]
             Cookie(s) => s.update_ttl(session_key, ttl).await,
+            #[cfg(feature = "database")]
+            Database(s) => s.update_ttl(session_key, ttl).await,
+            #[cfg(feature = "memory")]
+            Memory(s) => s.update_ttl(session_key, ttl).await,
             #[cfg(feature = "redis")]
             Redis(s) => s.update_ttl(session_key, ttl).await,
         }
@@ -158,17 +355,117 @@ impl SessionStore for Store {
         match self {
             #[cfg(feature = "cookie")]
             Cookie(s) => s.delete(session_key).await,
+            #[cfg(feature = "database")]
+            Database(s) => s.delete(session_key).await,
+            #[cfg(feature = "memory")]
+            Memory(s) => s.delete(session_key).await,
             #[cfg(feature = "redis")]
             Redis(s) => s.delete(session_key).await,
         }
     }
 }
 
+/// How activity affects the expiration of a session.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Expiration {
+    /// The time-to-live is only extended when the session state changes.
+    #[default]
+    Fixed,
+
+    /// The time-to-live is extended on every request associated with the
+    /// session.
+    Sliding,
+}
+
+impl From<Expiration> for TtlExtensionPolicy {
+    fn from(source: Expiration) -> Self {
+        match source {
+            Expiration::Fixed => Self::OnStateChanges,
+            Expiration::Sliding => Self::OnEveryRequest,
+        }
+    }
+}
+
+/// The default time-to-live for sessions, in seconds.
+fn default_ttl() -> u32 {
+    24 * 60 * 60
+}
+
+/// The value of the _SameSite_ cookie attribute.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SameSite {
+    /// The cookie is only sent in a first-party context.
+    Strict,
+
+    /// The cookie is also sent when navigating to the site from another site.
+    Lax,
+
+    /// The cookie is sent in all contexts.
+    None,
+}
+
+impl From<SameSite> for CookieSameSite {
+    fn from(source: SameSite) -> Self {
+        match source {
+            SameSite::Strict => Self::Strict,
+            SameSite::Lax => Self::Lax,
+            SameSite::None => Self::None,
+        }
+    }
+}
+
+/// The encoding of a secret represented by a string.
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum SecretEncoding {
+    /// A string of length `SIZE * 2` of hexadecimal characters.
+    #[default]
+    Hex,
+
+    /// A string of standard base64, with or without padding.
+    Base64,
+}
+
+/// Secrets serialised with a specific encoding.
+///
+/// The store configurations serialise their secrets through this, so that
+/// secrets are written with the encoding that was configured.
+struct Encoded<'a, T>(&'a T, SecretEncoding);
+
+impl<const SIZE: usize> Serialize for Encoded<'_, Secret<SIZE>> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.0.encode(self.1))
+    }
+}
+
+impl<const SIZE: usize> Serialize for Encoded<'_, Vec<Secret<SIZE>>> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer
+            .collect_seq(self.0.iter().map(|secret| secret.encode(self.1)))
+    }
+}
+
 /// A key used internally to maintain secrets.
 ///
 /// When represented by a string, this is a string of length `SIZE * 2` of
-/// hexadecimal characters. A byte is represented with the least significant
-/// bits written first, so `0x12u8` will be read and written as `"21"`.
+/// hexadecimal characters. A byte is represented with the most significant
+/// bits written first, so `0x12u8` will be read and written as `"12"`.
+///
+/// A base64 string is also accepted when parsing, but a secret on its own is
+/// always serialised as a hexadecimal string; use [`decode`](Self::decode) to
+/// learn the encoding of a parsed string, and [`encode`](Self::encode) to
+/// write a secret with a specific encoding. The store configurations write
+/// their secrets with their configured `encoding`.
 #[derive(Clone, PartialEq, Deserialize, Serialize)]
 #[serde(into = "String")]
 #[serde(try_from = "String")]
@@ -177,12 +474,137 @@ pub struct Secret<const SIZE: usize> {
     pub key: [u8; SIZE],
 }
 
+impl<const SIZE: usize> From<[u8; SIZE]> for Secret<SIZE> {
+    fn from(source: [u8; SIZE]) -> Self {
+        Self { key: source }
+    }
+}
+
 impl<const SIZE: usize> Secret<SIZE> {
     /// The digits used when serialising and deserialising.
     const DIGITS: [char; 16] = [
         '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', 'A', 'B', 'C', 'D',
         'E', 'F',
     ];
+
+    /// Generates a new random secret.
+    ///
+    /// The key is read from a cryptographically secure random number
+    /// generator. The [`Display`](::std::fmt::Display) representation of the
+    /// returned value can be used in configuration files.
+    pub fn generate() -> Self {
+        let mut key = [0u8; SIZE];
+        OsRng.fill_bytes(&mut key);
+        key.into()
+    }
+
+    /// Attempts to read a secret from a string, and returns it with the
+    /// detected encoding.
+    ///
+    /// Please see [`FromStr`](::std::str::FromStr) for how the encoding is
+    /// detected.
+    ///
+    /// # Arguments
+    /// *  `s` - The string to read.
+    pub fn decode(s: &str) -> Result<(Self, SecretEncoding), String> {
+        Self::from_hex(s)
+            .map(|secret| (secret, SecretEncoding::Hex))
+            .or_else(|e| {
+                Self::from_base64(s)
+                    .map(|secret| (secret, SecretEncoding::Base64))
+                    .map_err(|_| e)
+            })
+    }
+
+    /// Writes this secret as a string with a specific encoding.
+    ///
+    /// # Arguments
+    /// *  `encoding` - The encoding to use.
+    pub fn encode(&self, encoding: SecretEncoding) -> String {
+        match encoding {
+            SecretEncoding::Hex => self.to_string(),
+            SecretEncoding::Base64 => STANDARD.encode(self.key),
+        }
+    }
+
+    /// Attempts to read a secret from a hexadecimal string.
+    ///
+    /// # Arguments
+    /// *  `s` - The string of length `SIZE * 2`.
+    pub fn from_hex(s: &str) -> Result<Self, String> {
+        Ok(s.chars()
+            .map(|c| c.to_digit(16))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| "secret contains an invalid character".to_string())
+            .and_then(|bytes| {
+                if bytes.len() == 2 * SIZE {
+                    Ok(bytes)
+                } else {
+                    Err(format!(
+                        "expected length of secret to be {}, found {}",
+                        2 * SIZE,
+                        s.len(),
+                    ))
+                }
+            })?
+            .chunks(2)
+            .map(|c| (c[1] | (c[0] << 4)) as u8)
+            .collect::<Vec<_>>()
+            .try_into()
+            // We have explicitly checked the length before
+            .map(<[u8; SIZE]>::into)
+            .unwrap())
+    }
+
+    /// Attempts to read a secret from a base64 string.
+    ///
+    /// Both padded and unpadded strings are accepted.
+    ///
+    /// # Arguments
+    /// *  `s` - The base64 string.
+    pub fn from_base64(s: &str) -> Result<Self, String> {
+        let bytes = if s.ends_with('=') {
+            STANDARD.decode(s)
+        } else {
+            STANDARD_NO_PAD.decode(s)
+        }
+        .map_err(|e| format!("invalid base64 secret: {}", e))?;
+        let length = bytes.len();
+        <[u8; SIZE]>::try_from(bytes).map(Self::from).map_err(|_| {
+            format!(
+                "expected length of secret to be {} bytes, found {}",
+                SIZE, length,
+            )
+        })
+    }
+
+    /// Attempts to read a secret from an environment variable.
+    ///
+    /// If the variable is not set, or if its value is an invalid string, an
+    /// error is returned.
+    ///
+    /// # Arguments
+    /// *  `var` - The name of the environment variable.
+    pub fn from_env(var: &str) -> Result<Self, String> {
+        ::std::env::var(var)
+            .map_err(|e| format!("failed to read {}: {}", var, e))?
+            .parse()
+    }
+}
+
+impl<const SIZE: usize> ::std::fmt::Display for Secret<SIZE> {
+    /// Writes this secret as a hexadecimal string accepted by
+    /// [`FromStr`](::std::str::FromStr).
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        self.key.iter().try_for_each(|byte| {
+            write!(
+                f,
+                "{}{}",
+                Self::DIGITS[(byte >> 4) as usize],
+                Self::DIGITS[(byte & 0x0F) as usize],
+            )
+        })
+    }
 }
 
 impl<const SIZE: usize> ::std::fmt::Debug for Secret<SIZE> {
@@ -194,31 +616,14 @@ impl<const SIZE: usize> ::std::fmt::Debug for Secret<SIZE> {
 impl<const SIZE: usize> ::std::str::FromStr for Secret<SIZE> {
     type Err = String;
 
+    /// Parses a secret, detecting its encoding.
+    ///
+    /// A string of `SIZE * 2` hexadecimal characters is always read as
+    /// hexadecimal. Otherwise the string is read as base64 if it decodes to
+    /// exactly `SIZE` bytes; if it does not, the error describes why it is
+    /// not a valid hexadecimal string.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Self {
-            key: s
-                .chars()
-                .map(|c| c.to_digit(16))
-                .collect::<Option<Vec<_>>>()
-                .ok_or_else(|| format!("secret contains an invalid character"))
-                .and_then(|bytes| {
-                    if bytes.len() == 2 * SIZE {
-                        Ok(bytes)
-                    } else {
-                        Err(format!(
-                            "expected length of secret to be {}, found {}",
-                            2 * SIZE,
-                            s.len(),
-                        ))
-                    }
-                })?
-                .chunks(2)
-                .map(|c| (c[1] | (c[0] << 4)) as u8)
-                .collect::<Vec<_>>()
-                .try_into()
-                // We have explicitly checked the length before
-                .unwrap(),
-        })
+        Self::decode(s).map(|(secret, _)| secret)
     }
 }
 
@@ -236,19 +641,13 @@ impl<const SIZE: usize> TryFrom<String> for Secret<SIZE> {
     }
 }
 
-impl<const SIZE: usize> Into<String> for Secret<SIZE> {
-    /// Converts this secret into a hexadecimal encoded string.
-    fn into(self) -> String {
-        (0..SIZE)
-            .map(|i| {
-                let byte = self.key[i >> 1];
-                if i & 1 == 0 {
-                    Self::DIGITS[(byte & 0x0F) as usize]
-                } else {
-                    Self::DIGITS[((byte >> 4) & 0x0F) as usize]
-                }
-            })
-            .collect()
+impl<const SIZE: usize> From<Secret<SIZE>> for String {
+    /// Converts a secret into a hexadecimal encoded string.
+    ///
+    /// This is the string written by [`Display`](::std::fmt::Display), so it
+    /// is accepted by [`FromStr`](::std::str::FromStr).
+    fn from(source: Secret<SIZE>) -> Self {
+        source.to_string()
     }
 }
 
@@ -335,4 +734,164 @@ mod tests {
             result,
         );
     }
+
+    #[test]
+    fn secret_parse_base64() {
+        // Arrange
+        let source1 = "X0wRXw==";
+        let source2 = "X0wRXw";
+
+        // Act
+        let result1 = Secret::<4>::decode(source1);
+        let result2 = source2.parse::<Secret<4>>();
+
+        // Assert
+        assert_eq!(
+            Ok((
+                Secret::from([0x5f, 0x4c, 0x11, 0x5f]),
+                SecretEncoding::Base64
+            )),
+            result1,
+        );
+        assert_eq!(Ok(Secret::from([0x5f, 0x4c, 0x11, 0x5f])), result2);
+    }
+
+    #[test]
+    fn secret_decode_hex() {
+        // Arrange
+        let source = "5F4C115F";
+
+        // Act
+        let result = Secret::<4>::decode(source);
+
+        // Assert
+        assert_eq!(
+            Ok((Secret::from([0x5f, 0x4c, 0x11, 0x5f]), SecretEncoding::Hex)),
+            result,
+        );
+    }
+
+    #[test]
+    fn secret_from_base64_wrong_length() {
+        // Arrange
+        let source = "X0wRXwA=";
+
+        // Act
+        let result = Secret::<4>::from_base64(source);
+
+        // Assert
+        assert_eq!(
+            Err("expected length of secret to be 4 bytes, found 5".into()),
+            result,
+        );
+    }
+
+    #[test]
+    fn secret_into_string() {
+        // Arrange
+        let secret = Secret {
+            key: [0x5f, 0x4c, 0x11, 0x5f],
+        };
+
+        // Act
+        let result = String::from(secret.clone());
+
+        // Assert
+        assert_eq!("5F4C115F", result);
+        assert_eq!(Ok(secret), Secret::<4>::try_from(result));
+    }
+
+    #[test]
+    fn secret_encode() {
+        // Arrange
+        let secret = Secret::from([0x5f, 0x4c, 0x11, 0x5f]);
+
+        // Act
+        let hex = secret.encode(SecretEncoding::Hex);
+        let base64 = secret.encode(SecretEncoding::Base64);
+
+        // Assert
+        assert_eq!("5F4C115F", hex);
+        assert_eq!("X0wRXw==", base64);
+    }
+
+    #[test]
+    fn secret_generate_unique() {
+        // Act
+        let secret1 = Secret::<32>::generate();
+        let secret2 = Secret::<32>::generate();
+
+        // Assert
+        assert_ne!(secret1, secret2);
+    }
+
+    #[test]
+    fn secret_generate_display_parse() {
+        // Arrange
+        let expected = Secret::<32>::generate();
+
+        // Act
+        let result = expected.to_string().parse::<Secret<32>>();
+
+        // Assert
+        assert_eq!(Ok(expected), result);
+    }
+
+    #[test]
+    fn secret_from_env_ok() {
+        // Arrange
+        ::std::env::set_var("WERU_SESSION_TEST_SECRET_OK", "5f4c115f");
+
+        // Act
+        let result = Secret::<4>::from_env("WERU_SESSION_TEST_SECRET_OK");
+
+        // Assert
+        assert_eq!(
+            Ok(Secret {
+                key: [0x5f, 0x4c, 0x11, 0x5f],
+            }),
+            result,
+        );
+    }
+
+    #[test]
+    fn secret_from_env_missing() {
+        // Act
+        let result = Secret::<4>::from_env("WERU_SESSION_TEST_SECRET_MISSING");
+
+        // Assert
+        assert!(result.is_err());
+    }
+
+    #[cfg(feature = "redis")]
+    #[test]
+    fn validate_invalid() {
+        // Arrange
+        let configuration = Configuration::Redis(redis::Configuration {
+            secret: Secret::generate(),
+            previous_secrets: Vec::new(),
+            encoding: SecretEncoding::Hex,
+            name: String::new(),
+            secure: None,
+            same_site: None,
+            partitioned: false,
+            connection_string: String::new(),
+            ttl: 0,
+            expiration: Default::default(),
+            key_prefix: "session".into(),
+        });
+
+        // Act
+        let result = configuration.validate();
+
+        // Assert
+        assert_eq!(
+            Err(vec![
+                "the connection string must not be empty".to_string(),
+                "the cookie name must not be empty".to_string(),
+                "the session time-to-live must be positive".to_string(),
+            ]),
+            result,
+        );
+    }
 }
@@ -1,4 +1,4 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, Serializer};
 
 pub use actix_session::storage::RedisSessionStore as Store;
 
@@ -7,26 +7,105 @@ use crate::configuration;
 /// The size, in bytes, of a key used to protect sessions.
 const SESSION_KEY_SIZE: usize = 32 + 32;
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize)]
 pub struct Configuration {
     /// The secret used to protect cookies.
     pub secret: super::Secret<SESSION_KEY_SIZE>,
 
+    /// Secrets previously used to protect cookies.
+    ///
+    /// Cookies protected by any of these are still accepted, but new cookies
+    /// are always protected by `secret`.
+    #[serde(default)]
+    pub previous_secrets: Vec<super::Secret<SESSION_KEY_SIZE>>,
+
+    /// The encoding used when serialising `secret` and `previous_secrets`.
+    ///
+    /// Secrets in either encoding are accepted when deserialising.
+    #[serde(default)]
+    pub encoding: super::SecretEncoding,
+
     /// The name of the cookie
     pub name: String,
 
+    /// Whether to set the _Secure_ attribute on the cookie.
+    ///
+    /// If not specified, the attribute is set.
+    #[serde(default)]
+    pub secure: Option<bool>,
+
+    /// The value of the _SameSite_ attribute of the cookie.
+    ///
+    /// If not specified, the default of the session middleware is used.
+    #[serde(default)]
+    pub same_site: Option<super::SameSite>,
+
+    /// Whether to set the _Partitioned_ attribute on the cookie.
+    ///
+    /// This allows the cookie to be used in embedded third-party contexts.
+    /// If _SameSite_ is `none`, this also forces the _Secure_ attribute.
+    #[serde(default)]
+    pub partitioned: bool,
+
     /// The Redis connection string.
     ///
     /// This is a string on the format `"redis://host:port"`.
     pub connection_string: String,
 
-    /// The time-to-live for cookies, in seconds.
+    /// The time-to-live for sessions, in seconds.
+    #[serde(default = "super::default_ttl")]
     pub ttl: u32,
 
+    /// How activity affects the expiration of sessions.
+    #[serde(default)]
+    pub expiration: super::Expiration,
+
     /// The prefix used to generate the Redis keys for sessions.
     pub key_prefix: String,
 }
 
+impl Serialize for Configuration {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        /// This configuration, with secrets written with its encoding.
+        #[derive(Serialize)]
+        struct Serialized<'a> {
+            secret: super::Encoded<'a, super::Secret<SESSION_KEY_SIZE>>,
+            previous_secrets:
+                super::Encoded<'a, Vec<super::Secret<SESSION_KEY_SIZE>>>,
+            encoding: super::SecretEncoding,
+            name: &'a str,
+            secure: Option<bool>,
+            same_site: Option<super::SameSite>,
+            partitioned: bool,
+            connection_string: &'a str,
+            ttl: u32,
+            expiration: super::Expiration,
+            key_prefix: &'a str,
+        }
+
+        Serialized {
+            secret: super::Encoded(&self.secret, self.encoding),
+            previous_secrets: super::Encoded(
+                &self.previous_secrets,
+                self.encoding,
+            ),
+            encoding: self.encoding,
+            name: &self.name,
+            secure: self.secure,
+            same_site: self.same_site,
+            partitioned: self.partitioned,
+            connection_string: &self.connection_string,
+            ttl: self.ttl,
+            expiration: self.expiration,
+            key_prefix: &self.key_prefix,
+        }
+        .serialize(serializer)
+    }
+}
+
 impl Configuration {
     /// Constructs a session middleware with this configuraed storage.
     pub async fn store(&self) -> Result<Store, configuration::Error> {
@@ -36,6 +115,15 @@ impl Configuration {
             .build()
             .await?)
     }
+
+    /// Verifies this configuration, and returns all problems found.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        if self.connection_string.is_empty() {
+            Err(vec!["the connection string must not be empty".into()])
+        } else {
+            Ok(())
+        }
+    }
 }
 
 /// Creates a clone of a store.
@@ -0,0 +1,106 @@
+use super::*;
+
+#[actix_rt::test]
+async fn load_unknown() {
+    // Arrange
+    let store = store().await;
+    let session_key = "unknown".to_string().try_into().unwrap();
+
+    // Act
+    let actual = store.load(&session_key).await.unwrap();
+
+    // Assert
+    assert_eq!(None, actual);
+}
+
+#[actix_rt::test]
+async fn save_load() {
+    // Arrange
+    let store = store().await;
+    let expected = state();
+
+    // Act
+    let session_key = store
+        .save(expected.clone(), &Duration::seconds(32))
+        .await
+        .unwrap();
+    let actual = clone(&store).load(&session_key).await.unwrap();
+
+    // Assert
+    assert_eq!(Some(expected), actual);
+}
+
+#[actix_rt::test]
+async fn save_load_expired() {
+    // Arrange
+    let store = store().await;
+
+    // Act
+    let session_key = store.save(state(), &Duration::ZERO).await.unwrap();
+    let actual = store.load(&session_key).await.unwrap();
+
+    // Assert
+    assert_eq!(None, actual);
+}
+
+#[actix_rt::test]
+async fn save_update_load() {
+    // Arrange
+    let store = store().await;
+    let expected = [("key".to_string(), "updated".to_string())]
+        .into_iter()
+        .collect::<SessionState>();
+
+    // Act
+    let session_key =
+        store.save(state(), &Duration::seconds(32)).await.unwrap();
+    let session_key = store
+        .update(session_key, expected.clone(), &Duration::seconds(32))
+        .await
+        .unwrap();
+    let actual = store.load(&session_key).await.unwrap();
+
+    // Assert
+    assert_eq!(Some(expected), actual);
+}
+
+#[actix_rt::test]
+async fn save_update_ttl_load() {
+    // Arrange
+    let store = store().await;
+    let expected = None;
+
+    // Act
+    let session_key =
+        store.save(state(), &Duration::seconds(32)).await.unwrap();
+    store
+        .update_ttl(&session_key, &Duration::ZERO)
+        .await
+        .unwrap();
+    let actual = store.load(&session_key).await.unwrap();
+
+    // Assert
+    assert_eq!(expected, actual);
+}
+
+#[actix_rt::test]
+async fn save_delete_load() {
+    // Arrange
+    let store = store().await;
+
+    // Act
+    let session_key =
+        store.save(state(), &Duration::seconds(32)).await.unwrap();
+    store.delete(&session_key).await.unwrap();
+    let actual = store.load(&session_key).await.unwrap();
+
+    // Assert
+    assert_eq!(None, actual);
+}
+
+/// A simple session state.
+fn state() -> SessionState {
+    [("key".to_string(), "value".to_string())]
+        .into_iter()
+        .collect()
+}
@@ -0,0 +1,188 @@
+//! # Cached entities
+//!
+//! This module combines a [cache](weru_cache::Cache) and the
+//! [database](weru_database::Entity) to read entities through the cache.
+//!
+//! Reads check the cache first, and populate it from the database on a miss.
+//! Writes go to the database, and then invalidate the cached entry.
+//!
+//! # Examples
+//!
+//! ```
+//! # use std::time::Duration;
+//! # use weru::cached;
+//! # use weru::database::entity;
+//! # use weru_cache::engine::backends::local;
+//! # use weru_database::Entity;
+//! # use weru_database::sqlx::prelude::*;
+//! # actix_rt::Runtime::new().unwrap().block_on(async {
+//! # let database = weru_database::Configuration {
+//! #     connection_string: "sqlite::memory:".into(),
+//! #     parts: None,
+//! #     slow_threshold: None,
+//! #     acquire_threshold: None,
+//! # }.engine().await.unwrap();
+//! # let cache = weru_cache::Configuration::Local(local::Configuration::default())
+//! #     .engine().await.unwrap();
+//!
+//! #[entity(Plants)]
+//! #[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+//! pub struct Plant {
+//!     pub name: String,
+//!     pub height: u32,
+//! }
+//!
+//! let cache = cache.cache::<String, Plant>("plants").await.unwrap();
+//! let mut connection = database.connection().await.unwrap();
+//! # connection.execute(r#"
+//! #     CREATE TABLE Plants (
+//! #         name TEXT NOT NULL PRIMARY KEY,
+//! #         height INTEGER NOT NULL
+//! #     );
+//! # "#).await.unwrap();
+//! let ttl = Duration::from_secs(60);
+//! let plant = Plant::new("Fern".into(), 30);
+//! cached::create(cache.as_ref(), &mut *connection, &plant).await.unwrap();
+//!
+//! // The first read populates the cache...
+//! let read = cached::read(cache.as_ref(), &mut *connection, &plant.name, ttl)
+//!     .await
+//!     .unwrap();
+//! assert_eq!(Some(plant.clone()), read);
+//! assert_eq!(Some(plant.clone()), cache.get(&plant.name).await.unwrap());
+//!
+//! // ...and updating invalidates it
+//! let taller = Plant::new("Fern".into(), 40);
+//! cached::update(cache.as_ref(), &mut *connection, &taller).await.unwrap();
+//! assert_eq!(None, cache.get(&plant.name).await.unwrap());
+//! let read = cached::read(cache.as_ref(), &mut *connection, &plant.name, ttl)
+//!     .await
+//!     .unwrap();
+//! assert_eq!(Some(taller), read);
+//! # });
+//! ```
+
+use std::time::Duration;
+
+use weru_cache::{Cache, Key, Value};
+use weru_database::{sqlx, Database, Entity};
+
+/// An error occurring when accessing a cached entity.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// Accessing the cache failed.
+    #[error("cache error: {0}")]
+    Cache(#[from] weru_cache::Error),
+
+    /// Accessing the database failed.
+    #[error("database error: {0}")]
+    Database(#[from] weru_database::Error),
+}
+
+/// Loads an entity, reading through the cache.
+///
+/// If the entity is not cached, it is read from the database and then
+/// cached. Missing entities are not cached.
+///
+/// # Arguments
+/// *  `cache` - The cache holding entities.
+/// *  `e` - The database executor.
+/// *  `key` - The key of the entity.
+/// *  `ttl` - The time-to-live for a newly cached entity.
+pub async fn read<'a, T, E>(
+    cache: &dyn Cache<T::Key, T>,
+    e: E,
+    key: &T::Key,
+    ttl: Duration,
+) -> Result<Option<T>, Error>
+where
+    T: Entity + Value,
+    T::Key: Key,
+    E: sqlx::Executor<'a, Database = Database>,
+{
+    if let Some(entity) = cache.get(key).await? {
+        Ok(Some(entity))
+    } else if let Some(entity) = T::read(e, key).await? {
+        cache.put(key.clone(), entity.clone(), ttl).await?;
+        Ok(Some(entity))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Inserts an entity to the database, and invalidates its cache entry.
+///
+/// # Arguments
+/// *  `cache` - The cache holding entities.
+/// *  `e` - The database executor.
+/// *  `entity` - The entity to insert.
+pub async fn create<'a, T, E>(
+    cache: &dyn Cache<T::Key, T>,
+    e: E,
+    entity: &T,
+) -> Result<(), Error>
+where
+    T: Entity + Value,
+    T::Key: Key,
+    E: sqlx::Executor<'a, Database = Database>,
+{
+    entity.create(e).await?;
+    invalidate(cache, entity).await
+}
+
+/// Updates an entity in the database, and invalidates its cache entry.
+///
+/// # Arguments
+/// *  `cache` - The cache holding entities.
+/// *  `e` - The database executor.
+/// *  `entity` - The entity to update.
+pub async fn update<'a, T, E>(
+    cache: &dyn Cache<T::Key, T>,
+    e: E,
+    entity: &T,
+) -> Result<(), Error>
+where
+    T: Entity + Value,
+    T::Key: Key,
+    E: sqlx::Executor<'a, Database = Database>,
+{
+    entity.update(e).await?;
+    invalidate(cache, entity).await
+}
+
+/// Deletes an entity from the database, and invalidates its cache entry.
+///
+/// # Arguments
+/// *  `cache` - The cache holding entities.
+/// *  `e` - The database executor.
+/// *  `entity` - The entity to delete.
+pub async fn delete<'a, T, E>(
+    cache: &dyn Cache<T::Key, T>,
+    e: E,
+    entity: &T,
+) -> Result<(), Error>
+where
+    T: Entity + Value,
+    T::Key: Key,
+    E: sqlx::Executor<'a, Database = Database>,
+{
+    entity.delete(e).await?;
+    invalidate(cache, entity).await
+}
+
+/// Removes the cache entry of an entity.
+///
+/// # Arguments
+/// *  `cache` - The cache holding entities.
+/// *  `entity` - The entity to remove.
+async fn invalidate<T>(
+    cache: &dyn Cache<T::Key, T>,
+    entity: &T,
+) -> Result<(), Error>
+where
+    T: Entity + Value,
+    T::Key: Key,
+{
+    cache.pop(entity.key()).await?;
+    Ok(())
+}
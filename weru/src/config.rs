@@ -0,0 +1,145 @@
+//! # The application configuration
+//!
+//! The [configuration struct](Config) collects the configurations of all
+//! subsystems, and allows loading them from a single _TOML_ file.
+//!
+//! Every section is optional, and only sections for enabled features are
+//! available.
+//!
+//! # Examples
+//!
+//! ```
+//! # actix_rt::Runtime::new().unwrap().block_on(async {
+//! let config = r#"
+//!     [cache]
+//!     type = "local"
+//!
+//!     [channel]
+//!     type = "local"
+//!     queue_size = 16
+//!
+//!     [database]
+//!     connection_string = "sqlite::memory:"
+//! "#
+//! .parse::<weru::Config>()
+//! .unwrap();
+//!
+//! let cache = config.cache.unwrap().engine().await.unwrap();
+//! let channel = config.channel.unwrap().engine().await.unwrap();
+//! let database = config.database.unwrap().engine().await.unwrap();
+//! assert!(config.email.is_none());
+//! # });
+//! ```
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+/// An error occurring when loading a configuration.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// The configuration file could not be read.
+    #[error("failed to read configuration: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// The configuration file is invalid.
+    #[error("invalid configuration: {0}")]
+    Parse(#[from] toml::de::Error),
+}
+
+/// A serialised application configuration.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Config {
+    /// The cache configuration.
+    #[cfg(feature = "cache")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache: Option<weru_cache::Configuration>,
+
+    /// The channel configuration.
+    #[cfg(feature = "channel")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel: Option<weru_channel::Configuration>,
+
+    /// The database configuration.
+    #[cfg(feature = "database")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub database: Option<weru_database::Configuration>,
+
+    /// The e-mail configuration.
+    #[cfg(feature = "email")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<weru_email::Configuration>,
+
+    /// The session configuration.
+    #[cfg(feature = "session")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session: Option<weru_session::Configuration>,
+}
+
+impl Config {
+    /// Loads a configuration from a _TOML_ file.
+    ///
+    /// # Arguments
+    /// *  `path` - The path to the configuration file.
+    pub fn from_path<P>(path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        std::fs::read_to_string(path)?.parse()
+    }
+
+    /// Verifies the configurations of all subsystems.
+    ///
+    /// All problems are returned, each prefixed by the name of its section,
+    /// so an application can report every misconfiguration at startup
+    /// before constructing any engine.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut problems = Vec::new();
+        let mut check = |section: &str, result: Result<(), Vec<String>>| {
+            if let Err(e) = result {
+                problems.extend(
+                    e.into_iter()
+                        .map(|problem| format!("{section}: {problem}")),
+                );
+            }
+        };
+        #[cfg(feature = "cache")]
+        if let Some(cache) = &self.cache {
+            check("cache", cache.validate());
+        }
+        #[cfg(feature = "channel")]
+        if let Some(channel) = &self.channel {
+            check("channel", channel.validate());
+        }
+        #[cfg(feature = "database")]
+        if let Some(database) = &self.database {
+            check("database", database.validate());
+        }
+        #[cfg(feature = "email")]
+        if let Some(email) = &self.email {
+            check("email", email.validate());
+        }
+        #[cfg(feature = "session")]
+        if let Some(session) = &self.session {
+            check("session", session.validate());
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems)
+        }
+    }
+}
+
+impl std::str::FromStr for Config {
+    type Err = Error;
+
+    /// Parses a configuration from a _TOML_ string.
+    ///
+    /// # Arguments
+    /// *  `s` - The configuration string.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(toml::from_str(s)?)
+    }
+}
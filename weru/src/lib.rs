@@ -50,9 +50,7 @@
 //!         .expect("logging initialised");
 //!
 //!     let cache = web::Data::new(
-//!         weru::cache::Configuration::Local(
-//!             weru::cache::engine::backends::local::Configuration,
-//!         )
+//!         weru::cache::Configuration::Local(Default::default())
 //!         .engine()
 //!         .await
 //!         .expect("a local cache engine")
@@ -94,6 +92,9 @@
 //! in those crates. To use *sqlx* migration macros, add *sqlx* as a
 //! dependency.
 
+pub mod config;
+pub use config::Config;
+
 #[cfg(feature = "cache")]
 pub mod cache {
     //! # The *weru* cache
@@ -120,7 +121,8 @@ pub mod cache {
     //! // Create a configuration for a process local cache.
     //! //
     //! // You would normally load this value from a file.
-    //! let configuration = Configuration::Local(local::Configuration);
+    //! let configuration =
+    //!     Configuration::Local(local::Configuration::default());
     //!
     //! // Create a cache engine from the configuration...
     //! let engine = configuration.engine().await.unwrap();
@@ -141,6 +143,9 @@ pub mod cache {
     pub use weru_cache::*;
 }
 
+#[cfg(all(feature = "cache", feature = "database"))]
+pub mod cached;
+
 #[cfg(feature = "channel")]
 pub mod channel {
     //! # The *weru* channel
@@ -166,6 +171,7 @@ pub mod channel {
     //! // You would normally load this value from a file.
     //! let configuration = Configuration::Local(local::Configuration {
     //!        queue_size: 10,
+    //!        replay: 0,
     //! });
     //!
     //! // Create a channel engine from the configuration...
@@ -206,6 +212,9 @@ pub mod database {
     //! // You would normally load this value from a file.
     //! let configuration = Configuration {
     //!        connection_string: "sqlite::memory:".into(),
+    //!        parts: None,
+    //!        slow_threshold: None,
+    //!        acquire_threshold: None,
     //! };
     //!
     //! // Create a database engine from the configuration...
@@ -234,6 +243,80 @@ pub mod database {
     //!        "#).await.unwrap().get(0)
     //! };
     //! assert_eq!(value, String::from("value"));
+    //!
+    //! // Queries not corresponding to entities may return any rows
+    //! let values: Vec<(String,)> = weru_database::query_as(
+    //!        &mut *connection,
+    //!        "SELECT value FROM Test WHERE value = ?",
+    //!        &["value".into()],
+    //! ).await.unwrap();
+    //! assert_eq!(values, vec![(String::from("value"),)]);
+    //! let value: Option<(String,)> = weru_database::query_as_optional(
+    //!        &mut *connection,
+    //!        "SELECT value FROM Test WHERE value = ?",
+    //!        &[weru_database::Bind::Null],
+    //! ).await.unwrap();
+    //! assert_eq!(value, None);
+    //!
+    //! // The columns of arbitrary rows can be inspected
+    //! let row = connection.fetch_one("SELECT value FROM Test").await.unwrap();
+    //! assert_eq!(
+    //!        weru_database::columns(&row),
+    //!        vec![(String::from("value"), String::from("TEXT"))],
+    //! );
+    //!
+    //! // Transactions may require a specific isolation level; SQLite only
+    //! // supports serialisable transactions
+    //! {
+    //!        use weru_database::{begin_with_isolation, IsolationLevel};
+    //!        let tx = begin_with_isolation(
+    //!            &mut connection,
+    //!            IsolationLevel::Serializable,
+    //!        ).await.unwrap();
+    //!        tx.rollback().await.unwrap();
+    //!        assert!(begin_with_isolation(
+    //!            &mut connection,
+    //!            IsolationLevel::ReadCommitted,
+    //!        ).await.is_err());
+    //! }
+    //!
+    //! // Run a function in a transaction that is committed on success and
+    //! // rolled back on failure
+    //! drop(connection);
+    //! let count = engine.transaction(|tx| Box::pin(async move {
+    //!        tx.execute(r#"INSERT INTO Test(value) VALUES("other")"#).await?;
+    //!        let (count,): (i64,) = weru_database::query_as_optional(
+    //!            &mut **tx,
+    //!            "SELECT COUNT(*) FROM Test",
+    //!            &[],
+    //!        ).await?.unwrap();
+    //!        Ok(count)
+    //! })).await.unwrap();
+    //! assert_eq!(count, 2);
+    //! let failed = engine.transaction::<_, ()>(|tx| Box::pin(async move {
+    //!        tx.execute(r#"INSERT INTO Test(value) VALUES("failed")"#).await?;
+    //!        Err(weru_database::Error::RowNotFound)
+    //! })).await;
+    //! assert!(failed.is_err());
+    //! let mut connection = engine.connection().await.unwrap();
+    //! let values: Vec<(String,)> = weru_database::query_as(
+    //!        &mut *connection,
+    //!        "SELECT value FROM Test ORDER BY value",
+    //!        &[],
+    //! ).await.unwrap();
+    //! assert_eq!(
+    //!        values,
+    //!        vec![(String::from("other"),), (String::from("value"),)],
+    //! );
+    //!
+    //! // Verify that the database is reachable
+    //! assert!(engine.health_check().await.is_ok());
+    //!
+    //! // Close the engine when shutting down; this waits for connections in
+    //! // use to be returned
+    //! drop(connection);
+    //! engine.close().await;
+    //! assert!(engine.connection().await.is_err());
     //! # });
     //! ```
     pub use weru_database::*;
@@ -242,8 +325,45 @@ pub mod database {
     ///
     /// This macro allows you to specify a `struct` that corresponds to a
     /// table. The macro argument specifies the name of the table, and the
-    /// `struct` fields specify the columns. The first field is the unique
-    /// primary key.
+    /// `struct` fields specify the columns. The field marked with `#[key]` is
+    /// the unique primary key; if no field is marked, the first field is the
+    /// key. The generated `new` constructor takes the key as its first
+    /// argument, followed by the remaining fields in declaration order.
+    ///
+    /// Fields other than the key may be marked with `#[json]` to store them as
+    /// JSON. Such fields are bound and read through
+    /// [`Json`](weru_database::sqlx::types::Json), so their types must
+    /// implement `serde::Serialize` and `serde::Deserialize`.
+    ///
+    /// Fields other than the key may also be marked with `#[raw]` to bind
+    /// them without conversion and read them using their native column type.
+    /// This is useful for enums deriving `sqlx::Type`, for example with
+    /// `#[sqlx(type_name = "text")]`. The types of such fields must implement
+    /// `Clone`, `sqlx::Type`, `sqlx::Encode` and `sqlx::Decode` for the
+    /// database in use.
+    ///
+    /// Any field may be marked with `#[index]` or `#[index(unique)]` to
+    /// create an index on its column. The statements are available as
+    /// [`Entity::INDEXES`](weru_database::Entity::INDEXES), and are executed
+    /// by [`Entity::ensure_indexes`](weru_database::Entity::ensure_indexes).
+    /// Indexes are named `<table>_<column>_idx`; like the table name, the
+    /// index name is quoted in the statements.
+    ///
+    /// Passing `hooks` after the table name, as in `#[entity(Pets, hooks)]`,
+    /// makes the entity call the hooks of
+    /// [`EntityHooks`](weru_database::EntityHooks) after it has been
+    /// successfully created, updated or deleted. The trait must then be
+    /// implemented for the `struct`. Partial updates run no hooks, since they
+    /// never load the updated item.
+    ///
+    /// Passing `no_description` after the table name, as in
+    /// `#[entity(Planets, no_description)]`, suppresses the description
+    /// `struct`. This suits read-only entities such as views and reference
+    /// tables, and fields whose types cannot be wrapped in `Option`. The
+    /// description of the entity is then `()`, so
+    /// [`Entity::merge`](weru_database::Entity::merge) and
+    /// [`Entity::update_partial`](weru_database::Entity::update_partial) do
+    /// nothing.
     ///
     /// Please see the trait [`Entity`](weru_database::Entity) for more
     /// information.
@@ -252,12 +372,16 @@ pub mod database {
     ///
     /// ```
     /// # use weru::database::entity;
+    /// # use futures::TryStreamExt;
     /// # use std::time::Duration;
     /// # use weru_database::{Configuration, Engine, Entity};
     /// # use weru_database::sqlx::prelude::*;
     /// # actix_rt::Runtime::new().unwrap().block_on(async {
     /// # let engine = Configuration {
     /// #     connection_string: "sqlite::memory:".into(),
+    /// #     parts: None,
+    /// #     slow_threshold: None,
+    /// #     acquire_threshold: None,
     /// # }.engine().await.unwrap();
     ///
     /// #[entity(Pets)]
@@ -297,10 +421,725 @@ pub mod database {
     ///         .unwrap()
     ///         .unwrap();
     ///     assert_eq!(new_pet, recreated);
+    ///
+    ///     // Stream all pets without loading the entire table
+    ///     let pets = Pet::stream(&mut *tx)
+    ///         .try_collect::<Vec<_>>()
+    ///         .await
+    ///         .unwrap();
+    ///     assert_eq!(vec![new_pet], pets);
+    /// }
+    /// # });
+    /// ```
+    ///
+    /// Fields stored as JSON:
+    ///
+    /// ```
+    /// # use weru::database::entity;
+    /// # use weru_database::{Configuration, Engine, Entity};
+    /// # use weru_database::sqlx::prelude::*;
+    /// # actix_rt::Runtime::new().unwrap().block_on(async {
+    /// # let engine = Configuration {
+    /// #     connection_string: "sqlite::memory:".into(),
+    /// #     parts: None,
+    /// #     slow_threshold: None,
+    /// #     acquire_threshold: None,
+    /// # }.engine().await.unwrap();
+    ///
+    /// #[entity(Owners)]
+    /// #[derive(Debug, PartialEq)]
+    /// pub struct Owner {
+    ///     pub name: String,
+    ///     #[json]
+    ///     pub pets: Vec<String>,
+    /// }
+    ///
+    /// let mut connection = engine.connection().await.unwrap();
+    /// # connection.execute(r#"
+    /// #     CREATE TABLE Owners (
+    /// #         name TEXT NOT NULL,
+    /// #         pets TEXT NOT NULL
+    /// #     );
+    /// # "#).await.unwrap();
+    /// let owner = Owner::new("Peter".into(), vec!["Spidey".into()]);
+    /// owner.create(&mut *connection).await.unwrap();
+    /// let recreated = Owner::read(&mut *connection, &"Peter".into()).await
+    ///     .unwrap()
+    ///     .unwrap();
+    /// assert_eq!(owner, recreated);
+    /// # });
+    /// ```
+    ///
+    /// Indexed fields:
+    ///
+    /// ```
+    /// # use weru::database::entity;
+    /// # use weru_database::{Configuration, Engine, Entity};
+    /// # use weru_database::sqlx::prelude::*;
+    /// # actix_rt::Runtime::new().unwrap().block_on(async {
+    /// # let engine = Configuration {
+    /// #     connection_string: "sqlite::memory:".into(),
+    /// #     parts: None,
+    /// #     slow_threshold: None,
+    /// #     acquire_threshold: None,
+    /// # }.engine().await.unwrap();
+    ///
+    /// #[entity(Users)]
+    /// #[derive(Debug, PartialEq)]
+    /// pub struct User {
+    ///     pub id: i64,
+    ///     #[index(unique)]
+    ///     pub email: String,
+    ///     #[index]
+    ///     pub country: String,
+    /// }
+    ///
+    /// let mut connection = engine.connection().await.unwrap();
+    /// # connection.execute(r#"
+    /// #     CREATE TABLE Users (
+    /// #         id INTEGER NOT NULL,
+    /// #         email TEXT NOT NULL,
+    /// #         country TEXT NOT NULL
+    /// #     );
+    /// # "#).await.unwrap();
+    /// assert_eq!(2, User::INDEXES.len());
+    /// assert_eq!(
+    ///     r#"CREATE UNIQUE INDEX IF NOT EXISTS "Users_email_idx" ON "Users" (email)"#,
+    ///     User::INDEXES[0],
+    /// );
+    ///
+    /// // Ensuring indexes is idempotent
+    /// User::ensure_indexes(&mut *connection).await.unwrap();
+    /// User::ensure_indexes(&mut *connection).await.unwrap();
+    ///
+    /// User::new(1, "a@example.com".into(), "SE".into())
+    ///     .create(&mut *connection)
+    ///     .await
+    ///     .unwrap();
+    /// assert!(User::new(2, "a@example.com".into(), "SE".into())
+    ///     .create(&mut *connection)
+    ///     .await
+    ///     .is_err());
+    /// # });
+    /// ```
+    ///
+    /// Hooks run on changes:
+    ///
+    /// ```
+    /// # use std::sync::atomic::{AtomicUsize, Ordering};
+    /// # use weru::database::entity;
+    /// # use weru_database::{Configuration, Engine, Entity, EntityHooks};
+    /// # use weru_database::sqlx::prelude::*;
+    /// # actix_rt::Runtime::new().unwrap().block_on(async {
+    /// # let engine = Configuration {
+    /// #     connection_string: "sqlite::memory:".into(),
+    /// #     parts: None,
+    /// #     slow_threshold: None,
+    /// #     acquire_threshold: None,
+    /// # }.engine().await.unwrap();
+    ///
+    /// static CHANGES: AtomicUsize = AtomicUsize::new(0);
+    ///
+    /// #[entity(Toys, hooks)]
+    /// #[derive(Debug, PartialEq)]
+    /// pub struct Toy {
+    ///     pub name: String,
+    ///     pub squeaky: bool,
+    /// }
+    ///
+    /// #[async_trait::async_trait]
+    /// impl EntityHooks for Toy {
+    ///     async fn on_created(&self) {
+    ///         CHANGES.fetch_add(1, Ordering::SeqCst);
+    ///     }
+    ///
+    ///     async fn on_updated(&self) {
+    ///         CHANGES.fetch_add(100, Ordering::SeqCst);
+    ///     }
+    ///
+    ///     async fn on_deleted(&self) {
+    ///         CHANGES.fetch_add(10, Ordering::SeqCst);
+    ///     }
+    /// }
+    ///
+    /// let mut connection = engine.connection().await.unwrap();
+    /// # connection.execute(r#"
+    /// #     CREATE TABLE Toys (
+    /// #         name TEXT NOT NULL PRIMARY KEY,
+    /// #         squeaky BOOLEAN NOT NULL
+    /// #     );
+    /// # "#).await.unwrap();
+    /// let toy = Toy::new("Ball".into(), true);
+    /// toy.create(&mut *connection).await.unwrap();
+    /// assert!(toy.create(&mut *connection).await.is_err());
+    /// toy.update(&mut *connection).await.unwrap();
+    /// let description = ToyDescription {
+    ///     squeaky: Some(false),
+    /// };
+    /// Toy::update_partial(&mut *connection, &toy.name, &description)
+    ///     .await
+    ///     .unwrap();
+    /// toy.delete(&mut *connection).await.unwrap();
+    /// assert!(toy.delete(&mut *connection).await.is_err());
+    ///
+    /// // Only successful operations run hooks, and partial updates run none
+    /// assert_eq!(111, CHANGES.load(Ordering::SeqCst));
+    /// # });
+    /// ```
+    ///
+    /// Fields stored using their native type:
+    ///
+    /// ```
+    /// # use weru::database::entity;
+    /// # use weru_database::{Configuration, Engine, Entity};
+    /// # use weru_database::sqlx::prelude::*;
+    /// # actix_rt::Runtime::new().unwrap().block_on(async {
+    /// # let engine = Configuration {
+    /// #     connection_string: "sqlite::memory:".into(),
+    /// #     parts: None,
+    /// #     slow_threshold: None,
+    /// #     acquire_threshold: None,
+    /// # }.engine().await.unwrap();
+    ///
+    /// #[derive(
+    ///     Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize,
+    ///     sqlx::Type,
+    /// )]
+    /// #[sqlx(type_name = "text", rename_all = "lowercase")]
+    /// pub enum Status {
+    ///     Hungry,
+    ///     Sated,
+    /// }
+    ///
+    /// #[entity(Cats)]
+    /// #[derive(Debug, PartialEq)]
+    /// pub struct Cat {
+    ///     pub name: String,
+    ///     #[raw]
+    ///     pub status: Status,
+    /// }
+    ///
+    /// let mut connection = engine.connection().await.unwrap();
+    /// # connection.execute(r#"
+    /// #     CREATE TABLE Cats (
+    /// #         name TEXT NOT NULL,
+    /// #         status TEXT NOT NULL
+    /// #     );
+    /// # "#).await.unwrap();
+    /// let cat = Cat::new("Tom".into(), Status::Hungry);
+    /// cat.create(&mut *connection).await.unwrap();
+    /// let recreated = Cat::read(&mut *connection, &"Tom".into()).await
+    ///     .unwrap()
+    ///     .unwrap();
+    /// assert_eq!(cat, recreated);
+    /// # });
+    /// ```
+    ///
+    /// Any field may be marked as the key:
+    ///
+    /// ```
+    /// # use weru::database::entity;
+    /// # use weru_database::{Configuration, Engine, Entity};
+    /// # use weru_database::sqlx::prelude::*;
+    /// # actix_rt::Runtime::new().unwrap().block_on(async {
+    /// # let engine = Configuration {
+    /// #     connection_string: "sqlite::memory:".into(),
+    /// #     parts: None,
+    /// #     slow_threshold: None,
+    /// #     acquire_threshold: None,
+    /// # }.engine().await.unwrap();
+    ///
+    /// #[entity(Dogs)]
+    /// #[derive(Clone, Debug, PartialEq)]
+    /// pub struct Dog {
+    ///     pub name: String,
+    ///     #[key]
+    ///     pub tag: u32,
+    ///     pub breed: String,
+    /// }
+    ///
+    /// let mut connection = engine.connection().await.unwrap();
+    /// # connection.execute(r#"
+    /// #     CREATE TABLE Dogs (
+    /// #         name TEXT NOT NULL,
+    /// #         tag INTEGER NOT NULL,
+    /// #         breed TEXT NOT NULL
+    /// #     );
+    /// # "#).await.unwrap();
+    /// let dog = Dog::new(7, "Fido".into(), "Beagle".into());
+    /// dog.create(&mut *connection).await.unwrap();
+    /// let renamed = dog.clone().merge(DogDescription {
+    ///     name: Some("Rex".into()),
+    ///     ..Default::default()
+    /// });
+    /// renamed.update(&mut *connection).await.unwrap();
+    /// let recreated = Dog::read(&mut *connection, &7).await
+    ///     .unwrap()
+    ///     .unwrap();
+    /// assert_eq!(renamed, recreated);
+    /// # });
+    /// ```
+    ///
+    /// Entities can be listed a page at a time, ordered by key:
+    ///
+    /// ```
+    /// # use weru::database::entity;
+    /// # use weru_database::{Configuration, Engine, Entity};
+    /// # use weru_database::sqlx::prelude::*;
+    /// # actix_rt::Runtime::new().unwrap().block_on(async {
+    /// # let engine = Configuration {
+    /// #     connection_string: "sqlite::memory:".into(),
+    /// #     parts: None,
+    /// #     slow_threshold: None,
+    /// #     acquire_threshold: None,
+    /// # }.engine().await.unwrap();
+    ///
+    /// #[entity(Fish)]
+    /// #[derive(Clone, Debug, PartialEq)]
+    /// pub struct Fish {
+    ///     pub id: u32,
+    ///     pub name: String,
+    /// }
+    ///
+    /// let mut connection = engine.connection().await.unwrap();
+    /// # connection.execute(r#"
+    /// #     CREATE TABLE Fish (
+    /// #         id INTEGER NOT NULL PRIMARY KEY,
+    /// #         name TEXT NOT NULL
+    /// #     );
+    /// # "#).await.unwrap();
+    /// for (id, name) in [(3, "Nemo"), (1, "Dory"), (2, "Marlin")] {
+    ///     Fish::new(id, name.into()).create(&mut *connection).await.unwrap();
+    /// }
+    /// let first = Fish::list_after(&mut *connection, None, 2).await.unwrap();
+    /// assert_eq!(vec![1, 2], first.iter().map(|f| f.id).collect::<Vec<_>>());
+    /// let next = Fish::list_after(&mut *connection, Some(first[1].id), 2)
+    ///     .await
+    ///     .unwrap();
+    /// assert_eq!(vec![3], next.iter().map(|f| f.id).collect::<Vec<_>>());
+    /// # });
+    /// ```
+    ///
+    /// Single columns can be updated without overwriting the others:
+    ///
+    /// ```
+    /// # use weru::database::entity;
+    /// # use weru_database::{Configuration, Engine, Entity, Error};
+    /// # use weru_database::sqlx::prelude::*;
+    /// # actix_rt::Runtime::new().unwrap().block_on(async {
+    /// # let engine = Configuration {
+    /// #     connection_string: "sqlite::memory:".into(),
+    /// #     parts: None,
+    /// #     slow_threshold: None,
+    /// #     acquire_threshold: None,
+    /// # }.engine().await.unwrap();
+    ///
+    /// #[entity(Cats)]
+    /// #[derive(Clone, Debug, PartialEq)]
+    /// pub struct Cat {
+    ///     pub id: u32,
+    ///     pub name: String,
+    ///     pub lives: u32,
+    /// }
+    ///
+    /// let mut connection = engine.connection().await.unwrap();
+    /// # connection.execute(r#"
+    /// #     CREATE TABLE Cats (
+    /// #         id INTEGER NOT NULL PRIMARY KEY,
+    /// #         name TEXT NOT NULL,
+    /// #         lives INTEGER NOT NULL
+    /// #     );
+    /// # "#).await.unwrap();
+    /// let cat = Cat::new(1, "Tom".into(), 9);
+    /// cat.create(&mut *connection).await.unwrap();
+    /// let description = CatDescription {
+    ///     lives: Some(8),
+    ///     ..Default::default()
+    /// };
+    /// Cat::update_partial(&mut *connection, &1, &description)
+    ///     .await
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     Some(Cat::new(1, "Tom".into(), 8)),
+    ///     Cat::read(&mut *connection, &1).await.unwrap(),
+    /// );
+    ///
+    /// // An empty description does nothing, and a missing entity fails
+    /// Cat::update_partial(&mut *connection, &2, &Default::default())
+    ///     .await
+    ///     .unwrap();
+    /// assert!(matches!(
+    ///     Cat::update_partial(&mut *connection, &2, &description).await,
+    ///     Err(Error::RowNotFound),
+    /// ));
+    ///
+    /// // Deletion may be strict or idempotent
+    /// assert!(cat.delete_if_exists(&mut *connection).await.unwrap());
+    /// assert!(!cat.delete_if_exists(&mut *connection).await.unwrap());
+    /// assert!(matches!(
+    ///     cat.delete(&mut *connection).await,
+    ///     Err(Error::RowNotFound),
+    /// ));
+    /// # });
+    /// ```
+    ///
+    /// Entities can be reloaded to pick up values set by the database:
+    ///
+    /// ```
+    /// # use weru::database::entity;
+    /// # use weru_database::{Configuration, Engine, Entity, Error};
+    /// # use weru_database::sqlx::prelude::*;
+    /// # actix_rt::Runtime::new().unwrap().block_on(async {
+    /// # let engine = Configuration {
+    /// #     connection_string: "sqlite::memory:".into(),
+    /// #     parts: None,
+    /// #     slow_threshold: None,
+    /// #     acquire_threshold: None,
+    /// # }.engine().await.unwrap();
+    ///
+    /// #[entity(Counters)]
+    /// #[derive(Clone, Debug, PartialEq)]
+    /// pub struct Counter {
+    ///     pub name: String,
+    ///     pub value: u32,
+    /// }
+    ///
+    /// let mut connection = engine.connection().await.unwrap();
+    /// # connection.execute(r#"
+    /// #     CREATE TABLE Counters (
+    /// #         name TEXT NOT NULL PRIMARY KEY,
+    /// #         value INTEGER NOT NULL
+    /// #     );
+    /// # "#).await.unwrap();
+    /// let mut counter = Counter::new("visits".into(), 0);
+    /// counter.create(&mut *connection).await.unwrap();
+    /// connection
+    ///     .execute("UPDATE Counters SET value = value + 1")
+    ///     .await
+    ///     .unwrap();
+    /// counter.refresh(&mut *connection).await.unwrap();
+    /// assert_eq!(1, counter.value);
+    ///
+    /// // Refreshing a deleted entity fails
+    /// counter.delete(&mut *connection).await.unwrap();
+    /// assert!(matches!(
+    ///     counter.refresh(&mut *connection).await,
+    ///     Err(Error::RowNotFound),
+    /// ));
+    /// # });
+    /// ```
+    ///
+    /// The statements of an entity can be prepared ahead of time:
+    ///
+    /// ```
+    /// # use weru::database::entity;
+    /// # use weru_database::{Configuration, Engine, Entity};
+    /// # use weru_database::sqlx::prelude::*;
+    /// # actix_rt::Runtime::new().unwrap().block_on(async {
+    /// # let engine = Configuration {
+    /// #     connection_string: "sqlite::memory:".into(),
+    /// #     parts: None,
+    /// #     slow_threshold: None,
+    /// #     acquire_threshold: None,
+    /// # }.engine().await.unwrap();
+    ///
+    /// #[entity(Rocks)]
+    /// #[derive(Clone, Debug, PartialEq)]
+    /// pub struct Rock {
+    ///     pub name: String,
+    ///     pub weight: u32,
+    /// }
+    ///
+    /// let mut connection = engine.connection().await.unwrap();
+    /// # connection.execute(r#"
+    /// #     CREATE TABLE Rocks (
+    /// #         name TEXT NOT NULL PRIMARY KEY,
+    /// #         weight INTEGER NOT NULL
+    /// #     );
+    /// # "#).await.unwrap();
+    /// let before = weru_database::cached_statements(&connection);
+    /// weru_database::prepare::<Rock>(&mut connection).await.unwrap();
+    /// assert_eq!(5, weru_database::cached_statements(&connection) - before);
+    ///
+    /// // Preparing again reuses the cached statements
+    /// weru_database::prepare::<Rock>(&mut connection).await.unwrap();
+    /// assert_eq!(5, weru_database::cached_statements(&connection) - before);
+    /// # });
+    /// ```
+    ///
+    /// Many entities can be inserted at once:
+    ///
+    /// ```
+    /// # use weru::database::entity;
+    /// # use weru_database::{Configuration, Engine, Entity};
+    /// # use weru_database::sqlx::prelude::*;
+    /// # actix_rt::Runtime::new().unwrap().block_on(async {
+    /// # let engine = Configuration {
+    /// #     connection_string: "sqlite::memory:".into(),
+    /// #     parts: None,
+    /// #     slow_threshold: None,
+    /// #     acquire_threshold: None,
+    /// # }.engine().await.unwrap();
+    ///
+    /// #[entity(Trees)]
+    /// #[derive(Clone, Debug, PartialEq)]
+    /// pub struct Tree {
+    ///     pub id: u32,
+    ///     pub name: String,
     /// }
+    ///
+    /// let mut connection = engine.connection().await.unwrap();
+    /// # connection.execute(r#"
+    /// #     CREATE TABLE Trees (
+    /// #         id INTEGER NOT NULL PRIMARY KEY,
+    /// #         name TEXT NOT NULL
+    /// #     );
+    /// # "#).await.unwrap();
+    /// let trees = (0..20_000)
+    ///     .map(|id| Tree::new(id, format!("Tree {}", id)))
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(0, Tree::create_many(&mut *connection, &[]).await.unwrap());
+    /// assert_eq!(
+    ///     20_000,
+    ///     Tree::create_many(&mut *connection, &trees).await.unwrap(),
+    /// );
+    /// assert_eq!(
+    ///     Some(trees[19_999].clone()),
+    ///     Tree::read(&mut *connection, &19_999).await.unwrap(),
+    /// );
+    ///
+    /// // Reading many entities returns them in no particular order
+    /// assert_eq!(
+    ///     Vec::<Tree>::new(),
+    ///     Tree::read_many(&mut *connection, &[]).await.unwrap(),
+    /// );
+    /// let mut read = Tree::read_many(&mut *connection, &[7, 3, 20_000])
+    ///     .await
+    ///     .unwrap();
+    /// read.sort_by_key(|tree| tree.id);
+    /// assert_eq!(vec![trees[3].clone(), trees[7].clone()], read);
+    /// # });
+    /// ```
+    ///
+    /// To change an entity based on its current value without racing other
+    /// transactions, read it with `read_for_update`. This locks the row until
+    /// the transaction ends on _MySQL_ and _PostgreSQL_; _SQLite_ has no row
+    /// locks, so there it is a plain read:
+    ///
+    /// ```
+    /// # use weru::database::entity;
+    /// # use weru_database::{Configuration, Engine, Entity};
+    /// # use weru_database::sqlx::prelude::*;
+    /// # actix_rt::Runtime::new().unwrap().block_on(async {
+    /// # let engine = Configuration {
+    /// #     connection_string: "sqlite::memory:".into(),
+    /// #     parts: None,
+    /// #     slow_threshold: None,
+    /// #     acquire_threshold: None,
+    /// # }.engine().await.unwrap();
+    ///
+    /// #[entity(Stock)]
+    /// #[derive(Clone, Debug, PartialEq)]
+    /// pub struct Item {
+    ///     pub name: String,
+    ///     pub count: u32,
+    /// }
+    ///
+    /// let mut connection = engine.connection().await.unwrap();
+    /// # connection.execute(r#"
+    /// #     CREATE TABLE Stock (
+    /// #         name TEXT NOT NULL PRIMARY KEY,
+    /// #         count INTEGER NOT NULL
+    /// #     );
+    /// # "#).await.unwrap();
+    /// Item::new("bolt".into(), 1)
+    ///     .create(&mut *connection)
+    ///     .await
+    ///     .unwrap();
+    ///
+    /// let mut tx = connection.begin().await.unwrap();
+    /// let mut item = Item::read_for_update(&mut tx, &"bolt".into())
+    ///     .await
+    ///     .unwrap()
+    ///     .unwrap();
+    /// if item.count > 0 {
+    ///     item.count -= 1;
+    ///     item.update(&mut *tx).await.unwrap();
+    /// }
+    /// tx.commit().await.unwrap();
+    ///
+    /// assert_eq!(
+    ///     Some(Item::new("bolt".into(), 0)),
+    ///     Item::read(&mut *connection, &"bolt".into()).await.unwrap(),
+    /// );
+    /// # });
+    /// ```
+    ///
+    /// Entities may also be stored in tables named at runtime. Like the table
+    /// name of the macro, these names are quoted, so they are case sensitive
+    /// on backends with case sensitive quoted identifiers:
+    ///
+    /// ```
+    /// # use weru::database::entity;
+    /// # use weru_database::{Configuration, Engine, Entity};
+    /// # use weru_database::sqlx::prelude::*;
+    /// # actix_rt::Runtime::new().unwrap().block_on(async {
+    /// # let engine = Configuration {
+    /// #     connection_string: "sqlite::memory:".into(),
+    /// #     parts: None,
+    /// #     slow_threshold: None,
+    /// #     acquire_threshold: None,
+    /// # }.engine().await.unwrap();
+    ///
+    /// #[entity(Birds)]
+    /// #[derive(Clone, Debug, PartialEq)]
+    /// pub struct Bird {
+    ///     pub name: String,
+    ///     pub species: String,
+    /// }
+    ///
+    /// let mut connection = engine.connection().await.unwrap();
+    /// # connection.execute(r#"
+    /// #     CREATE TABLE TenantBirds (
+    /// #         name TEXT NOT NULL PRIMARY KEY,
+    /// #         species TEXT NOT NULL
+    /// #     );
+    /// # "#).await.unwrap();
+    /// let table = "TenantBirds";
+    /// let bird = Bird::new("Tweety".into(), "Canary".into());
+    /// bird.create_in(&mut *connection, table).await.unwrap();
+    /// let renamed = bird.clone().merge(BirdDescription {
+    ///     species: Some("Finch".into()),
+    /// });
+    /// renamed.update_in(&mut *connection, table).await.unwrap();
+    /// let recreated = Bird::read_in(&mut *connection, table, &"Tweety".into())
+    ///     .await
+    ///     .unwrap()
+    ///     .unwrap();
+    /// assert_eq!(renamed, recreated);
+    /// recreated.delete_in(&mut *connection, table).await.unwrap();
+    /// assert_eq!(
+    ///     None,
+    ///     Bird::read_in(&mut *connection, table, &"Tweety".into())
+    ///         .await
+    ///         .unwrap(),
+    /// );
+    /// # });
+    /// ```
+    ///
+    /// Read-only entities need no description:
+    ///
+    /// ```
+    /// # use weru::database::entity;
+    /// # use weru_database::{Configuration, Engine, Entity};
+    /// # use weru_database::sqlx::prelude::*;
+    /// # actix_rt::Runtime::new().unwrap().block_on(async {
+    /// # let engine = Configuration {
+    /// #     connection_string: "sqlite::memory:".into(),
+    /// #     parts: None,
+    /// #     slow_threshold: None,
+    /// #     acquire_threshold: None,
+    /// # }.engine().await.unwrap();
+    ///
+    /// #[entity(Planets, no_description)]
+    /// #[derive(Clone, Debug, PartialEq)]
+    /// pub struct Planet {
+    ///     pub name: String,
+    ///     pub moons: i64,
+    /// }
+    ///
+    /// let mut connection = engine.connection().await.unwrap();
+    /// # connection.execute(r#"
+    /// #     CREATE TABLE Planets (
+    /// #         name TEXT NOT NULL PRIMARY KEY,
+    /// #         moons INTEGER NOT NULL
+    /// #     );
+    /// #     INSERT INTO Planets (name, moons) VALUES ('Earth', 1);
+    /// # "#).await.unwrap();
+    /// let earth = Planet::read(&mut *connection, &"Earth".into())
+    ///     .await
+    ///     .unwrap()
+    ///     .unwrap();
+    /// assert_eq!(Planet::new("Earth".into(), 1), earth);
+    /// assert_eq!(earth.clone(), earth.clone().merge(()));
     /// # });
     /// ```
     pub use weru_macros::database_entity as entity;
+
+    /// Derives database entities.
+    ///
+    /// This is an alternative to the [`entity`] attribute macro that reads
+    /// the `struct` without rewriting it, so it combines with other derives
+    /// and field attributes. The generated description, constructor and
+    /// [`Entity`](weru_database::Entity) implementation are the same.
+    ///
+    /// The table is named with `#[entity(table = "...")]` on the `struct`,
+    /// optionally followed by `hooks` as in `#[entity(table = "Pets", hooks)]`
+    /// and `no_description` as for the attribute macro.
+    /// Fields may be annotated with `#[entity(...)]` taking these arguments:
+    ///
+    /// *  `key` - Marks the unique primary key; if no field is marked, the
+    ///    first field is the key.
+    /// *  `column = "..."` - Names the column, which defaults to the field
+    ///    name.
+    /// *  `json` and `raw` - Like `#[json]` and `#[raw]` for the attribute
+    ///    macro.
+    /// *  `index` and `index(unique)` - Like `#[index]` and `#[index(unique)]`
+    ///    for the attribute macro.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use weru_database::{Configuration, Engine, Entity};
+    /// # use weru_database::sqlx::prelude::*;
+    /// # actix_rt::Runtime::new().unwrap().block_on(async {
+    /// # let engine = Configuration {
+    /// #     connection_string: "sqlite::memory:".into(),
+    /// #     parts: None,
+    /// #     slow_threshold: None,
+    /// #     acquire_threshold: None,
+    /// # }.engine().await.unwrap();
+    ///
+    /// #[derive(Clone, Debug, PartialEq, weru::database::Entity)]
+    /// #[entity(table = "Books")]
+    /// pub struct Book {
+    ///     #[entity(column = "book_title", index)]
+    ///     pub title: String,
+    ///     #[entity(key)]
+    ///     pub isbn: String,
+    ///     #[entity(json)]
+    ///     pub authors: Vec<String>,
+    /// }
+    ///
+    /// let mut connection = engine.connection().await.unwrap();
+    /// # connection.execute(r#"
+    /// #     CREATE TABLE Books (
+    /// #         isbn TEXT NOT NULL PRIMARY KEY,
+    /// #         book_title TEXT NOT NULL,
+    /// #         authors TEXT NOT NULL
+    /// #     );
+    /// # "#).await.unwrap();
+    /// Book::ensure_indexes(&mut *connection).await.unwrap();
+    /// let book = Book::new(
+    ///     "978-0261103573".into(),
+    ///     "The Fellowship of the Ring".into(),
+    ///     vec!["J. R. R. Tolkien".into()],
+    /// );
+    /// book.create(&mut *connection).await.unwrap();
+    /// let renamed = book.clone().merge(BookDescription {
+    ///     title: Some("The Lord of the Rings".into()),
+    ///     ..Default::default()
+    /// });
+    /// renamed.update(&mut *connection).await.unwrap();
+    /// assert_eq!(
+    ///     Some(renamed),
+    ///     Book::read(&mut *connection, &"978-0261103573".into())
+    ///         .await
+    ///         .unwrap(),
+    /// );
+    /// # });
+    /// ```
+    pub use weru_macros::Entity;
 }
 
 #[cfg(feature = "email")]
@@ -321,12 +1160,14 @@ pub mod email {
     //! // You would normally load this value from a file.
     //! let configuration = Configuration {
     //!     from: "Test Sender <test@email.test>".parse().unwrap(),
+    //!     from_names: Default::default(),
     //!     templates: configuration::Templates {
     //!         default_language: "l1".into(),
     //!         path: "../crates/email/resources/test/email/template/valid.toml"
     //!             .into(),
     //!     },
     //!     transport: Transport::Drop(drop::Configuration),
+    //!     retry: None,
     //! };
     //!
     //! // Create an e-mail engine from the configuration...
@@ -347,6 +1188,18 @@ pub mod email {
     //!     &"t1".into(),
     //!     &replacements,
     //! ).await.unwrap();
+    //!
+    //! // ...or build the e-mail one option at a time
+    //! sender
+    //!     .message()
+    //!     .to("Recipient 1 <recipient1@email.test>".parse().unwrap())
+    //!     .cc("Recipient 2 <recipient2@email.test>".parse().unwrap())
+    //!     .languages(["en-GB".into()])
+    //!     .template("t1")
+    //!     .replace("replace", "Hello!")
+    //!     .send()
+    //!     .await
+    //!     .unwrap();
     //! # });
     //! ```
     pub use weru_email::*;
@@ -371,7 +1224,14 @@ pub mod session {
     //!        e464c40145589aad0a25331fdc835dbd1a442e53b1604c1bf1665671a478324d\
     //!        f42ca55f76ef4f5c5e25e6ca18438566ca6fff5cefcc83a0042157df9dee4521"
     //!        .parse().unwrap(),
+    //!    previous_secrets: Vec::new(),
+    //!    encoding: Default::default(),
     //!    name: "cookie-name".into(),
+    //!    secure: None,
+    //!    same_site: None,
+    //!    partitioned: false,
+    //!    ttl: 24 * 60 * 60,
+    //!    expiration: Default::default(),
     //! });
     //!
     //! // Create the cookie store